@@ -0,0 +1,76 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Prints the ACPI tables in hex, like `acpidump(8)`.
+
+#![no_std]
+#![deny(unsafe_op_in_unsafe_fn)]
+
+extern crate alloc;
+
+use {
+    alloc::{string::String, vec},
+    ralib::println,
+};
+
+/// The tables to print. `FACP` is the FADT, and `APIC` is the MADT.
+const SIGNATURES: [&[u8; 4]; 6] = [b"FACP", b"APIC", b"HPET", b"MCFG", b"DMAR", b"DSDT"];
+
+const BYTES_PER_LINE: usize = 16;
+
+#[no_mangle]
+pub extern "C" fn main() {
+    ralib::init();
+    raheap::init();
+
+    for signature in SIGNATURES {
+        dump(*signature);
+    }
+
+    syscalls::exit();
+}
+
+fn dump(signature: [u8; 4]) {
+    let Some(len) = syscalls::acpi_table(signature, &mut []) else {
+        return;
+    };
+
+    let mut table = vec![0; len];
+    let _ = syscalls::acpi_table(signature, &mut table);
+
+    println!("{} ({} bytes)", printable(&signature), len);
+
+    for (i, line) in table.chunks(BYTES_PER_LINE).enumerate() {
+        let mut hex = [0; BYTES_PER_LINE * 3];
+
+        for (b, h) in line.iter().zip(hex.chunks_mut(3)) {
+            h.copy_from_slice(&[b' ', hex_digit(b >> 4), hex_digit(b & 0xf)]);
+        }
+
+        println!(
+            "    {:04X}:{:<48}  {}",
+            i * BYTES_PER_LINE,
+            printable(&hex[..line.len() * 3]),
+            printable(line)
+        );
+    }
+
+    println!();
+}
+
+fn hex_digit(n: u8) -> u8 {
+    b"0123456789ABCDEF"[usize::from(n)]
+}
+
+/// Converts `bytes` to a string, replacing unprintable characters with `.`.
+fn printable(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|&b| {
+            if b.is_ascii_graphic() || b == b' ' {
+                char::from(b)
+            } else {
+                '.'
+            }
+        })
+        .collect()
+}
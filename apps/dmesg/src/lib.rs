@@ -0,0 +1,42 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Prints the kernel log, like `dmesg(1)`.
+
+#![no_std]
+#![deny(unsafe_op_in_unsafe_fn)]
+
+extern crate alloc;
+
+use {alloc::vec, ralib::println, syscalls::LogRecord};
+
+/// The number of the records read at once.
+const RECORDS_PER_READ: usize = 16;
+
+#[no_mangle]
+pub extern "C" fn main() {
+    ralib::init();
+    raheap::init();
+
+    print_kernel_log();
+
+    syscalls::exit();
+}
+
+fn print_kernel_log() {
+    let mut records = vec![LogRecord::default(); RECORDS_PER_READ];
+    let mut from = 0;
+
+    loop {
+        let n = syscalls::read_kernel_log(from, &mut records);
+
+        if n == 0 {
+            break;
+        }
+
+        for r in &records[..n] {
+            println!("{}", r);
+
+            from = r.sequence() + 1;
+        }
+    }
+}
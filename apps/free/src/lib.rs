@@ -0,0 +1,74 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Prints the memory usage of the system and each process, like `free(1)`.
+
+#![no_std]
+#![deny(unsafe_op_in_unsafe_fn)]
+
+extern crate alloc;
+
+use {
+    alloc::vec,
+    os_units::NumOfPages,
+    ralib::{print, println},
+    syscalls::ProcessMemoryStats,
+    x86_64::structures::paging::{PageSize, Size4KiB},
+};
+
+#[no_mangle]
+pub extern "C" fn main() {
+    ralib::init();
+    raheap::init();
+
+    if print_memory_stats().is_none() {
+        println!("Failed to get the memory statistics.");
+    }
+
+    syscalls::exit();
+}
+
+fn print_memory_stats() -> Option<()> {
+    let mut processes = vec![ProcessMemoryStats::default(); 16];
+    let (stats, n) = syscalls::memory_stats(&mut processes)?;
+
+    if n > processes.len() {
+        processes.resize(n, ProcessMemoryStats::default());
+
+        let (_, n) = syscalls::memory_stats(&mut processes)?;
+        processes.truncate(n);
+    } else {
+        processes.truncate(n);
+    }
+
+    println!(
+        "{:>10} {:>12} {:>12} {:>12} {:>12}",
+        "", "total", "used", "free", "reserved"
+    );
+    println!(
+        "{:>10} {:>12} {:>12} {:>12} {:>12}",
+        "Mem (KiB):",
+        kib(stats.total()),
+        kib(stats.used()),
+        kib(stats.free()),
+        kib(stats.reserved())
+    );
+    println!("Largest free run: {} KiB", kib(stats.largest_free_run()));
+
+    println!();
+    println!("{:>5} {:<16} {:>12}", "PID", "NAME", "RSS (KiB)");
+
+    for p in &processes {
+        println!(
+            "{:>5} {:<16} {:>12}",
+            p.pid(),
+            p.name(),
+            kib(p.resident_pages())
+        );
+    }
+
+    Some(())
+}
+
+fn kib(n: NumOfPages<Size4KiB>) -> u64 {
+    n.as_usize() as u64 * Size4KiB::SIZE / 1024
+}
@@ -0,0 +1,58 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Launches the tools in the initrd typed on the console.
+//!
+//! Each line is the name of an executable, such as `acpidump` or `acpidump.bin`. The tool is
+//! spawned as a child of this process and runs concurrently with it, sharing the console.
+
+#![no_std]
+#![deny(unsafe_op_in_unsafe_fn)]
+
+extern crate alloc;
+
+use {
+    alloc::{format, string::String, vec::Vec},
+    ralib::println,
+};
+
+const STDIN: i32 = 0;
+
+#[no_mangle]
+pub extern "C" fn main() {
+    ralib::init();
+    raheap::init();
+
+    let mut line = Vec::new();
+    let mut buf = [0; 64];
+
+    while let Some(n @ 1..) = syscalls::read(STDIN, &mut buf) {
+        for &b in &buf[..n] {
+            if b == b'\n' {
+                run(&String::from_utf8_lossy(&line));
+                line.clear();
+            } else {
+                line.push(b);
+            }
+        }
+    }
+
+    syscalls::exit();
+}
+
+fn run(line: &str) {
+    let name = line.trim();
+
+    if name.is_empty() {
+        return;
+    }
+
+    let spawned = if name.ends_with(".bin") {
+        syscalls::spawn(name)
+    } else {
+        syscalls::spawn(&format!("{}.bin", name))
+    };
+
+    if spawned.is_none() {
+        println!("sh: {}: command not found", name);
+    }
+}
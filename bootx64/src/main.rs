@@ -46,7 +46,7 @@ pub fn efi_main(image: Handle, mut system_table: SystemTable<Boot>) -> uefi::Sta
     );
     let mem_map = bootx64::exit::boot_services(system_table);
 
-    let mut boot_info = boot_info::Info::new(entry_addr, vram_info, mem_map, rsdp);
+    let mut boot_info = boot_info::Info::new(entry_addr, vram_info, mem_map, rsdp, bytes_initrd);
 
     paging::init(&mut boot_info, &reserved_regions);
     jump::to_kernel(boot_info);
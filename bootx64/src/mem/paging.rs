@@ -58,7 +58,7 @@ fn enable_recursive_mapping() {
 
     p4[510].set_addr(
         get_pml4_addr(),
-        PageTableFlags::PRESENT | PageTableFlags::WRITABLE | PageTableFlags::USER_ACCESSIBLE,
+        PageTableFlags::PRESENT | PageTableFlags::WRITABLE,
     );
 }
 
@@ -82,8 +82,7 @@ fn map_virt_to_phys(region: &reserved::Range, allocator: &mut AllocatorWithEfiMe
         let p = PhysFrame::containing_address(
             region.phys() + usize::try_from(Size4KiB::SIZE).unwrap() * i,
         );
-        let f =
-            PageTableFlags::PRESENT | PageTableFlags::WRITABLE | PageTableFlags::USER_ACCESSIBLE;
+        let f = PageTableFlags::PRESENT | PageTableFlags::WRITABLE;
         unsafe { p4.map_to(v, p, f, allocator) }.unwrap().flush();
     }
 }
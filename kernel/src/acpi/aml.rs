@@ -0,0 +1,256 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! The AML namespace built from the DSDT and the SSDTs.
+
+use {
+    super::pci,
+    crate::{
+        interrupt::timer,
+        mem::{accessor, allocator},
+    },
+    acpi::{AcpiHandler, AcpiTables, AmlTable},
+    alloc::{boxed::Box, vec::Vec},
+    aml::{value::Args, AmlContext, AmlError, AmlName, AmlType, AmlValue, DebugVerbosity},
+    conquer_once::spin::OnceCell,
+    core::{convert::TryInto, ops::DerefMut, slice, str::FromStr, time::Duration},
+    log::warn,
+    spinning_top::Spinlock,
+    x86_64::{instructions::port::Port, PhysAddr},
+};
+
+static CONTEXT: OnceCell<Spinlock<AmlContext>> = OnceCell::uninit();
+
+pub(super) fn init(table: &AcpiTables<allocator::acpi::Mapper>) {
+    let mut context = AmlContext::new(Box::new(Handler), DebugVerbosity::None);
+
+    for t in table.dsdt.iter().chain(&table.ssdts) {
+        if let Err(e) = parse(&mut context, t) {
+            warn!("Failed to parse an AML table: {:?}", e);
+        }
+    }
+
+    if let Err(e) = context.initialize_objects() {
+        warn!("Failed to initialize the AML objects: {:?}", e);
+    }
+
+    CONTEXT.init_once(|| Spinlock::new(context));
+}
+
+/// Evaluates the object `name` in `scope`.
+///
+/// The value is returned as is if the object is not a method.
+pub(super) fn evaluate(
+    context: &mut AmlContext,
+    scope: &AmlName,
+    name: &str,
+) -> Result<AmlValue, AmlError> {
+    let path = AmlName::from_str(name)?.resolve(scope)?;
+
+    context.invoke_method(&path, Args::EMPTY)
+}
+
+/// Returns the elements of the package at `path` as integers.
+pub(super) fn integers_in_package(path: &str) -> Result<Vec<u64>, AmlError> {
+    let name = AmlName::from_str(path)?;
+    let context = lock();
+
+    match context.namespace.get_by_path(&name)? {
+        AmlValue::Package(elements) => elements.iter().map(|e| e.as_integer(&*context)).collect(),
+        v => Err(AmlError::IncompatibleValueConversion {
+            current: v.type_of(),
+            target: AmlType::Package,
+        }),
+    }
+}
+
+fn parse(context: &mut AmlContext, table: &AmlTable) -> Result<(), AmlError> {
+    let len: usize = table.length.try_into().unwrap();
+
+    // SAFETY: The address and the length are read from the ACPI tables.
+    let mapping = unsafe { allocator::acpi::Mapper.map_physical_region::<u8>(table.address, len) };
+
+    // SAFETY: The region is mapped for `len` bytes, and the mapping lives until the end of this
+    // function.
+    let stream = unsafe { slice::from_raw_parts(mapping.virtual_start().as_ptr(), len) };
+
+    context.parse_table(stream)
+}
+
+pub(super) fn lock() -> impl DerefMut<Target = AmlContext> {
+    CONTEXT
+        .try_get()
+        .expect("The AML context is not initialized.")
+        .try_lock()
+        .expect("Failed to lock the AML context.")
+}
+
+/// Accesses the hardware on behalf of the AML code.
+struct Handler;
+impl Handler {
+    fn read_memory<T: Copy>(address: usize) -> T {
+        // SAFETY: The firmware specifies the address.
+        let a: accessor::Single<T> =
+            unsafe { accessor::new(PhysAddr::new(address.try_into().unwrap())) };
+
+        a.read_volatile()
+    }
+
+    fn write_memory<T: Copy>(address: usize, value: T) {
+        // SAFETY: The firmware specifies the address.
+        let mut a: accessor::Single<T> =
+            unsafe { accessor::new(PhysAddr::new(address.try_into().unwrap())) };
+
+        a.write_volatile(value);
+    }
+
+    /// Returns `false` and warns if `segment` is not the PCI segment 0, which is the only
+    /// supported one. Reading an unsupported segment returns all ones as a nonexistent device
+    /// does, and writing to it is ignored.
+    fn segment_supported(segment: u16) -> bool {
+        if segment != 0 {
+            warn!(
+                "Only the PCI segment 0 is supported, but {} is accessed.",
+                segment
+            );
+        }
+
+        segment == 0
+    }
+}
+impl aml::Handler for Handler {
+    fn read_u8(&self, address: usize) -> u8 {
+        Self::read_memory(address)
+    }
+
+    fn read_u16(&self, address: usize) -> u16 {
+        Self::read_memory(address)
+    }
+
+    fn read_u32(&self, address: usize) -> u32 {
+        Self::read_memory(address)
+    }
+
+    fn read_u64(&self, address: usize) -> u64 {
+        Self::read_memory(address)
+    }
+
+    fn write_u8(&mut self, address: usize, value: u8) {
+        Self::write_memory(address, value);
+    }
+
+    fn write_u16(&mut self, address: usize, value: u16) {
+        Self::write_memory(address, value);
+    }
+
+    fn write_u32(&mut self, address: usize, value: u32) {
+        Self::write_memory(address, value);
+    }
+
+    fn write_u64(&mut self, address: usize, value: u64) {
+        Self::write_memory(address, value);
+    }
+
+    fn read_io_u8(&self, port: u16) -> u8 {
+        // SAFETY: The firmware specifies the port.
+        unsafe { Port::new(port).read() }
+    }
+
+    fn read_io_u16(&self, port: u16) -> u16 {
+        // SAFETY: The firmware specifies the port.
+        unsafe { Port::new(port).read() }
+    }
+
+    fn read_io_u32(&self, port: u16) -> u32 {
+        // SAFETY: The firmware specifies the port.
+        unsafe { Port::new(port).read() }
+    }
+
+    fn write_io_u8(&self, port: u16, value: u8) {
+        // SAFETY: The firmware specifies the port.
+        unsafe { Port::new(port).write(value) };
+    }
+
+    fn write_io_u16(&self, port: u16, value: u16) {
+        // SAFETY: The firmware specifies the port.
+        unsafe { Port::new(port).write(value) };
+    }
+
+    fn write_io_u32(&self, port: u16, value: u32) {
+        // SAFETY: The firmware specifies the port.
+        unsafe { Port::new(port).write(value) };
+    }
+
+    fn read_pci_u8(&self, segment: u16, bus: u8, device: u8, function: u8, offset: u16) -> u8 {
+        if Self::segment_supported(segment) {
+            pci::read(bus, device, function, offset)
+        } else {
+            u8::MAX
+        }
+    }
+
+    fn read_pci_u16(&self, segment: u16, bus: u8, device: u8, function: u8, offset: u16) -> u16 {
+        if Self::segment_supported(segment) {
+            pci::read(bus, device, function, offset)
+        } else {
+            u16::MAX
+        }
+    }
+
+    fn read_pci_u32(&self, segment: u16, bus: u8, device: u8, function: u8, offset: u16) -> u32 {
+        if Self::segment_supported(segment) {
+            pci::read(bus, device, function, offset)
+        } else {
+            u32::MAX
+        }
+    }
+
+    fn write_pci_u8(
+        &self,
+        segment: u16,
+        bus: u8,
+        device: u8,
+        function: u8,
+        offset: u16,
+        value: u8,
+    ) {
+        if Self::segment_supported(segment) {
+            pci::write(bus, device, function, offset, value);
+        }
+    }
+
+    fn write_pci_u16(
+        &self,
+        segment: u16,
+        bus: u8,
+        device: u8,
+        function: u8,
+        offset: u16,
+        value: u16,
+    ) {
+        if Self::segment_supported(segment) {
+            pci::write(bus, device, function, offset, value);
+        }
+    }
+
+    fn write_pci_u32(
+        &self,
+        segment: u16,
+        bus: u8,
+        device: u8,
+        function: u8,
+        offset: u16,
+        value: u32,
+    ) {
+        if Self::segment_supported(segment) {
+            pci::write(bus, device, function, offset, value);
+        }
+    }
+
+    fn stall(&self, microseconds: u64) {
+        timer::busy_wait(Duration::from_micros(microseconds));
+    }
+
+    fn sleep(&self, milliseconds: u64) {
+        timer::busy_wait(Duration::from_millis(milliseconds));
+    }
+}
@@ -0,0 +1,222 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! The devices in the AML namespace.
+//!
+//! The devices are enumerated once at boot, and the user-space drivers query them through the
+//! system process.
+
+use {
+    super::aml,
+    ::aml::{
+        namespace::LevelType,
+        pci_routing::{PciRoutingTable, Pin},
+        resource::{
+            self, AddressSpaceResourceType, InterruptPolarity, InterruptTrigger, IrqDescriptor,
+            MemoryRangeDescriptor, Resource,
+        },
+        AmlContext, AmlError, AmlName, AmlValue,
+    },
+    alloc::vec::Vec,
+    bit_field::BitField,
+    conquer_once::spin::OnceCell,
+    core::{
+        convert::{TryFrom, TryInto},
+        str::FromStr,
+    },
+    log::{info, warn},
+    syscalls::{AcpiHid, AcpiIrq, AcpiResource},
+};
+
+/// The `_HID`s of the PCI and PCI Express root bridges.
+const PCI_ROOT_BRIDGES: [&str; 2] = ["PNP0A03", "PNP0A08"];
+
+static DEVICES: OnceCell<Vec<Device>> = OnceCell::uninit();
+static PCI_ROUTING: OnceCell<PciRoutingTable> = OnceCell::uninit();
+
+pub(super) fn init() {
+    let mut context = aml::lock();
+
+    let devices = enumerate(&mut context).unwrap_or_else(|e| {
+        warn!("Failed to enumerate the ACPI devices: {:?}", e);
+        Vec::new()
+    });
+
+    info!("ACPI: {} devices", devices.len());
+
+    if let Some(bridge) = devices.iter().find(|d| d.is_pci_root_bridge()) {
+        match routing_table(&mut context, &bridge.path) {
+            Ok(t) => PCI_ROUTING.init_once(|| t),
+            Err(e) => warn!("Failed to read `_PRT` of {:?}: {:?}", bridge.path, e),
+        }
+    }
+
+    DEVICES.init_once(|| devices);
+}
+
+/// Returns the `_HID` of the `index`-th device.
+pub(crate) fn hid(index: usize) -> Option<AcpiHid> {
+    devices().get(index).map(|d| d.hid)
+}
+
+/// Returns the `index`-th resource of the `device`-th device.
+pub(crate) fn resource(device: usize, index: usize) -> Option<AcpiResource> {
+    devices().get(device)?.resources.get(index).copied()
+}
+
+/// Returns the interrupt which the interrupt `pin` of the PCI `device` on the bus 0 is connected
+/// to. `pin` is 1 for INTA#.
+pub(crate) fn route_pci_interrupt(device: u8, pin: u8) -> Option<AcpiIrq> {
+    let pin = match pin {
+        1 => Pin::IntA,
+        2 => Pin::IntB,
+        3 => Pin::IntC,
+        4 => Pin::IntD,
+        _ => return None,
+    };
+
+    let table = PCI_ROUTING.get()?;
+
+    let irq = table.route(device.into(), 0, pin, &mut aml::lock()).ok()?;
+
+    Some(convert_irq(&irq))
+}
+
+fn devices() -> &'static [Device] {
+    DEVICES.get().map_or(&[], Vec::as_slice)
+}
+
+fn enumerate(context: &mut AmlContext) -> Result<Vec<Device>, AmlError> {
+    let mut paths = Vec::new();
+
+    context.namespace.traverse(|name, level| {
+        if let LevelType::Device = level.typ {
+            paths.push(name.clone());
+        }
+
+        Ok(true)
+    })?;
+
+    let mut devices = Vec::new();
+
+    for path in paths {
+        if !is_present(context, &path) {
+            continue;
+        }
+
+        let Ok(hid) = aml::evaluate(context, &path, "_HID") else {
+            continue;
+        };
+
+        let Some(hid) = convert_hid(&hid) else {
+            warn!("Invalid `_HID` of {:?}: {:?}", path, hid);
+            continue;
+        };
+
+        let resources = aml::evaluate(context, &path, "_CRS")
+            .and_then(|crs| resource::resource_descriptor_list(&crs))
+            .map(|r| r.iter().filter_map(convert_resource).collect())
+            .unwrap_or_default();
+
+        devices.push(Device {
+            path,
+            hid,
+            resources,
+        });
+    }
+
+    Ok(devices)
+}
+
+/// Returns `false` if `_STA` of the device says that the device is not present.
+fn is_present(context: &mut AmlContext, path: &AmlName) -> bool {
+    const PRESENT: usize = 0;
+
+    match aml::evaluate(context, path, "_STA") {
+        Ok(sta) => sta.as_integer(context).map_or(true, |s| s.get_bit(PRESENT)),
+        Err(_) => true,
+    }
+}
+
+fn routing_table(context: &mut AmlContext, bridge: &AmlName) -> Result<PciRoutingTable, AmlError> {
+    let path = AmlName::from_str("_PRT")?.resolve(bridge)?;
+
+    PciRoutingTable::from_prt_path(&path, context)
+}
+
+/// Converts the value of `_HID`, which is either a compressed EISA ID or a string.
+fn convert_hid(hid: &AmlValue) -> Option<AcpiHid> {
+    match hid {
+        AmlValue::Integer(id) => {
+            let id = u32::try_from(*id).ok()?.swap_bytes();
+
+            let mut s = [0; 7];
+
+            for (i, c) in s[..3].iter_mut().enumerate() {
+                let shift = 26 - 5 * i;
+                let c5: u8 = id.get_bits(shift..shift + 5).try_into().unwrap();
+
+                *c = b'@' + c5;
+            }
+
+            for (i, c) in s[3..].iter_mut().enumerate() {
+                let shift = 12 - 4 * i;
+                let digit: u8 = id.get_bits(shift..shift + 4).try_into().unwrap();
+
+                *c = b"0123456789ABCDEF"[usize::from(digit)];
+            }
+
+            AcpiHid::new(core::str::from_utf8(&s).ok()?)
+        }
+        AmlValue::String(s) => AcpiHid::new(s),
+        _ => None,
+    }
+}
+
+fn convert_resource(r: &Resource) -> Option<AcpiResource> {
+    match r {
+        Resource::Irq(irq) => Some(AcpiResource::Irq(convert_irq(irq))),
+        Resource::IOPort(io) => Some(AcpiResource::Io {
+            base: io.memory_range.0,
+            len: io.range_length.into(),
+        }),
+        Resource::MemoryRange(MemoryRangeDescriptor::FixedLocation {
+            base_address,
+            range_length,
+            ..
+        }) => Some(AcpiResource::Memory {
+            base: (*base_address).into(),
+            len: (*range_length).into(),
+        }),
+        Resource::AddressSpace(a) => match a.resource_type {
+            AddressSpaceResourceType::MemoryRange => Some(AcpiResource::Memory {
+                base: a.address_range.0,
+                len: a.length,
+            }),
+            AddressSpaceResourceType::IORange => Some(AcpiResource::Io {
+                base: a.address_range.0.try_into().ok()?,
+                len: a.length.try_into().ok()?,
+            }),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn convert_irq(irq: &IrqDescriptor) -> AcpiIrq {
+    AcpiIrq::new(
+        irq.irq,
+        matches!(irq.trigger, InterruptTrigger::Level),
+        matches!(irq.polarity, InterruptPolarity::ActiveLow),
+    )
+}
+
+struct Device {
+    path: AmlName,
+    hid: AcpiHid,
+    resources: Vec<AcpiResource>,
+}
+impl Device {
+    fn is_pci_root_bridge(&self) -> bool {
+        PCI_ROOT_BRIDGES.contains(&self.hid.as_str())
+    }
+}
@@ -1,5 +1,11 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+pub(crate) mod aml;
+pub(crate) mod device;
+mod pci;
+pub(crate) mod power;
+pub(crate) mod table;
+
 use core::convert::TryInto;
 
 use {acpi::AcpiTables, x86_64::PhysAddr};
@@ -14,3 +20,14 @@ pub(crate) unsafe fn get(rsdb: PhysAddr) -> AcpiTables<allocator::acpi::Mapper>
     // SAFETY: The caller must ensure that `rsdb` is the valid RSDB address.
     unsafe { AcpiTables::from_rsdp(mapper, rsdb.as_u64().try_into().unwrap()).unwrap() }
 }
+
+/// Loads the AML tables, enumerates the devices in them, reads the registers to power off and
+/// reboot the machine, and records the tables for the user-space processes.
+///
+/// The timer must be initialized before calling this function because the AML code may wait.
+pub(crate) fn init(table: &AcpiTables<allocator::acpi::Mapper>) {
+    aml::init(table);
+    device::init();
+    power::init(table);
+    table::init(table);
+}
@@ -0,0 +1,51 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! The legacy PCI configuration space access mechanism.
+//!
+//! Only the segment 0 is accessible with this mechanism.
+
+use {
+    core::convert::TryInto,
+    x86_64::instructions::port::{Port, PortRead, PortWrite},
+};
+
+const CONFIG_ADDRESS: u16 = 0xcf8;
+const CONFIG_DATA: u16 = 0xcfc;
+
+pub(super) fn read<T: PortRead>(bus: u8, device: u8, function: u8, offset: u16) -> T {
+    let port = select(bus, device, function, offset);
+
+    // SAFETY: `select` selects the register.
+    unsafe { Port::new(port).read() }
+}
+
+pub(super) fn write<T: PortWrite>(bus: u8, device: u8, function: u8, offset: u16, value: T) {
+    let port = select(bus, device, function, offset);
+
+    // SAFETY: `select` selects the register. The caller is the firmware or the kernel, which knows
+    // the register.
+    unsafe { Port::new(port).write(value) };
+}
+
+/// Selects the register and returns the port to access it.
+fn select(bus: u8, device: u8, function: u8, offset: u16) -> u16 {
+    assert!(device < 32, "Invalid device number: {}", device);
+    assert!(function < 8, "Invalid function number: {}", function);
+
+    let offset: u8 = offset
+        .try_into()
+        .expect("The offset is out of the legacy configuration space.");
+
+    let address = 1 << 31
+        | u32::from(bus) << 16
+        | u32::from(device) << 11
+        | u32::from(function) << 8
+        | u32::from(offset & !0b11);
+
+    let mut port = Port::new(CONFIG_ADDRESS);
+
+    // SAFETY: Writing to CONFIG_ADDRESS only selects the register.
+    unsafe { port.write(address) };
+
+    CONFIG_DATA + u16::from(offset & 0b11)
+}
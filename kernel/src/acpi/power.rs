@@ -0,0 +1,264 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Powering off and rebooting the machine.
+//!
+//! The registers described in the FADT are tried first. If they do not work, rebooting falls back
+//! to the keyboard controller and a triple fault.
+
+use {
+    super::{aml, pci},
+    crate::mem::{accessor, allocator},
+    acpi::{
+        fadt::Fadt,
+        platform::address::{AddressSpace, GenericAddress},
+        AcpiTables,
+    },
+    bit_field::BitField,
+    conquer_once::spin::OnceCell,
+    core::{
+        arch::asm,
+        convert::{TryFrom, TryInto},
+        hint,
+        ops::Range,
+    },
+    log::{info, warn},
+    x86_64::{
+        instructions::{
+            self, interrupts,
+            port::{Port, PortRead, PortWrite},
+            tables::{self, DescriptorTablePointer},
+        },
+        PhysAddr, VirtAddr,
+    },
+};
+
+static REGISTERS: OnceCell<Registers> = OnceCell::uninit();
+
+pub(super) fn init(table: &AcpiTables<allocator::acpi::Mapper>) {
+    match Registers::new(table) {
+        Ok(r) => REGISTERS.init_once(|| r),
+        Err(e) => warn!("Failed to read the FADT: {:?}", e),
+    }
+}
+
+/// Powers off the machine.
+///
+/// If the machine cannot enter the S5 state, this function halts the processor forever.
+pub(crate) fn shutdown() -> ! {
+    interrupts::disable();
+
+    info!("Powering off.");
+
+    if let Some(r) = REGISTERS.get() {
+        r.enter_s5();
+    }
+
+    warn!("Failed to power off. It is now safe to turn off the machine.");
+
+    loop {
+        instructions::hlt();
+    }
+}
+
+/// Reboots the machine.
+pub(crate) fn reboot() -> ! {
+    interrupts::disable();
+
+    info!("Rebooting.");
+
+    if let Some(r) = REGISTERS.get() {
+        r.reset();
+    }
+
+    reset_with_keyboard_controller();
+
+    triple_fault();
+}
+
+struct Registers {
+    pm1a_control: GenericAddress,
+    pm1b_control: Option<GenericAddress>,
+    /// SLP_TYPa and SLP_TYPb of the S5 state.
+    s5: Option<(u16, u16)>,
+    /// The reset register and the value to write to it.
+    reset: Option<(GenericAddress, u8)>,
+}
+impl Registers {
+    const SLP_TYP: Range<usize> = 10..13;
+    const SLP_EN: usize = 13;
+
+    fn new(table: &AcpiTables<allocator::acpi::Mapper>) -> Result<Self, acpi::AcpiError> {
+        let fadt = table.find_table::<Fadt>()?;
+
+        let s5 = s5_sleep_types();
+
+        let revision = fadt.header.revision;
+        let reset_value = fadt.reset_value;
+
+        // The reset register is only present since the revision 2.
+        let reset = (revision >= 2)
+            .then(|| fadt.reset_register().ok())
+            .flatten()
+            .filter(|r| r.address != 0)
+            .map(|r| (r, reset_value));
+
+        Ok(Self {
+            pm1a_control: fadt.pm1a_control_block()?,
+            pm1b_control: fadt.pm1b_control_block()?,
+            s5,
+            reset,
+        })
+    }
+
+    fn enter_s5(&self) {
+        if let Some((a, b)) = self.s5 {
+            let r = Self::write_sleep_type(&self.pm1a_control, a).and_then(|()| {
+                self.pm1b_control
+                    .as_ref()
+                    .map_or(Ok(()), |pm1b| Self::write_sleep_type(pm1b, b))
+            });
+
+            if let Err(e) = r {
+                warn!("Failed to enter the S5 state: {:?}", e);
+            }
+        }
+    }
+
+    fn write_sleep_type(control: &GenericAddress, ty: u16) -> Result<(), Error> {
+        let mut v: u16 = read(control)?;
+
+        v.set_bits(Self::SLP_TYP, ty);
+        v.set_bit(Self::SLP_EN, true);
+
+        write(control, v)
+    }
+
+    fn reset(&self) {
+        if let Some((register, value)) = &self.reset {
+            if let Err(e) = write(register, *value) {
+                warn!("Failed to write to the reset register: {:?}", e);
+            }
+        }
+    }
+}
+
+/// Returns SLP_TYPa and SLP_TYPb of the S5 state from the `\_S5` object.
+fn s5_sleep_types() -> Option<(u16, u16)> {
+    let types = match aml::integers_in_package("\\_S5") {
+        Ok(t) => t,
+        Err(e) => {
+            warn!("Failed to evaluate `\\_S5`: {:?}", e);
+            return None;
+        }
+    };
+
+    // Some firmwares omit SLP_TYPb.
+    let ty = |i: usize| {
+        types
+            .get(i)
+            .or_else(|| types.first())
+            .and_then(|&t| u16::try_from(t).ok())
+            .filter(|&t| t < 8)
+    };
+
+    let s5 = ty(0).zip(ty(1));
+
+    if s5.is_none() {
+        warn!("Invalid `\\_S5` object: {:?}", types);
+    }
+
+    s5
+}
+
+#[derive(Debug)]
+enum Error {
+    UnsupportedAddressSpace(AddressSpace),
+}
+
+fn read<T: Copy + PortRead>(register: &GenericAddress) -> Result<T, Error> {
+    match register.address_space {
+        AddressSpace::SystemIo => {
+            // SAFETY: The port is read from the FADT.
+            Ok(unsafe { Port::new(register.address.try_into().unwrap()).read() })
+        }
+        AddressSpace::SystemMemory => {
+            // SAFETY: The address is read from the FADT.
+            let a: accessor::Single<T> = unsafe { accessor::new(PhysAddr::new(register.address)) };
+
+            Ok(a.read_volatile())
+        }
+        AddressSpace::PciConfigSpace => {
+            let (device, function, offset) = pci_location(register.address);
+
+            Ok(pci::read(0, device, function, offset))
+        }
+        s => Err(Error::UnsupportedAddressSpace(s)),
+    }
+}
+
+fn write<T: Copy + PortWrite>(register: &GenericAddress, value: T) -> Result<(), Error> {
+    match register.address_space {
+        AddressSpace::SystemIo => {
+            // SAFETY: The port is read from the FADT.
+            unsafe { Port::new(register.address.try_into().unwrap()).write(value) };
+        }
+        AddressSpace::SystemMemory => {
+            // SAFETY: The address is read from the FADT.
+            let mut a: accessor::Single<T> =
+                unsafe { accessor::new(PhysAddr::new(register.address)) };
+
+            a.write_volatile(value);
+        }
+        AddressSpace::PciConfigSpace => {
+            let (device, function, offset) = pci_location(register.address);
+
+            pci::write(0, device, function, offset, value);
+        }
+        s => return Err(Error::UnsupportedAddressSpace(s)),
+    }
+
+    Ok(())
+}
+
+/// Splits the address of a register in the PCI configuration space into the device number, the
+/// function number, and the offset.
+fn pci_location(address: u64) -> (u8, u8, u16) {
+    (
+        address.get_bits(32..48).try_into().unwrap(),
+        address.get_bits(16..32).try_into().unwrap(),
+        address.get_bits(0..16).try_into().unwrap(),
+    )
+}
+
+fn reset_with_keyboard_controller() {
+    const INPUT_BUFFER_FULL: usize = 1;
+    const PULSE_RESET_LINE: u8 = 0xfe;
+
+    let mut port = Port::<u8>::new(0x64);
+
+    for _ in 0..0x1_0000 {
+        // SAFETY: Reading the status register of the keyboard controller has no side effect.
+        if !unsafe { port.read() }.get_bit(INPUT_BUFFER_FULL) {
+            break;
+        }
+
+        hint::spin_loop();
+    }
+
+    // SAFETY: The machine resets.
+    unsafe { port.write(PULSE_RESET_LINE) };
+}
+
+fn triple_fault() -> ! {
+    let idt = DescriptorTablePointer {
+        limit: 0,
+        base: VirtAddr::zero(),
+    };
+
+    // SAFETY: The breakpoint exception cannot be delivered with the empty IDT, and the processor
+    // resets on the resulting triple fault. Nothing runs after this.
+    unsafe {
+        tables::lidt(&idt);
+        asm!("int3", options(noreturn));
+    }
+}
@@ -0,0 +1,66 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! The raw ACPI tables for the user-space processes.
+
+use {
+    crate::mem::allocator,
+    acpi::{sdt::SdtHeader, AcpiHandler, AcpiTables},
+    alloc::collections::BTreeMap,
+    conquer_once::spin::OnceCell,
+    core::{convert::TryInto, mem, slice},
+};
+
+static TABLES: OnceCell<BTreeMap<[u8; 4], Region>> = OnceCell::uninit();
+
+pub(super) fn init(table: &AcpiTables<allocator::acpi::Mapper>) {
+    let mut tables = BTreeMap::new();
+
+    for (signature, sdt) in &table.sdts {
+        if let Ok(s) = signature.as_str().as_bytes().try_into() {
+            tables.insert(
+                s,
+                Region {
+                    start: sdt.physical_address,
+                    len: sdt.length.try_into().unwrap(),
+                },
+            );
+        }
+    }
+
+    // `AcpiTables` holds the DSDT without its header.
+    if let Some(dsdt) = &table.dsdt {
+        let header_len = mem::size_of::<SdtHeader>();
+        let len: usize = dsdt.length.try_into().unwrap();
+
+        tables.insert(
+            *b"DSDT",
+            Region {
+                start: dsdt.address - header_len,
+                len: len + header_len,
+            },
+        );
+    }
+
+    TABLES.init_once(|| tables);
+}
+
+/// Calls `f` with the table `signature`, including its header.
+///
+/// Returns [`None`] if there is no such table.
+pub(crate) fn with_table<T>(signature: [u8; 4], f: impl FnOnce(&[u8]) -> T) -> Option<T> {
+    let region = TABLES.get()?.get(&signature)?;
+
+    // SAFETY: The region is read from the ACPI tables.
+    let mapping =
+        unsafe { allocator::acpi::Mapper.map_physical_region::<u8>(region.start, region.len) };
+
+    // SAFETY: The region is mapped for `region.len` bytes while `mapping` lives.
+    let table = unsafe { slice::from_raw_parts(mapping.virtual_start().as_ptr(), region.len) };
+
+    Some(f(table))
+}
+
+struct Region {
+    start: usize,
+    len: usize,
+}
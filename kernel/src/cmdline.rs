@@ -0,0 +1,30 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Boot options written in the `cmdline` file of the initrd.
+//!
+//! The options are separated by whitespaces, e.g. `noaslr swap=2048,65536`.
+
+use {crate::fs, conquer_once::spin::Lazy, core::str, log::warn};
+
+static CMDLINE: Lazy<&'static str> = Lazy::new(load);
+
+/// Returns `true` if `option` is given.
+pub(crate) fn has(option: &str) -> bool {
+    CMDLINE.split_whitespace().any(|o| o == option)
+}
+
+/// Returns the value of the option in the form of `key=value`.
+pub(crate) fn value(key: &str) -> Option<&'static str> {
+    CMDLINE
+        .split_whitespace()
+        .find_map(|o| o.strip_prefix(key).and_then(|rest| rest.strip_prefix('=')))
+}
+
+fn load() -> &'static str {
+    fs::find("cmdline").map_or("", |f| {
+        str::from_utf8(f.content()).unwrap_or_else(|_| {
+            warn!("The command line is not valid UTF-8.");
+            ""
+        })
+    })
+}
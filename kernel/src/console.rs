@@ -0,0 +1,104 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! The console input from the USB keyboard and the serial port.
+//!
+//! The characters are collected into a line, which is logged and becomes readable from the
+//! standard input when a newline is input.
+
+use {
+    crate::{
+        interrupt::irq,
+        process::{scheduler, WaitQueue},
+    },
+    alloc::{collections::VecDeque, string::String, vec::Vec},
+    core::ops::DerefMut,
+    log::{info, warn},
+    spinning_top::Spinlock,
+    terminal::serial,
+};
+
+const BACKSPACE: char = '\u{8}';
+const DELETE: char = '\u{7f}';
+
+/// The maximum number of the bytes which are input but not read yet. The oldest bytes are
+/// discarded if no process reads the standard input.
+const INPUT_CAPACITY: usize = 4096;
+
+static CONSOLE: Spinlock<Console> = Spinlock::new(Console {
+    line: String::new(),
+    input: VecDeque::new(),
+    readers: WaitQueue::new(),
+});
+
+/// Starts receiving the input from the serial port.
+pub(crate) fn init() {
+    if !irq::register_kernel(serial::IRQ, receive_from_serial) {
+        warn!("Failed to route the IRQ of the serial port.");
+    }
+}
+
+/// Handles a character typed on the console.
+pub(crate) fn input(c: char) {
+    let mut console = lock();
+
+    match c {
+        '\r' | '\n' => console.complete_line(),
+        BACKSPACE | DELETE => {
+            console.line.pop();
+        }
+        c if !c.is_control() => console.line.push(c),
+        _ => {}
+    }
+}
+
+/// Reads at most `len` bytes of the completed lines, waiting until a line is input.
+pub(crate) fn read(len: usize) -> Vec<u8> {
+    loop {
+        let mut console = lock();
+
+        if !console.input.is_empty() {
+            let len = len.min(console.input.len());
+
+            return console.input.drain(..len).collect();
+        }
+
+        console.readers.add_current();
+
+        drop(console);
+
+        scheduler::block();
+    }
+}
+
+fn receive_from_serial() {
+    while let Some(b) = serial::try_receive() {
+        input(char::from(b));
+    }
+}
+
+fn lock() -> impl DerefMut<Target = Console> {
+    CONSOLE.try_lock().expect("Failed to lock the console.")
+}
+
+struct Console {
+    line: String,
+    /// The completed lines which are not read yet.
+    input: VecDeque<u8>,
+    /// The processes waiting for a line.
+    readers: WaitQueue,
+}
+impl Console {
+    fn complete_line(&mut self) {
+        info!("{}", self.line);
+
+        self.line.push('\n');
+
+        let overflow = (self.input.len() + self.line.len()).saturating_sub(INPUT_CAPACITY);
+        self.input.drain(..overflow.min(self.input.len()));
+        self.input.extend(self.line.bytes());
+
+        self.line.clear();
+
+        self.readers.wake_all();
+    }
+}
@@ -0,0 +1,492 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! The reader of cpio archives.
+//!
+//! The old portable format (`070707`), the new ASCII format (`070701`) and the new CRC format
+//! (`070702`) are supported. The format is detected for each entry.
+
+use {
+    super::Error,
+    core::{convert::TryInto, str},
+};
+
+const TRAILER: &str = "TRAILER!!!";
+
+#[derive(Debug)]
+pub(crate) struct CpioArchievedFile {
+    name: &'static str,
+    content: &'static [u8],
+    header: Header,
+}
+impl CpioArchievedFile {
+    /// The initrd is never unmapped, so the content lives as long as the kernel.
+    pub(crate) fn content(&self) -> &'static [u8] {
+        self.content
+    }
+
+    /// Returns the path of the file as it is written in the archive.
+    pub(crate) fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub(crate) fn mode(&self) -> u32 {
+        self.header.mode
+    }
+
+    pub(crate) fn uid(&self) -> u32 {
+        self.header.uid
+    }
+
+    /// The modification time in seconds since the Unix epoch.
+    pub(crate) fn mtime(&self) -> u64 {
+        self.header.mtime
+    }
+}
+
+/// Iterates over the files in an archive. The iteration stops after the first error.
+pub(super) struct Iter {
+    rest: &'static [u8],
+    finished: bool,
+}
+impl Iter {
+    pub(super) fn new(archive: &'static [u8]) -> Self {
+        Self {
+            rest: archive,
+            finished: false,
+        }
+    }
+
+    fn parse_next(&mut self) -> Result<Option<CpioArchievedFile>, Error> {
+        let format = Format::detect(self.rest)?;
+        let header = format.parse_header(self.rest)?;
+
+        let name_start = format.header_size();
+        let name_end = name_start + header.name_size;
+        let content_start = format.align(name_end);
+        let content_end = content_start + header.file_size;
+
+        let name = self
+            .rest
+            .get(name_start..name_end)
+            .ok_or(Error::Truncated)?;
+        let name = parse_name(name)?;
+
+        if name == TRAILER {
+            return Ok(None);
+        }
+
+        let content = self.rest.get(content_start..content_end);
+        let content = content.ok_or(Error::Truncated)?;
+
+        if format == Format::Crc && checksum(content) != header.check {
+            return Err(Error::ChecksumMismatch { name });
+        }
+
+        // The padding after the content may be missing if the archive is truncated. In that case,
+        // the next call returns `Error::Truncated`.
+        self.rest = self.rest.get(format.align(content_end)..).unwrap_or(&[]);
+
+        Ok(Some(CpioArchievedFile {
+            name,
+            content,
+            header,
+        }))
+    }
+}
+impl Iterator for Iter {
+    type Item = Result<CpioArchievedFile, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        let r = self.parse_next();
+
+        self.finished = !matches!(r, Ok(Some(_)));
+
+        r.transpose()
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Format {
+    /// The old portable format, whose fields are octal numbers.
+    Odc,
+    /// The new ASCII format, whose fields are hexadecimal numbers.
+    Newc,
+    /// The same as [`Format::Newc`], but with the checksum of the content.
+    Crc,
+}
+impl Format {
+    fn detect(archive: &[u8]) -> Result<Self, Error> {
+        let magic: [u8; 6] = archive
+            .get(..6)
+            .ok_or(Error::Truncated)?
+            .try_into()
+            .unwrap();
+
+        match &magic {
+            b"070707" => Ok(Self::Odc),
+            b"070701" => Ok(Self::Newc),
+            b"070702" => Ok(Self::Crc),
+            _ => Err(Error::InvalidMagic(magic)),
+        }
+    }
+
+    fn parse_header(self, archive: &[u8]) -> Result<Header, Error> {
+        let header = archive.get(..self.header_size()).ok_or(Error::Truncated)?;
+
+        match self {
+            Self::Odc => Header::parse_odc(header),
+            Self::Newc | Self::Crc => Header::parse_newc(header),
+        }
+    }
+
+    fn header_size(self) -> usize {
+        match self {
+            Self::Odc => 76,
+            Self::Newc | Self::Crc => 110,
+        }
+    }
+
+    /// Rounds up `offset` from the start of a header to the start of the next field.
+    ///
+    /// In the new formats, the name and the content start at 4-byte boundaries.
+    fn align(self, offset: usize) -> usize {
+        match self {
+            Self::Odc => offset,
+            Self::Newc | Self::Crc => (offset + 3) & !3,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Header {
+    mode: u32,
+    uid: u32,
+    mtime: u64,
+    name_size: usize,
+    file_size: usize,
+    check: u32,
+}
+impl Header {
+    fn parse_odc(header: &[u8]) -> Result<Self, Error> {
+        let mut fields = Fields::new(header, 8);
+
+        fields.skip(6 + 6 + 6);
+        let mode = fields.next(6)?;
+        let uid = fields.next(6)?;
+        fields.skip(6 + 6 + 6);
+        let mtime = fields.next(11)?;
+        let name_size = fields.next(6)?;
+        let file_size = fields.next(11)?;
+
+        Ok(Self {
+            mode: to_u32(mode)?,
+            uid: to_u32(uid)?,
+            mtime,
+            name_size: to_usize(name_size)?,
+            file_size: to_usize(file_size)?,
+            check: 0,
+        })
+    }
+
+    fn parse_newc(header: &[u8]) -> Result<Self, Error> {
+        let mut fields = Fields::new(header, 16);
+
+        fields.skip(6 + 8);
+        let mode = fields.next(8)?;
+        let uid = fields.next(8)?;
+        fields.skip(8 + 8);
+        let mtime = fields.next(8)?;
+        let file_size = fields.next(8)?;
+        fields.skip(8 * 4);
+        let name_size = fields.next(8)?;
+        let check = fields.next(8)?;
+
+        Ok(Self {
+            mode: to_u32(mode)?,
+            uid: to_u32(uid)?,
+            mtime,
+            name_size: to_usize(name_size)?,
+            file_size: to_usize(file_size)?,
+            check: to_u32(check)?,
+        })
+    }
+}
+
+/// The numeric fields of a header, which are ASCII numbers without any terminator.
+struct Fields<'a> {
+    bytes: &'a [u8],
+    radix: u32,
+}
+impl<'a> Fields<'a> {
+    fn new(bytes: &'a [u8], radix: u32) -> Self {
+        Self { bytes, radix }
+    }
+
+    fn skip(&mut self, len: usize) {
+        self.bytes = &self.bytes[len..];
+    }
+
+    fn next(&mut self, len: usize) -> Result<u64, Error> {
+        let (field, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+
+        let s = str::from_utf8(field).map_err(|_| Error::InvalidNumber)?;
+        u64::from_str_radix(s, self.radix).map_err(|_| Error::InvalidNumber)
+    }
+}
+
+/// Parses a name, which includes the terminating NUL.
+fn parse_name(name: &'static [u8]) -> Result<&'static str, Error> {
+    match name.split_last() {
+        Some((0, name)) => str::from_utf8(name).map_err(|_| Error::InvalidName),
+        _ => Err(Error::InvalidName),
+    }
+}
+
+/// The checksum of the new CRC format, which is the sum of the bytes of the content.
+fn checksum(content: &[u8]) -> u32 {
+    content
+        .iter()
+        .fold(0_u32, |sum, &b| sum.wrapping_add(b.into()))
+}
+
+fn to_u32(n: u64) -> Result<u32, Error> {
+    n.try_into().map_err(|_| Error::InvalidNumber)
+}
+
+fn to_usize(n: u64) -> Result<usize, Error> {
+    n.try_into().map_err(|_| Error::InvalidNumber)
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use {
+        super::{checksum, Error, Iter},
+        syscalls::Stat,
+    };
+
+    const MODE: u32 = 0o100_644;
+    const UID: u32 = 1000;
+    const MTIME: u64 = 1_729_345_530;
+
+    fn odc(name: &str, content: &[u8]) -> Vec<u8> {
+        let mut entry = format!(
+            "070707{:06o}{:06o}{:06o}{:06o}{:06o}{:06o}{:06o}{:011o}{:06o}{:011o}",
+            0,
+            0,
+            MODE,
+            UID,
+            0,
+            1,
+            0,
+            MTIME,
+            name.len() + 1,
+            content.len()
+        )
+        .into_bytes();
+
+        entry.extend_from_slice(name.as_bytes());
+        entry.push(0);
+        entry.extend_from_slice(content);
+
+        entry
+    }
+
+    pub(in crate::fs) fn newc(name: &str, content: &[u8]) -> Vec<u8> {
+        new_ascii("070701", name, MODE, content, 0)
+    }
+
+    pub(in crate::fs) fn directory(name: &str) -> Vec<u8> {
+        new_ascii("070701", name, Stat::S_IFDIR | 0o755, b"", 0)
+    }
+
+    fn crc(name: &str, content: &[u8], check: u32) -> Vec<u8> {
+        new_ascii("070702", name, MODE, content, check)
+    }
+
+    fn new_ascii(magic: &str, name: &str, mode: u32, content: &[u8], check: u32) -> Vec<u8> {
+        let mut entry = format!(
+            "{}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}",
+            magic,
+            0,
+            mode,
+            UID,
+            0,
+            1,
+            MTIME,
+            content.len(),
+            0,
+            0,
+            0,
+            0,
+            name.len() + 1,
+            check
+        )
+        .into_bytes();
+
+        entry.extend_from_slice(name.as_bytes());
+        entry.push(0);
+        pad(&mut entry);
+        entry.extend_from_slice(content);
+        pad(&mut entry);
+
+        entry
+    }
+
+    fn pad(entry: &mut Vec<u8>) {
+        entry.resize((entry.len() + 3) & !3, 0);
+    }
+
+    pub(in crate::fs) fn archive(entries: &[Vec<u8>]) -> &'static [u8] {
+        entries.concat().leak()
+    }
+
+    fn assert_file(iter: &mut Iter, name: &str, content: &[u8]) {
+        let file = iter.next().unwrap().unwrap();
+
+        assert_eq!(file.name(), name);
+        assert_eq!(file.content(), content);
+        assert_eq!(file.mode(), MODE);
+        assert_eq!(file.uid(), UID);
+        assert_eq!(file.mtime(), MTIME);
+    }
+
+    #[test]
+    fn odc_format() {
+        let mut iter = Iter::new(archive(&[
+            odc("hello.txt", b"Hello"),
+            odc("empty", b""),
+            odc("TRAILER!!!", b""),
+        ]));
+
+        assert_file(&mut iter, "hello.txt", b"Hello");
+        assert_file(&mut iter, "empty", b"");
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn newc_format() {
+        let mut iter = Iter::new(archive(&[
+            newc("hello.txt", b"Hello"),
+            newc("TRAILER!!!", b""),
+        ]));
+
+        assert_file(&mut iter, "hello.txt", b"Hello");
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn newc_padding() {
+        // The names and the contents of all lengths modulo 4 are followed by padding.
+        let names = ["a", "ab", "abc", "abcd"];
+        let contents: [&[u8]; 4] = [b"1", b"12", b"123", b"1234"];
+
+        let mut entries: Vec<_> = names
+            .iter()
+            .zip(contents)
+            .map(|(name, content)| newc(name, content))
+            .collect();
+        entries.push(newc("TRAILER!!!", b""));
+
+        let mut iter = Iter::new(archive(&entries));
+
+        for (name, content) in names.iter().zip(contents) {
+            assert_file(&mut iter, name, content);
+        }
+
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn crc_format() {
+        let mut iter = Iter::new(archive(&[
+            crc("hello.txt", b"Hello", checksum(b"Hello")),
+            crc("TRAILER!!!", b"", 0),
+        ]));
+
+        assert_file(&mut iter, "hello.txt", b"Hello");
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn crc_checksum_mismatch() {
+        let mut iter = Iter::new(archive(&[
+            crc("hello.txt", b"Hello", checksum(b"Hello") + 1),
+            crc("next.txt", b"", 0),
+            crc("TRAILER!!!", b"", 0),
+        ]));
+
+        assert_eq!(
+            iter.next().unwrap().unwrap_err(),
+            Error::ChecksumMismatch { name: "hello.txt" }
+        );
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn mixed_formats() {
+        let mut iter = Iter::new(archive(&[
+            odc("old", b"1"),
+            newc("new", b"2"),
+            crc("TRAILER!!!", b"", 0),
+        ]));
+
+        assert_file(&mut iter, "old", b"1");
+        assert_file(&mut iter, "new", b"2");
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn truncated_header() {
+        let entry = newc("hello.txt", b"Hello");
+        let mut iter = Iter::new(archive(&[entry[..100].to_vec()]));
+
+        assert_eq!(iter.next().unwrap().unwrap_err(), Error::Truncated);
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn truncated_name() {
+        let entry = newc("hello.txt", b"Hello");
+        let mut iter = Iter::new(archive(&[entry[..115].to_vec()]));
+
+        assert_eq!(iter.next().unwrap().unwrap_err(), Error::Truncated);
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn truncated_content() {
+        let entry = odc("hello.txt", b"Hello");
+        let mut iter = Iter::new(archive(&[entry[..entry.len() - 1].to_vec()]));
+
+        assert_eq!(iter.next().unwrap().unwrap_err(), Error::Truncated);
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn missing_trailer() {
+        let mut iter = Iter::new(archive(&[newc("hello.txt", b"Hello")]));
+
+        assert_file(&mut iter, "hello.txt", b"Hello");
+        assert_eq!(iter.next().unwrap().unwrap_err(), Error::Truncated);
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn invalid_magic() {
+        let mut entry = newc("hello.txt", b"Hello");
+        entry[5] = b'3';
+
+        let mut iter = Iter::new(archive(&[entry]));
+
+        assert_eq!(
+            iter.next().unwrap().unwrap_err(),
+            Error::InvalidMagic(*b"070703")
+        );
+        assert!(iter.next().is_none());
+    }
+}
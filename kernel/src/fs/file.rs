@@ -0,0 +1,80 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use {
+    super::{Error, Node},
+    core::convert::TryFrom,
+    syscalls::{DirEntry, Whence},
+};
+
+/// A file or a directory opened by a process.
+#[derive(Debug)]
+pub(crate) struct OpenFile {
+    node: Node,
+    /// The offset in bytes for a file, or the index of the next entry for a directory.
+    offset: usize,
+}
+impl OpenFile {
+    pub(crate) fn open(path: &str) -> Result<Self, Error> {
+        Ok(Self {
+            node: super::lookup(path)?,
+            offset: 0,
+        })
+    }
+
+    /// Returns at most `len` bytes from the current offset without advancing the offset. Call
+    /// [`OpenFile::advance`] after consuming them.
+    ///
+    /// Returns [`None`] if this is a directory.
+    pub(crate) fn peek(&self, len: usize) -> Option<&'static [u8]> {
+        let Node::File(f) = &self.node else {
+            return None;
+        };
+
+        let content = f.content().get(self.offset..).unwrap_or(&[]);
+
+        Some(&content[..len.min(content.len())])
+    }
+
+    pub(crate) fn advance(&mut self, len: usize) {
+        self.offset += len;
+    }
+
+    /// Moves the offset to `offset` from `whence`. The offset may be beyond the end of the file.
+    ///
+    /// Returns [`None`] if the new offset is negative.
+    pub(crate) fn seek(&mut self, offset: i64, whence: Whence) -> Option<u64> {
+        let base = match whence {
+            Whence::Set => 0,
+            Whence::Current => self.offset,
+            Whence::End => self.len(),
+        };
+
+        let new = i64::try_from(base).ok()?.checked_add(offset)?;
+        self.offset = usize::try_from(new).ok()?;
+
+        u64::try_from(new).ok()
+    }
+
+    /// Returns the next entry of the directory and advances the offset.
+    ///
+    /// Returns [`None`] if this is not a directory or if there is no more entry.
+    pub(crate) fn read_dir(&mut self) -> Option<DirEntry> {
+        let Node::Directory { path, .. } = &self.node else {
+            return None;
+        };
+
+        let children = super::children(path).ok()?;
+        let (name, node) = children.iter().nth(self.offset)?;
+
+        self.offset += 1;
+
+        Some(DirEntry::new(name, node.stat()))
+    }
+
+    fn len(&self) -> usize {
+        match &self.node {
+            Node::File(f) => f.content().len(),
+            Node::Directory { .. } => 0,
+        }
+    }
+}
@@ -0,0 +1,375 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! The file system on the initrd.
+//!
+//! A path is relative to the root of the archive, and the leading `/` and `./` are ignored, so
+//! `fonts/ascii.psf`, `/fonts/ascii.psf` and `./fonts/ascii.psf` are the same file. A directory
+//! exists if the archive has an entry for it or a file under it.
+
+mod cpio;
+pub(crate) mod file;
+
+use {
+    alloc::collections::BTreeMap,
+    conquer_once::spin::OnceCell,
+    core::{convert::TryInto, slice},
+    cpio::CpioArchievedFile,
+    log::{info, warn},
+    os_units::Bytes,
+    predefined_mmap::INITRD_ADDR,
+    syscalls::Stat,
+};
+
+static INITRD: OnceCell<&'static [u8]> = OnceCell::uninit();
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Error {
+    NotFound,
+    IsDirectory,
+    InvalidMagic([u8; 6]),
+    InvalidNumber,
+    InvalidName,
+    Truncated,
+    ChecksumMismatch { name: &'static str },
+}
+
+/// A file or a directory in the initrd.
+#[derive(Debug)]
+pub(crate) enum Node {
+    File(CpioArchievedFile),
+    /// `entry` is [`None`] if the directory is implied only by the paths of the files under it.
+    Directory {
+        path: &'static str,
+        entry: Option<CpioArchievedFile>,
+    },
+}
+impl Node {
+    fn new(f: CpioArchievedFile) -> Self {
+        if f.mode() & Stat::S_IFMT == Stat::S_IFDIR {
+            Self::Directory {
+                path: normalize(f.name()),
+                entry: Some(f),
+            }
+        } else {
+            Self::File(f)
+        }
+    }
+
+    pub(crate) fn stat(&self) -> Stat {
+        match self {
+            Self::File(f) => Stat::new(
+                f.content().len().try_into().unwrap(),
+                f.mode(),
+                f.uid(),
+                f.mtime(),
+            ),
+            Self::Directory { entry: Some(f), .. } => Stat::new(0, f.mode(), f.uid(), f.mtime()),
+            Self::Directory { entry: None, .. } => Stat::new(0, Stat::S_IFDIR | 0o555, 0, 0),
+        }
+    }
+}
+
+pub(super) fn init(bytes: Bytes) {
+    // SAFETY: The boot loader maps the whole initrd to `INITRD_ADDR`, and it is never unmapped.
+    let initrd = unsafe { slice::from_raw_parts(INITRD_ADDR.as_ptr(), bytes.as_usize()) };
+
+    let r = INITRD.try_init_once(|| initrd);
+    r.expect("`fs::init` is called more than once.");
+}
+
+pub(super) fn list_names() {
+    for f in iter() {
+        match f {
+            Ok(f) => info!(
+                "Name: {}, file size: {}, mode: {:o}, uid: {}, mtime: {}",
+                f.name(),
+                f.content().len(),
+                f.mode(),
+                f.uid(),
+                f.mtime()
+            ),
+            Err(e) => warn!("The initrd is malformed: {:?}", e),
+        }
+    }
+}
+
+pub(super) fn find(path: &str) -> Result<CpioArchievedFile, Error> {
+    match lookup(path)? {
+        Node::File(f) => Ok(f),
+        Node::Directory { .. } => Err(Error::IsDirectory),
+    }
+}
+
+pub(super) fn lookup(path: &str) -> Result<Node, Error> {
+    lookup_in(iter(), path)
+}
+
+/// Returns the files and the directories right under the directory `dir`, sorted by their names.
+fn children(dir: &str) -> Result<BTreeMap<&'static str, Node>, Error> {
+    children_in(iter(), dir)
+}
+
+/// The same as [`lookup`], but searches `files` instead of the initrd.
+fn lookup_in(
+    files: impl IntoIterator<Item = Result<CpioArchievedFile, Error>>,
+    path: &str,
+) -> Result<Node, Error> {
+    let path = normalize(path);
+
+    if path.is_empty() {
+        return Ok(Node::Directory {
+            path: "",
+            entry: None,
+        });
+    }
+
+    let mut implied = None;
+
+    for f in files {
+        let f = f?;
+        let name = normalize(f.name());
+
+        if name == path {
+            return Ok(Node::new(f));
+        }
+
+        if implied.is_none() && relative(name, path).is_some() {
+            implied = Some(&name[..path.len()]);
+        }
+    }
+
+    implied
+        .map(|path| Node::Directory { path, entry: None })
+        .ok_or(Error::NotFound)
+}
+
+/// The same as [`children`], but searches `files` instead of the initrd.
+fn children_in(
+    files: impl IntoIterator<Item = Result<CpioArchievedFile, Error>>,
+    dir: &str,
+) -> Result<BTreeMap<&'static str, Node>, Error> {
+    let mut children = BTreeMap::new();
+
+    for f in files {
+        let f = f?;
+        let path = normalize(f.name());
+
+        let Some(rest) = relative(path, dir) else {
+            continue;
+        };
+
+        if let Some((child, _)) = rest.split_once('/') {
+            let child_path = &path[..path.len() - rest.len() + child.len()];
+
+            children.entry(child).or_insert(Node::Directory {
+                path: child_path,
+                entry: None,
+            });
+        } else {
+            children.insert(rest, Node::new(f));
+        }
+    }
+
+    Ok(children)
+}
+
+/// Iterates over the files in the initrd. The iteration stops after the first error.
+fn iter() -> impl Iterator<Item = Result<CpioArchievedFile, Error>> {
+    let initrd = INITRD.try_get().expect("`fs::init` is not called.");
+
+    cpio::Iter::new(initrd)
+}
+
+/// Removes the leading `/` and `./` and the trailing `/` from `path`. The root is an empty string.
+fn normalize(path: &str) -> &str {
+    let mut path = path.trim_matches('/');
+
+    while let Some(rest) = path.strip_prefix("./") {
+        path = rest.trim_start_matches('/');
+    }
+
+    if path == "." {
+        ""
+    } else {
+        path
+    }
+}
+
+/// Returns `path` relative to the directory `dir`, or [`None`] if `path` is not under `dir`.
+fn relative<'a>(path: &'a str, dir: &str) -> Option<&'a str> {
+    let rest = if dir.is_empty() {
+        path
+    } else {
+        path.strip_prefix(dir)?.strip_prefix('/')?
+    };
+
+    (!rest.is_empty()).then_some(rest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        children_in,
+        cpio::{
+            self,
+            tests::{archive, directory, newc},
+        },
+        lookup_in, normalize, relative, Error, Node,
+    };
+
+    fn initrd() -> cpio::Iter {
+        cpio::Iter::new(archive(&[
+            directory("./fonts/"),
+            newc("./fonts/ascii.psf", b"font"),
+            newc("fontsx/a.txt", b"a"),
+            newc("/apps/sh.bin", b"sh"),
+            newc("TRAILER!!!", b""),
+        ]))
+    }
+
+    fn assert_directory(node: &Node, expected_path: &str, has_entry: bool) {
+        let Node::Directory { path, entry } = node else {
+            panic!("{:?} is not a directory.", node);
+        };
+
+        assert_eq!(*path, expected_path);
+        assert_eq!(entry.is_some(), has_entry);
+    }
+
+    fn assert_file(node: &Node, name: &str) {
+        let Node::File(f) = node else {
+            panic!("{:?} is not a file.", node);
+        };
+
+        assert_eq!(f.name(), name);
+    }
+
+    #[test]
+    fn normalize_root() {
+        for path in ["", "/", "//", ".", "./", "/./", "././", "./."] {
+            assert_eq!(normalize(path), "", "{:?}", path);
+        }
+    }
+
+    #[test]
+    fn normalize_leading_and_trailing_slashes() {
+        for path in [
+            "fonts/ascii.psf",
+            "/fonts/ascii.psf",
+            "./fonts/ascii.psf",
+            "/./fonts/ascii.psf",
+            ".//fonts/ascii.psf",
+            "././fonts/ascii.psf",
+            "fonts/ascii.psf/",
+            "./fonts/ascii.psf//",
+        ] {
+            assert_eq!(normalize(path), "fonts/ascii.psf", "{:?}", path);
+        }
+    }
+
+    #[test]
+    fn normalize_keeps_dot_files() {
+        assert_eq!(normalize(".hidden"), ".hidden");
+        assert_eq!(normalize("./.hidden"), ".hidden");
+    }
+
+    #[test]
+    fn relative_to_root() {
+        assert_eq!(relative("fonts/ascii.psf", ""), Some("fonts/ascii.psf"));
+        assert_eq!(relative("", ""), None);
+    }
+
+    #[test]
+    fn relative_to_directory() {
+        assert_eq!(relative("fonts/ascii.psf", "fonts"), Some("ascii.psf"));
+        assert_eq!(relative("fonts/a/b", "fonts/a"), Some("b"));
+        assert_eq!(relative("fonts", "fonts"), None);
+        assert_eq!(relative("apps/sh.bin", "fonts"), None);
+    }
+
+    #[test]
+    fn relative_sibling_prefix() {
+        assert_eq!(relative("fontsx/a.txt", "fonts"), None);
+        assert_eq!(relative("fontsx", "fonts"), None);
+    }
+
+    #[test]
+    fn lookup_root() {
+        for path in ["", "/", "./"] {
+            assert_directory(&lookup_in(initrd(), path).unwrap(), "", false);
+        }
+    }
+
+    #[test]
+    fn lookup_file() {
+        for path in ["fonts/ascii.psf", "/fonts/ascii.psf", "./fonts/ascii.psf/"] {
+            assert_file(&lookup_in(initrd(), path).unwrap(), "./fonts/ascii.psf");
+        }
+    }
+
+    #[test]
+    fn lookup_archived_directory() {
+        for path in ["fonts", "/fonts/", "./fonts"] {
+            assert_directory(&lookup_in(initrd(), path).unwrap(), "fonts", true);
+        }
+    }
+
+    #[test]
+    fn lookup_implied_directory() {
+        assert_directory(&lookup_in(initrd(), "apps/").unwrap(), "apps", false);
+        assert_directory(&lookup_in(initrd(), "fontsx").unwrap(), "fontsx", false);
+    }
+
+    #[test]
+    fn lookup_not_found() {
+        for path in ["font", "fonts/ascii", "apps/sh", "sh.bin"] {
+            assert_eq!(
+                lookup_in(initrd(), path).unwrap_err(),
+                Error::NotFound,
+                "{:?}",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn lookup_malformed_archive() {
+        let files = cpio::Iter::new(archive(&[newc("a.txt", b"a")]));
+
+        assert_eq!(lookup_in(files, "b.txt").unwrap_err(), Error::Truncated);
+    }
+
+    #[test]
+    fn children_of_root() {
+        let children = children_in(initrd(), "").unwrap();
+
+        assert_eq!(
+            children.keys().copied().collect::<Vec<_>>(),
+            ["apps", "fonts", "fontsx"]
+        );
+        assert_directory(&children["apps"], "apps", false);
+        assert_directory(&children["fonts"], "fonts", true);
+        assert_directory(&children["fontsx"], "fontsx", false);
+    }
+
+    #[test]
+    fn children_of_directory() {
+        let children = children_in(initrd(), "fonts").unwrap();
+
+        assert_eq!(children.keys().copied().collect::<Vec<_>>(), ["ascii.psf"]);
+        assert_file(&children["ascii.psf"], "./fonts/ascii.psf");
+    }
+
+    #[test]
+    fn children_of_implied_directory() {
+        let children = children_in(initrd(), "apps").unwrap();
+
+        assert_eq!(children.keys().copied().collect::<Vec<_>>(), ["sh.bin"]);
+        assert_file(&children["sh.bin"], "/apps/sh.bin");
+    }
+
+    #[test]
+    fn children_of_file() {
+        assert!(children_in(initrd(), "fonts/ascii.psf").unwrap().is_empty());
+    }
+}
@@ -1,13 +1,128 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 use {
-    super::pic,
+    super::{local, pic},
     crate::mem::{accessor::Single, allocator},
-    acpi::{platform::interrupt::IoApic, AcpiTables, InterruptModel},
+    acpi::{
+        platform::interrupt::{InterruptSourceOverride, IoApic, Polarity, TriggerMode},
+        AcpiTables, InterruptModel,
+    },
+    alloc::vec::Vec,
+    conquer_once::spin::OnceCell,
+    core::convert::TryFrom,
     x86_64::PhysAddr,
 };
 
 /// Currently this OS does not support multiple I/O APIC.
+static IO_APIC: OnceCell<Config> = OnceCell::uninit();
+
+const MAX_IRQ: u8 = 24;
+
+/// The first GSI which is not an ISA IRQ.
+const NUM_OF_ISA_IRQS: u8 = 16;
+
+pub(crate) fn init(table: &AcpiTables<allocator::acpi::Mapper>) {
+    pic::disable();
+    let platform_info = table.platform_info().unwrap();
+    let interrupt = platform_info.interrupt_model;
+    if let InterruptModel::Apic(apic) = interrupt {
+        let config = Config::new(&apic.io_apics, &apic.interrupt_source_overrides);
+
+        // SAFETY: This operation is safe because `table` contains valid information.
+        let mut registers = unsafe { Registers::new(config.address) };
+        registers.mask_all();
+
+        IO_APIC.init_once(|| config);
+    }
+}
+
+/// Routes `irq` to `vector` of the current processor and unmasks it.
+///
+/// Returns `false` if there is no I/O APIC or the I/O APIC does not have the input of `irq`.
+pub(crate) fn route(irq: u8, vector: u8) -> bool {
+    with_registers(irq, |r, input| {
+        let (active_low, level_triggered) = config().trigger_of(irq);
+
+        r.redirect(input, vector, active_low, level_triggered, local::id());
+    })
+    .is_some()
+}
+
+pub(crate) fn mask(irq: u8) {
+    with_registers(irq, Registers::mask);
+}
+
+pub(crate) fn unmask(irq: u8) {
+    with_registers(irq, Registers::unmask);
+}
+
+fn with_registers<T>(irq: u8, f: impl FnOnce(&mut Registers, u8) -> T) -> Option<T> {
+    let config = IO_APIC.get()?;
+    let input = config.input_of(irq)?;
+
+    // SAFETY: `Config::address` is the address read from the ACPI tables.
+    let mut registers = unsafe { Registers::new(config.address) };
+
+    Some(f(&mut registers, input))
+}
+
+fn config() -> &'static Config {
+    IO_APIC.get().expect("The I/O APIC is not initialized.")
+}
+
+struct Config {
+    address: PhysAddr,
+    gsi_base: u32,
+    overrides: Vec<InterruptSourceOverride>,
+}
+impl Config {
+    fn new(io_apics: &[IoApic], overrides: &[InterruptSourceOverride]) -> Self {
+        Self {
+            address: PhysAddr::new(io_apics[0].address.into()),
+            gsi_base: io_apics[0].global_system_interrupt_base,
+            overrides: overrides.to_vec(),
+        }
+    }
+
+    /// Returns the input pin of the I/O APIC which `irq` is connected to.
+    fn input_of(&self, irq: u8) -> Option<u8> {
+        let gsi = self
+            .override_of(irq)
+            .map_or_else(|| irq.into(), |o| o.global_system_interrupt);
+
+        u8::try_from(gsi.checked_sub(self.gsi_base)?)
+            .ok()
+            .filter(|input| *input < MAX_IRQ)
+    }
+
+    /// Returns whether `irq` is active low and whether it is level-triggered.
+    fn trigger_of(&self, irq: u8) -> (bool, bool) {
+        // The ISA interrupts are active high and edge-triggered, and the PCI interrupts are active
+        // low and level-triggered unless the ACPI tables say otherwise.
+        let default = irq >= NUM_OF_ISA_IRQS;
+
+        self.override_of(irq).map_or((default, default), |o| {
+            let active_low = match o.polarity {
+                Polarity::SameAsBus => default,
+                Polarity::ActiveHigh => false,
+                Polarity::ActiveLow => true,
+            };
+            let level = match o.trigger_mode {
+                TriggerMode::SameAsBus => default,
+                TriggerMode::Edge => false,
+                TriggerMode::Level => true,
+            };
+
+            (active_low, level)
+        })
+    }
+
+    fn override_of(&self, irq: u8) -> Option<&InterruptSourceOverride> {
+        (irq < NUM_OF_ISA_IRQS)
+            .then(|| self.overrides.iter().find(|o| o.isa_source == irq))
+            .flatten()
+    }
+}
 
 struct Registers {
     addr: Single<u32>,
@@ -16,17 +131,16 @@ struct Registers {
 impl Registers {
     const DEST_BASE: u8 = 0x10;
 
-    /// SAFETY: This operation is unsafe because the caller must ensure that `IoApic::address` must
+    const MASK_INTERRUPT: u32 = 1 << 16;
+    const LEVEL_TRIGGERED: u32 = 1 << 15;
+    const ACTIVE_LOW: u32 = 1 << 13;
+
+    /// SAFETY: This operation is unsafe because the caller must ensure that `io_apic_base` must
     /// be a valid address to I/O APIC registers.
     ///
-    /// There is no need to create an instance of `IoApic` manually, but because it is possible as
-    /// the all fields of the struct are public, this method is unsafe.
-    ///
     /// This method must be called in the kernel privilege.
-    unsafe fn new(io_apics: &[IoApic]) -> Self {
-        let io_apic_base = PhysAddr::new(io_apics[0].address.into());
-
-        // SAFETY: The caller must ensure that `io_apics[0]`.address` is the correct address.
+    unsafe fn new(io_apic_base: PhysAddr) -> Self {
+        // SAFETY: The caller must ensure that `io_apic_base` is the correct address.
         unsafe {
             Self {
                 addr: crate::mem::accessor::new(io_apic_base),
@@ -36,32 +150,53 @@ impl Registers {
     }
 
     fn mask_all(&mut self) {
-        const MAX_IRQ: u8 = 24;
-
         for i in 0..MAX_IRQ {
             self.mask(i);
         }
     }
 
-    fn mask(&mut self, irq: u8) {
-        const MASK_INTERRUPT: u32 = 0x100;
+    fn mask(&mut self, input: u8) {
+        let v = self.read(Self::DEST_BASE + input * 2);
 
-        self.write(Self::DEST_BASE + irq * 2, MASK_INTERRUPT);
+        self.write(Self::DEST_BASE + input * 2, v | Self::MASK_INTERRUPT);
     }
 
-    fn write(&mut self, index: u8, v: u32) {
+    fn unmask(&mut self, input: u8) {
+        let v = self.read(Self::DEST_BASE + input * 2);
+
+        self.write(Self::DEST_BASE + input * 2, v & !Self::MASK_INTERRUPT);
+    }
+
+    #[allow(clippy::fn_params_excessive_bools)]
+    fn redirect(
+        &mut self,
+        input: u8,
+        vector: u8,
+        active_low: bool,
+        level_triggered: bool,
+        apic_id: u8,
+    ) {
+        let mut low = u32::from(vector);
+
+        if active_low {
+            low |= Self::ACTIVE_LOW;
+        }
+
+        if level_triggered {
+            low |= Self::LEVEL_TRIGGERED;
+        }
+
+        self.write(Self::DEST_BASE + input * 2 + 1, u32::from(apic_id) << 24);
+        self.write(Self::DEST_BASE + input * 2, low);
+    }
+
+    fn read(&mut self, index: u8) -> u32 {
         self.addr.write_volatile(index.into());
-        self.data.write_volatile(v);
+        self.data.read_volatile()
     }
-}
 
-pub(crate) fn init(table: &AcpiTables<allocator::acpi::Mapper>) {
-    pic::disable();
-    let platform_info = table.platform_info().unwrap();
-    let interrupt = platform_info.interrupt_model;
-    if let InterruptModel::Apic(apic) = interrupt {
-        // SAFETY: This operation is safe because `table` contains valid information.
-        let mut registers = unsafe { Registers::new(&apic.io_apics) };
-        registers.mask_all();
+    fn write(&mut self, index: u8, v: u32) {
+        self.addr.write_volatile(index.into());
+        self.data.write_volatile(v);
     }
 }
@@ -1,6 +1,14 @@
 use {
-    crate::{interrupt::apic::local, process},
-    x86_64::structures::idt::InterruptStackFrame,
+    crate::{
+        interrupt::{apic::local, irq},
+        mem::swap,
+        process,
+    },
+    log::error,
+    x86_64::{
+        registers::control::Cr2,
+        structures::idt::{InterruptStackFrame, PageFaultErrorCode},
+    },
 };
 
 pub(super) extern "x86-interrupt" fn h_20(_: InterruptStackFrame) {
@@ -8,3 +16,34 @@ pub(super) extern "x86-interrupt" fn h_20(_: InterruptStackFrame) {
 
     process::switch();
 }
+
+/// The common handler of the interrupts routed to the user-space drivers.
+pub(super) fn user_irq(_: InterruptStackFrame, vector: u8, _: Option<u64>) {
+    irq::handle(vector);
+}
+
+pub(super) extern "x86-interrupt" fn page_fault(
+    frame: InterruptStackFrame,
+    error_code: PageFaultErrorCode,
+) {
+    let addr = Cr2::read();
+
+    if !error_code.contains(PageFaultErrorCode::PROTECTION_VIOLATION) {
+        match swap::swap_in(addr) {
+            Ok(true) => return,
+            Ok(false) => {}
+            Err(e) => {
+                error!(
+                    "Failed to swap in {:?}: {:?}. Terminating {}.",
+                    addr,
+                    e,
+                    process::scheduler::current_process_name()
+                );
+
+                process::exit();
+            }
+        }
+    }
+
+    panic!("Page fault at {:?} ({:?}).\n{:#?}", addr, error_code, frame);
+}
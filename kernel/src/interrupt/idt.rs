@@ -1,14 +1,20 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 use {
-    crate::interrupt::handler::h_20, conquer_once::spin::Lazy,
-    x86_64::structures::idt::InterruptDescriptorTable,
+    crate::interrupt::{
+        handler::{h_20, page_fault, user_irq},
+        irq,
+    },
+    conquer_once::spin::Lazy,
+    x86_64::{set_general_handler, structures::idt::InterruptDescriptorTable},
 };
 
 static IDT: Lazy<InterruptDescriptorTable> = Lazy::new(|| {
     let mut idt = InterruptDescriptorTable::new();
 
+    idt.page_fault.set_handler_fn(page_fault);
     idt[0x20].set_handler_fn(h_20);
+    set_general_handler!(&mut idt, user_irq, irq::VECTORS);
 
     idt
 });
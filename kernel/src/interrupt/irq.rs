@@ -0,0 +1,188 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Hardware interrupts handled by user-space drivers.
+//!
+//! The kernel masks an interrupt when it happens and notifies the owner of it with a message.
+//! The interrupt stays masked until the owner acknowledges it.
+//!
+//! A few interrupts are handled by the kernel itself, such as the one of the serial port.
+
+use {
+    super::apic::{io, local},
+    crate::process::{self, Pid},
+    alloc::collections::BTreeMap,
+    conquer_once::spin::Lazy,
+    core::ops::{DerefMut, RangeInclusive},
+    message::{Body, Header, Message},
+    spinning_top::Spinlock,
+    syscalls::{Interrupt, MsiMessage, INTERRUPT_SENDER, MSI_VECTORS},
+};
+
+/// The vector of the I/O APIC input 0.
+const IRQ_BASE: u8 = 0x30;
+
+/// The vectors which the user-space drivers may own.
+pub(super) const VECTORS: RangeInclusive<u8> = IRQ_BASE..=*MSI_VECTORS.end();
+
+static OWNERS: Lazy<Spinlock<BTreeMap<u8, Owner>>> = Lazy::new(|| Spinlock::new(BTreeMap::new()));
+
+/// Routes `interrupt` to the current process.
+///
+/// Returns `false` if `interrupt` is invalid or already registered.
+pub(crate) fn register(interrupt: Interrupt) -> bool {
+    let owner = Owner::Process {
+        pid: process::scheduler::current_pid(),
+        interrupt,
+    };
+
+    register_owner(interrupt, owner)
+}
+
+/// Makes the kernel handle the IRQ `irq` by calling `handler` in the interrupt handler.
+///
+/// Returns `false` if `irq` is invalid or already registered.
+pub(crate) fn register_kernel(irq: u8, handler: fn()) -> bool {
+    register_owner(Interrupt::Irq(irq), Owner::Kernel(handler))
+}
+
+/// Unmasks `interrupt` if the current process owns it.
+pub(crate) fn ack(interrupt: Interrupt) -> bool {
+    let owned = vector_of(interrupt)
+        .and_then(|v| lock().get(&v).copied())
+        .map_or(false, |o| o.is_current_process(interrupt));
+
+    if owned {
+        if let Interrupt::Irq(irq) = interrupt {
+            io::unmask(irq);
+        }
+    }
+
+    owned
+}
+
+/// Stops routing `interrupt` to the current process.
+///
+/// Returns `false` if the current process does not own `interrupt`.
+pub(crate) fn unregister(interrupt: Interrupt) -> bool {
+    let Some(vector) = vector_of(interrupt) else {
+        return false;
+    };
+
+    let mut owners = lock();
+
+    if !owners
+        .get(&vector)
+        .map_or(false, |o| o.is_current_process(interrupt))
+    {
+        return false;
+    }
+
+    if let Interrupt::Irq(irq) = interrupt {
+        io::mask(irq);
+    }
+
+    owners.remove(&vector);
+
+    true
+}
+
+/// Masks and unregisters all interrupts owned by the process `pid`.
+pub(crate) fn release(pid: Pid) {
+    lock().retain(|_, owner| match *owner {
+        Owner::Process { pid: p, interrupt } if p == pid => {
+            if let Interrupt::Irq(irq) = interrupt {
+                io::mask(irq);
+            }
+
+            false
+        }
+        _ => true,
+    });
+}
+
+/// Returns the message which signals the MSI `vector` if the current process owns it.
+pub(crate) fn msi_message(vector: u8) -> Option<MsiMessage> {
+    const ADDRESS_BASE: u64 = 0xfee0_0000;
+
+    let interrupt = Interrupt::Msi(vector);
+    let owner = lock().get(&vector_of(interrupt)?).copied()?;
+
+    owner.is_current_process(interrupt).then(|| {
+        // Edge-triggered, fixed delivery to the current processor.
+        MsiMessage::new(ADDRESS_BASE | u64::from(local::id()) << 12, vector.into())
+    })
+}
+
+pub(super) fn handle(vector: u8) {
+    let owner = lock().get(&vector).copied();
+
+    match owner {
+        Some(Owner::Process { pid, interrupt }) => {
+            if let Interrupt::Irq(irq) = interrupt {
+                io::mask(irq);
+            }
+
+            let (kind, number) = interrupt.into_raw();
+            let m = Message::new(Header::new(INTERRUPT_SENDER), Body(kind, number, 0, 0, 0));
+
+            process::scheduler::notify(pid, m);
+        }
+        Some(Owner::Kernel(handler)) => handler(),
+        None => {}
+    }
+
+    local::end_of_interrupt();
+}
+
+fn register_owner(interrupt: Interrupt, owner: Owner) -> bool {
+    let Some(vector) = vector_of(interrupt) else {
+        return false;
+    };
+
+    let mut owners = lock();
+
+    if owners.contains_key(&vector) {
+        return false;
+    }
+
+    if let Interrupt::Irq(irq) = interrupt {
+        if !io::route(irq, vector) {
+            return false;
+        }
+    }
+
+    owners.insert(vector, owner);
+
+    true
+}
+
+fn vector_of(interrupt: Interrupt) -> Option<u8> {
+    match interrupt {
+        Interrupt::Irq(irq) => IRQ_BASE
+            .checked_add(irq)
+            .filter(|v| *v < *MSI_VECTORS.start()),
+        Interrupt::Msi(v) => MSI_VECTORS.contains(&v).then_some(v),
+    }
+}
+
+fn lock() -> impl DerefMut<Target = BTreeMap<u8, Owner>> {
+    OWNERS
+        .try_lock()
+        .expect("Failed to lock the interrupt owners.")
+}
+
+#[derive(Copy, Clone)]
+enum Owner {
+    Process { pid: Pid, interrupt: Interrupt },
+    Kernel(fn()),
+}
+impl Owner {
+    fn is_current_process(self, interrupt: Interrupt) -> bool {
+        match self {
+            Self::Process { pid, interrupt: i } => {
+                pid == process::scheduler::current_pid() && i == interrupt
+            }
+            Self::Kernel(_) => false,
+        }
+    }
+}
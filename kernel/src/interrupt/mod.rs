@@ -3,4 +3,5 @@
 pub(crate) mod apic;
 mod handler;
 pub(crate) mod idt;
+pub(crate) mod irq;
 pub(crate) mod timer;
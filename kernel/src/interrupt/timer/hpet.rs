@@ -0,0 +1,127 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! The High Precision Event Timer.
+//!
+//! Only the main counter is used. The comparators are left disabled.
+
+use {
+    crate::mem::{accessor::Single, allocator},
+    acpi::{AcpiTables, HpetInfo},
+    bit_field::BitField,
+    conquer_once::spin::OnceCell,
+    core::{convert::TryInto, time::Duration},
+    log::info,
+    x86_64::PhysAddr,
+};
+
+static HPET: OnceCell<Hpet> = OnceCell::uninit();
+
+/// Starts the main counter of the HPET described in the ACPI tables.
+///
+/// Returns [`None`] if there is no HPET.
+pub(super) fn init(table: &AcpiTables<allocator::acpi::Mapper>) -> Option<&'static Hpet> {
+    let info = HpetInfo::new(table).ok()?;
+
+    // SAFETY: The address is read from the ACPI tables.
+    let mut hpet = unsafe { Hpet::new(PhysAddr::new(info.base_address.try_into().unwrap())) };
+
+    hpet.start();
+
+    info!(
+        "HPET: {} Hz, {}-bit main counter",
+        hpet.frequency(),
+        if hpet.is_64bit() { 64 } else { 32 }
+    );
+
+    HPET.try_init_once(|| hpet).ok()?;
+
+    HPET.get()
+}
+
+/// Returns the time since the main counter started.
+///
+/// Returns [`None`] if there is no HPET or the main counter is 32-bit. A 32-bit counter wraps
+/// around in a few minutes, which is too short for the monotonic clock.
+pub(super) fn uptime() -> Option<Duration> {
+    HPET.get().filter(|h| h.is_64bit()).map(Hpet::elapsed)
+}
+
+/// Returns the HPET if [`init`] found one.
+pub(super) fn get() -> Option<&'static Hpet> {
+    HPET.get()
+}
+
+pub(super) struct Hpet {
+    capabilities: Single<u64>,
+    configuration: Single<u64>,
+    main_counter: Single<u64>,
+    /// The period of the main counter in femtoseconds.
+    period: u64,
+}
+impl Hpet {
+    const FEMTOSECONDS_PER_SECOND: u64 = 1_000_000_000_000_000;
+
+    const COUNT_SIZE_CAP: usize = 13;
+    const ENABLE_CNF: usize = 0;
+
+    /// # Safety
+    ///
+    /// `base` must be the address of the HPET registers.
+    unsafe fn new(base: PhysAddr) -> Self {
+        // SAFETY: The caller must ensure that `base` is the correct address.
+        let capabilities: Single<u64> = unsafe { crate::mem::accessor::new(base) };
+        let configuration = unsafe { crate::mem::accessor::new(base + 0x10_usize) };
+        let main_counter = unsafe { crate::mem::accessor::new(base + 0xf0_usize) };
+
+        let period = capabilities.read_volatile().get_bits(32..64);
+
+        Self {
+            capabilities,
+            configuration,
+            main_counter,
+            period,
+        }
+    }
+
+    /// Busy-waits for `d`, which must be shorter than the period of the main counter.
+    pub(super) fn wait(&self, d: Duration) {
+        let ticks = d.as_nanos() * 1_000_000 / u128::from(self.period);
+        let start = self.main_counter.read_volatile();
+
+        while u128::from(self.ticks_since(start)) < ticks {}
+    }
+
+    /// Returns the time since the main counter started.
+    fn elapsed(&self) -> Duration {
+        let fs = u128::from(self.main_counter.read_volatile()) * u128::from(self.period);
+        let ns = fs / 1_000_000;
+
+        Duration::from_nanos(ns.try_into().unwrap_or(u64::MAX))
+    }
+
+    fn ticks_since(&self, start: u64) -> u64 {
+        let d = self.main_counter.read_volatile().wrapping_sub(start);
+
+        if self.is_64bit() {
+            d
+        } else {
+            d & u64::from(u32::MAX)
+        }
+    }
+
+    fn start(&mut self) {
+        self.configuration.update_volatile(|c| {
+            c.set_bit(Self::ENABLE_CNF, true);
+        });
+    }
+
+    fn frequency(&self) -> u64 {
+        Self::FEMTOSECONDS_PER_SECOND / self.period
+    }
+
+    fn is_64bit(&self) -> bool {
+        self.capabilities
+            .read_volatile()
+            .get_bit(Self::COUNT_SIZE_CAP)
+    }
+}
@@ -0,0 +1,316 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! The Local APIC timer which drives the scheduler.
+//!
+//! The timer is calibrated with the HPET, or with the ACPI PM timer if there is no HPET. The timer
+//! fires once at the end of each time slice, whose length is given by the `tick=<Hz>` boot option,
+//! and it stops while only the idle process can run. The TSC-deadline mode is used if the
+//! processor supports it.
+//!
+//! The wall-clock time is the CMOS real-time clock read at boot plus the monotonic clock.
+
+mod hpet;
+mod pm;
+mod rtc;
+mod tsc;
+
+use {
+    crate::{
+        cmdline,
+        mem::{accessor::Single, allocator},
+    },
+    acpi::AcpiTables,
+    conquer_once::spin::OnceCell,
+    core::{
+        convert::TryInto,
+        hint,
+        ops::{DerefMut, RangeInclusive},
+        time::Duration,
+    },
+    hpet::Hpet,
+    log::{info, warn},
+    pm::AcpiPm,
+    spinning_top::Spinlock,
+    x86_64::{registers::model_specific::Msr, PhysAddr},
+};
+
+const LVT_TIMER: PhysAddr = PhysAddr::new_truncate(0xfee0_0320);
+const INITIAL_COUNT: PhysAddr = PhysAddr::new_truncate(0xfee0_0380);
+const CURRENT_COUNT: PhysAddr = PhysAddr::new_truncate(0xfee0_0390);
+const DIVIDE_CONFIG: PhysAddr = PhysAddr::new_truncate(0xfee0_03e0);
+const TIMER_VECTOR: u8 = 0x20;
+
+const IA32_TSC_DEADLINE: Msr = Msr::new(0x6e0);
+
+const DEFAULT_TICK_RATE: u32 = 100;
+const TICK_RATES: RangeInclusive<u32> = 10..=1000;
+
+static TIMER: OnceCell<Spinlock<LocalApic>> = OnceCell::uninit();
+
+/// The ACPI PM timer kept for [`busy_wait`] when there is no monotonic clock.
+static PM_TIMER: OnceCell<Spinlock<AcpiPm>> = OnceCell::uninit();
+
+/// The wall-clock time when the monotonic clock was 0.
+static BOOT_TIME: OnceCell<Duration> = OnceCell::uninit();
+
+pub(crate) fn init(table: &AcpiTables<allocator::acpi::Mapper>) {
+    let mut local_apic_tm = LocalApic::new();
+    let mut reference = Reference::new(table);
+
+    local_apic_tm.init(&mut reference);
+
+    TIMER.init_once(|| Spinlock::new(local_apic_tm));
+
+    if let Reference::Pm(p) = reference {
+        PM_TIMER.init_once(|| Spinlock::new(p));
+    }
+
+    init_wall_clock();
+
+    start_time_slice();
+}
+
+/// Makes the timer fire at the end of a new time slice.
+pub(crate) fn start_time_slice() {
+    lock().arm();
+}
+
+/// Stops the timer until [`start_time_slice`] is called.
+pub(crate) fn stop() {
+    lock().disarm();
+}
+
+/// Returns the time of the monotonic clock.
+///
+/// The invariant TSC is preferred, and a 64-bit HPET is used if the TSC is not invariant. Returns
+/// [`None`] if neither is available.
+pub(crate) fn uptime() -> Option<Duration> {
+    tsc::now().or_else(hpet::uptime)
+}
+
+/// Busy-waits for `d`.
+///
+/// The monotonic clock is used if available, and the HPET or the ACPI PM timer otherwise.
+pub(crate) fn busy_wait(d: Duration) {
+    if let Some(start) = uptime() {
+        while uptime().unwrap().saturating_sub(start) < d {
+            hint::spin_loop();
+        }
+    } else if let Some(h) = hpet::get() {
+        h.wait(d);
+    } else if let Some(p) = PM_TIMER.get() {
+        p.try_lock()
+            .expect("Failed to lock the ACPI PM timer.")
+            .wait(d);
+    } else {
+        warn!("No timer to wait with.");
+    }
+}
+
+/// Returns the time since the Unix epoch.
+///
+/// Returns [`None`] if there is no monotonic clock or the RTC holds an invalid date and time.
+pub(crate) fn realtime() -> Option<Duration> {
+    Some(*BOOT_TIME.get()? + uptime()?)
+}
+
+fn init_wall_clock() {
+    let Some(now) = rtc::read() else {
+        warn!("The RTC holds an invalid date and time.");
+        return;
+    };
+
+    info!("RTC: {}", now);
+
+    if let Some(uptime) = uptime() {
+        BOOT_TIME.init_once(|| now.since_unix_epoch().saturating_sub(uptime));
+    }
+}
+
+fn lock() -> impl DerefMut<Target = LocalApic> {
+    TIMER
+        .try_get()
+        .expect("The timer is not initialized.")
+        .try_lock()
+        .expect("Failed to lock the timer.")
+}
+
+fn tick_rate() -> u32 {
+    let Some(v) = cmdline::value("tick") else {
+        return DEFAULT_TICK_RATE;
+    };
+
+    match v.parse() {
+        Ok(hz) if TICK_RATES.contains(&hz) => hz,
+        _ => {
+            warn!(
+                "Invalid tick rate `{}`. It must be in {:?} Hz.",
+                v, TICK_RATES
+            );
+            DEFAULT_TICK_RATE
+        }
+    }
+}
+
+/// The timer to measure the frequencies of the Local APIC timer and the TSC.
+enum Reference {
+    Hpet(&'static Hpet),
+    Pm(AcpiPm),
+}
+impl Reference {
+    fn new(table: &AcpiTables<allocator::acpi::Mapper>) -> Self {
+        if let Some(h) = hpet::init(table) {
+            Self::Hpet(h)
+        } else {
+            info!("No HPET. The ACPI PM timer is used instead.");
+            Self::Pm(AcpiPm::new(table))
+        }
+    }
+
+    fn wait_milliseconds(&mut self, t: u32) {
+        match self {
+            Self::Hpet(h) => h.wait(Duration::from_millis(t.into())),
+            Self::Pm(p) => p.wait(Duration::from_millis(t.into())),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Hpet(_) => "HPET",
+            Self::Pm(_) => "ACPI PM timer",
+        }
+    }
+}
+
+struct LocalApic {
+    lvt_timer: Single<u32>,
+    initial_count: Single<u32>,
+    current_count: Single<u32>,
+    divide_config: Single<u32>,
+    /// The frequency of the Local APIC timer, or the TSC in the TSC-deadline mode.
+    frequency: u64,
+    time_slice: u64,
+    mode: Mode,
+}
+impl LocalApic {
+    /// Divides the bus clock by 16.
+    const DIVIDE_BY_16: u32 = 0b0011;
+
+    const MASKED: u32 = 1 << 16;
+    const TSC_DEADLINE: u32 = 1 << 18;
+
+    const CALIBRATION_MILLISECONDS: u32 = 50;
+
+    fn new() -> Self {
+        // SAFETY: These operations are safe because the addresses are the correct ones.
+        let lvt_timer = unsafe { crate::mem::accessor::new::<u32>(LVT_TIMER) };
+        let initial_count = unsafe { crate::mem::accessor::new::<u32>(INITIAL_COUNT) };
+        let current_count = unsafe { crate::mem::accessor::new::<u32>(CURRENT_COUNT) };
+        let divide_config = unsafe { crate::mem::accessor::new::<u32>(DIVIDE_CONFIG) };
+
+        let mode = if tsc::deadline_supported() {
+            Mode::TscDeadline
+        } else {
+            Mode::OneShot
+        };
+
+        Self {
+            lvt_timer,
+            initial_count,
+            current_count,
+            divide_config,
+            frequency: 0,
+            time_slice: 0,
+            mode,
+        }
+    }
+
+    fn init(&mut self, reference: &mut Reference) {
+        let (local_apic, tsc) = self.measure_frequencies(reference);
+
+        tsc::init_clock(tsc);
+
+        self.frequency = match self.mode {
+            Mode::OneShot => local_apic,
+            Mode::TscDeadline => tsc,
+        };
+
+        let hz = tick_rate();
+        self.time_slice = self.frequency / u64::from(hz);
+
+        info!(
+            "Local APIC timer: {} Hz, TSC: {} Hz (measured with the {}), mode: {:?}, tick: {} Hz",
+            local_apic,
+            tsc,
+            reference.name(),
+            self.mode,
+            hz
+        );
+
+        self.set_mode();
+    }
+
+    /// Returns the frequencies of the Local APIC timer and the TSC.
+    fn measure_frequencies(&mut self, reference: &mut Reference) -> (u64, u64) {
+        const MAX_COUNT: u32 = !0;
+
+        self.divide_config.write_volatile(Self::DIVIDE_BY_16);
+        self.lvt_timer
+            .write_volatile(Self::MASKED | u32::from(TIMER_VECTOR));
+        self.initial_count.write_volatile(MAX_COUNT);
+        let tsc_start = tsc::read();
+
+        reference.wait_milliseconds(Self::CALIBRATION_MILLISECONDS);
+
+        let local_apic = MAX_COUNT - self.current_count.read_volatile();
+        let tsc = tsc::read() - tsc_start;
+
+        self.initial_count.write_volatile(0);
+
+        let per_second = |n: u64| n * 1000 / u64::from(Self::CALIBRATION_MILLISECONDS);
+
+        (per_second(local_apic.into()), per_second(tsc))
+    }
+
+    fn set_mode(&mut self) {
+        let mut lvt = u32::from(TIMER_VECTOR);
+
+        if self.mode == Mode::TscDeadline {
+            lvt |= Self::TSC_DEADLINE;
+        }
+
+        self.divide_config.write_volatile(Self::DIVIDE_BY_16);
+        self.lvt_timer.write_volatile(lvt);
+    }
+
+    fn arm(&mut self) {
+        match self.mode {
+            Mode::OneShot => self
+                .initial_count
+                .write_volatile(self.time_slice.try_into().unwrap_or(u32::MAX)),
+            Mode::TscDeadline => write_tsc_deadline(tsc::read() + self.time_slice),
+        }
+    }
+
+    fn disarm(&mut self) {
+        match self.mode {
+            Mode::OneShot => self.initial_count.write_volatile(0),
+            // Writing 0 disarms the timer.
+            Mode::TscDeadline => write_tsc_deadline(0),
+        }
+    }
+}
+
+fn write_tsc_deadline(deadline: u64) {
+    let mut reg = IA32_TSC_DEADLINE;
+
+    // SAFETY: The Local APIC timer is in the TSC-deadline mode, and the deadline only decides when
+    // the timer fires.
+    unsafe { reg.write(deadline) }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Mode {
+    OneShot,
+    TscDeadline,
+}
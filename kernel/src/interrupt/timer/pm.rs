@@ -0,0 +1,113 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! The ACPI power management timer.
+
+use {
+    crate::mem::{accessor::Single, allocator},
+    acpi::{platform::address::AddressSpace, AcpiTables},
+    core::{convert::TryInto, hint, time::Duration},
+    x86_64::{instructions::port::PortReadOnly, PhysAddr},
+};
+
+pub(super) struct AcpiPm {
+    reader: Reader,
+    supported: SupportedBits,
+}
+impl AcpiPm {
+    pub(super) fn new(table: &AcpiTables<allocator::acpi::Mapper>) -> Self {
+        let pm_timer = table.platform_info().unwrap().pm_timer.unwrap();
+        let reader = match pm_timer.base.address_space {
+            AddressSpace::SystemMemory => Reader::Memory(MemoryReader::new(table)),
+            AddressSpace::SystemIo => Reader::Io(IoReader::new(table)),
+            _ => unreachable!(),
+        };
+
+        Self {
+            reader,
+            supported: if pm_timer.supports_32bit {
+                SupportedBits::Bits32
+            } else {
+                SupportedBits::Bits24
+            },
+        }
+    }
+
+    /// Busy-waits for `d`. The counter may wrap around any number of times while waiting.
+    pub(super) fn wait(&mut self, d: Duration) {
+        const FREQUENCY: u128 = 3_579_545;
+
+        let mask = match self.supported {
+            SupportedBits::Bits32 => u32::MAX,
+            SupportedBits::Bits24 => 0x00ff_ffff,
+        };
+
+        let ticks = FREQUENCY * d.as_nanos() / 1_000_000_000;
+
+        let mut elapsed = 0;
+        let mut last = self.reader.read();
+
+        while elapsed < ticks {
+            let now = self.reader.read();
+
+            elapsed += u128::from(now.wrapping_sub(last) & mask);
+            last = now;
+
+            hint::spin_loop();
+        }
+    }
+}
+
+enum Reader {
+    Io(IoReader),
+    Memory(MemoryReader),
+}
+impl Reader {
+    fn read(&mut self) -> u32 {
+        match self {
+            Reader::Io(i) => i.read(),
+            Reader::Memory(m) => m.read(),
+        }
+    }
+}
+
+struct IoReader {
+    // Initialization of the APIC timer is done in the kernel privilege. `syscall` must not be
+    // called.
+    port: PortReadOnly<u32>,
+}
+impl IoReader {
+    fn new(table: &AcpiTables<allocator::acpi::Mapper>) -> Self {
+        let b = table.platform_info().unwrap().pm_timer.unwrap().base;
+        Self {
+            port: PortReadOnly::new(b.address.try_into().unwrap()),
+        }
+    }
+
+    fn read(&mut self) -> u32 {
+        // SAFETY: This operation is safe as the `port` has an I/O address taken from `AcpiTables`.
+        unsafe { self.port.read() }
+    }
+}
+
+struct MemoryReader {
+    addr: Single<u32>,
+}
+impl MemoryReader {
+    fn new(table: &AcpiTables<allocator::acpi::Mapper>) -> Self {
+        let b = table.platform_info().unwrap().pm_timer.unwrap().base;
+        Self {
+            // SAFETY: This operation is safe as the address is generated from `AcpiTables`.
+            addr: unsafe { crate::mem::accessor::new(PhysAddr::new(b.address)) },
+        }
+    }
+
+    fn read(&mut self) -> u32 {
+        self.addr.read_volatile()
+    }
+}
+
+#[derive(Debug)]
+enum SupportedBits {
+    Bits32,
+    Bits24,
+}
@@ -0,0 +1,358 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! The CMOS real-time clock.
+//!
+//! The clock is read only once at boot. The century register is not standardized, so the year is
+//! assumed to be in the 21st century.
+
+use {
+    bit_field::BitField,
+    core::{fmt, hint, ops::RangeInclusive, time::Duration},
+    x86_64::instructions::port::Port,
+};
+
+const ADDRESS: u16 = 0x70;
+const DATA: u16 = 0x71;
+
+const SECONDS: u8 = 0x00;
+const MINUTES: u8 = 0x02;
+const HOURS: u8 = 0x04;
+const DAY: u8 = 0x07;
+const MONTH: u8 = 0x08;
+const YEAR: u8 = 0x09;
+const STATUS_A: u8 = 0x0a;
+const STATUS_B: u8 = 0x0b;
+
+/// The bits of `STATUS_A`.
+const UPDATE_IN_PROGRESS: usize = 7;
+
+/// The bits of `STATUS_B`.
+const HOUR_FORMAT_24: usize = 1;
+const BINARY_MODE: usize = 2;
+
+/// The PM bit of the hour register in the 12-hour format.
+const PM: usize = 7;
+
+/// Reads the current date and time in UTC.
+///
+/// Returns [`None`] if the registers hold an invalid date or time.
+pub(super) fn read() -> Option<DateTime> {
+    // The registers may change between the reads, so read them until the same values are read
+    // twice.
+    let mut last = read_raw();
+
+    loop {
+        let current = read_raw();
+
+        if current == last {
+            return current.decode(read_register(STATUS_B));
+        }
+
+        last = current;
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(super) struct DateTime {
+    year: u16,
+    month: u8,
+    day: u8,
+    hour: u8,
+    minute: u8,
+    second: u8,
+}
+impl DateTime {
+    /// Returns the time since the Unix epoch.
+    pub(super) fn since_unix_epoch(&self) -> Duration {
+        const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+        let days = days_since_unix_epoch(self.year.into(), self.month.into(), self.day.into());
+        let seconds =
+            u64::from(self.hour) * 60 * 60 + u64::from(self.minute) * 60 + u64::from(self.second);
+
+        Duration::from_secs(days * SECONDS_PER_DAY + seconds)
+    }
+}
+impl fmt::Display for DateTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+}
+
+/// The register values as they are, which may be in BCD and in the 12-hour format.
+#[derive(Copy, Clone, PartialEq, Eq)]
+struct Raw {
+    year: u8,
+    month: u8,
+    day: u8,
+    hour: u8,
+    minute: u8,
+    second: u8,
+}
+impl Raw {
+    /// Returns [`None`] if a field is out of its range or is not a valid BCD number.
+    fn decode(self, status_b: u8) -> Option<DateTime> {
+        let binary = status_b.get_bit(BINARY_MODE);
+        let decode = |v: u8, range: RangeInclusive<u8>| {
+            let v = if binary { Some(v) } else { from_bcd(v) };
+
+            v.filter(|v| range.contains(v))
+        };
+
+        let pm = self.hour.get_bit(PM);
+        let hour = self.hour & !(1 << PM);
+
+        let hour = if status_b.get_bit(HOUR_FORMAT_24) {
+            decode(hour, 0..=23)?
+        } else {
+            // 12 AM is 0 o'clock, and 12 PM is 12 o'clock.
+            decode(hour, 1..=12)? % 12 + if pm { 12 } else { 0 }
+        };
+
+        let year = 2000 + u16::from(decode(self.year, 0..=99)?);
+        let month = decode(self.month, 1..=12)?;
+
+        Some(DateTime {
+            year,
+            month,
+            day: decode(self.day, 1..=days_in_month(year, month))?,
+            hour,
+            minute: decode(self.minute, 0..=59)?,
+            second: decode(self.second, 0..=59)?,
+        })
+    }
+}
+
+fn read_raw() -> Raw {
+    while read_register(STATUS_A).get_bit(UPDATE_IN_PROGRESS) {
+        hint::spin_loop();
+    }
+
+    Raw {
+        year: read_register(YEAR),
+        month: read_register(MONTH),
+        day: read_register(DAY),
+        hour: read_register(HOURS),
+        minute: read_register(MINUTES),
+        second: read_register(SECONDS),
+    }
+}
+
+fn read_register(register: u8) -> u8 {
+    let mut address = Port::new(ADDRESS);
+    let mut data = Port::new(DATA);
+
+    // SAFETY: Reading the RTC registers has no side effect.
+    unsafe {
+        address.write(register);
+        data.read()
+    }
+}
+
+/// Returns [`None`] if `v` has a digit larger than 9.
+fn from_bcd(v: u8) -> Option<u8> {
+    let (tens, ones) = (v >> 4, v & 0xf);
+
+    (tens < 10 && ones < 10).then_some(tens * 10 + ones)
+}
+
+fn days_in_month(year: u16, month: u8) -> u8 {
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+
+    match month {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Converts a date in the Gregorian calendar to the number of days since 1970-01-01.
+///
+/// `month` and `day` must be valid, and the date must not be before the Unix epoch.
+///
+/// See: <https://howardhinnant.github.io/date_algorithms.html#days_from_civil>
+fn days_since_unix_epoch(year: u64, month: u64, day: u64) -> u64 {
+    // Count the years from March so that the leap day comes at the end of a year.
+    let year = if month <= 2 { year - 1 } else { year };
+
+    let era = year / 400;
+    let year_of_era = year % 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146_097 + day_of_era - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{
+            days_in_month, days_since_unix_epoch, from_bcd, DateTime, Raw, BINARY_MODE,
+            HOUR_FORMAT_24, PM,
+        },
+        core::time::Duration,
+    };
+
+    const BCD_24: u8 = 1 << HOUR_FORMAT_24;
+    const BCD_12: u8 = 0;
+    const BINARY_24: u8 = 1 << HOUR_FORMAT_24 | 1 << BINARY_MODE;
+    const BINARY_12: u8 = 1 << BINARY_MODE;
+
+    fn raw(year: u8, month: u8, day: u8, hour: u8, minute: u8, second: u8) -> Raw {
+        Raw {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+        }
+    }
+
+    fn date_time(year: u16, month: u8, day: u8, hour: u8, minute: u8, second: u8) -> DateTime {
+        DateTime {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+        }
+    }
+
+    #[test]
+    fn from_bcd_valid() {
+        assert_eq!(from_bcd(0x00), Some(0));
+        assert_eq!(from_bcd(0x09), Some(9));
+        assert_eq!(from_bcd(0x59), Some(59));
+        assert_eq!(from_bcd(0x99), Some(99));
+    }
+
+    #[test]
+    fn from_bcd_digit_over_9() {
+        assert_eq!(from_bcd(0x0a), None);
+        assert_eq!(from_bcd(0xa0), None);
+        assert_eq!(from_bcd(0xff), None);
+    }
+
+    #[test]
+    fn decode_bcd() {
+        let r = raw(0x24, 0x10, 0x19, 0x13, 0x45, 0x30);
+
+        assert_eq!(r.decode(BCD_24), Some(date_time(2024, 10, 19, 13, 45, 30)));
+    }
+
+    #[test]
+    fn decode_binary() {
+        let r = raw(24, 10, 19, 13, 45, 30);
+
+        assert_eq!(
+            r.decode(BINARY_24),
+            Some(date_time(2024, 10, 19, 13, 45, 30))
+        );
+    }
+
+    #[test]
+    fn decode_binary_as_bcd() {
+        // 0x1a is 26 in binary but not a BCD number.
+        let r = raw(24, 10, 0x1a, 13, 45, 30);
+
+        assert_eq!(
+            r.decode(BINARY_24),
+            Some(date_time(2024, 10, 26, 13, 45, 30))
+        );
+        assert_eq!(r.decode(BCD_24), None);
+    }
+
+    #[test]
+    fn decode_12_am() {
+        let r = raw(0x24, 0x10, 0x19, 0x12, 0x00, 0x00);
+
+        assert_eq!(r.decode(BCD_12), Some(date_time(2024, 10, 19, 0, 0, 0)));
+    }
+
+    #[test]
+    fn decode_12_pm() {
+        let r = raw(24, 10, 19, 12 | 1 << PM, 0, 0);
+
+        assert_eq!(r.decode(BINARY_12), Some(date_time(2024, 10, 19, 12, 0, 0)));
+    }
+
+    #[test]
+    fn decode_1_pm() {
+        let r = raw(0x24, 0x10, 0x19, 0x01 | 1 << PM, 0x00, 0x00);
+
+        assert_eq!(r.decode(BCD_12), Some(date_time(2024, 10, 19, 13, 0, 0)));
+    }
+
+    #[test]
+    fn decode_hour_out_of_range() {
+        assert_eq!(raw(0x24, 0x10, 0x19, 0x24, 0x00, 0x00).decode(BCD_24), None);
+        assert_eq!(raw(0x24, 0x10, 0x19, 0x00, 0x00, 0x00).decode(BCD_12), None);
+        assert_eq!(raw(0x24, 0x10, 0x19, 0x13, 0x00, 0x00).decode(BCD_12), None);
+    }
+
+    #[test]
+    fn decode_february_29() {
+        assert_eq!(
+            raw(0x24, 0x02, 0x29, 0x00, 0x00, 0x00).decode(BCD_24),
+            Some(date_time(2024, 2, 29, 0, 0, 0))
+        );
+        assert_eq!(raw(0x23, 0x02, 0x29, 0x00, 0x00, 0x00).decode(BCD_24), None);
+    }
+
+    #[test]
+    fn decode_invalid_date() {
+        assert_eq!(raw(0x24, 0x00, 0x01, 0x00, 0x00, 0x00).decode(BCD_24), None);
+        assert_eq!(raw(0x24, 0x13, 0x01, 0x00, 0x00, 0x00).decode(BCD_24), None);
+        assert_eq!(raw(0x24, 0x04, 0x31, 0x00, 0x00, 0x00).decode(BCD_24), None);
+        assert_eq!(raw(0x24, 0x04, 0x00, 0x00, 0x00, 0x00).decode(BCD_24), None);
+        assert_eq!(raw(0x24, 0x04, 0x01, 0x00, 0x60, 0x00).decode(BCD_24), None);
+        assert_eq!(raw(0x24, 0x04, 0x01, 0x00, 0x00, 0x60).decode(BCD_24), None);
+    }
+
+    #[test]
+    fn days_since_unix_epoch_known_dates() {
+        assert_eq!(days_since_unix_epoch(1970, 1, 1), 0);
+        assert_eq!(days_since_unix_epoch(2000, 3, 1), 11017);
+        assert_eq!(days_since_unix_epoch(2024, 2, 29), 19782);
+    }
+
+    #[test]
+    fn days_since_unix_epoch_is_consecutive() {
+        let mut expected = 0;
+
+        for year in 1970..2200 {
+            for month in 1..=12 {
+                for day in 1..=days_in_month(year, month) {
+                    let days = days_since_unix_epoch(year.into(), month.into(), day.into());
+
+                    assert_eq!(days, expected, "{year}-{month}-{day}");
+
+                    expected += 1;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn since_unix_epoch() {
+        assert_eq!(
+            date_time(1970, 1, 1, 0, 0, 0).since_unix_epoch(),
+            Duration::ZERO
+        );
+        assert_eq!(
+            date_time(2000, 1, 1, 0, 0, 0).since_unix_epoch(),
+            Duration::from_secs(946_684_800)
+        );
+        assert_eq!(
+            date_time(2024, 10, 19, 13, 45, 30).since_unix_epoch(),
+            Duration::from_secs(1_729_345_530)
+        );
+    }
+}
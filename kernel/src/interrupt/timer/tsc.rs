@@ -0,0 +1,55 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! The Time Stamp Counter.
+
+use {
+    conquer_once::spin::OnceCell,
+    core::{
+        arch::x86_64::{__cpuid, _rdtsc},
+        convert::TryInto,
+        time::Duration,
+    },
+};
+
+static CLOCK: OnceCell<u64> = OnceCell::uninit();
+
+/// Uses the TSC as the monotonic clock if it runs at `frequency` Hz constantly.
+pub(super) fn init_clock(frequency: u64) {
+    if invariant() {
+        CLOCK.init_once(|| frequency);
+    }
+}
+
+/// Returns the time since the processor was reset, or [`None`] if the TSC is not invariant.
+pub(super) fn now() -> Option<Duration> {
+    let frequency = *CLOCK.get()?;
+
+    let ns = u128::from(read()) * 1_000_000_000 / u128::from(frequency);
+
+    Some(Duration::from_nanos(ns.try_into().unwrap_or(u64::MAX)))
+}
+
+pub(super) fn read() -> u64 {
+    // SAFETY: Every x86_64 processor has the TSC.
+    unsafe { _rdtsc() }
+}
+
+pub(super) fn deadline_supported() -> bool {
+    const TSC_DEADLINE: u32 = 1 << 24;
+
+    // SAFETY: Every x86_64 processor supports the function `1`.
+    let r = unsafe { __cpuid(1) };
+
+    r.ecx & TSC_DEADLINE != 0
+}
+
+/// Returns `true` if the TSC runs at a constant rate regardless of the power states.
+fn invariant() -> bool {
+    const INVARIANT_TSC: u32 = 1 << 8;
+
+    // SAFETY: Every x86_64 processor supports the extended function `0x8000_0000`.
+    let max_leaf = unsafe { __cpuid(0x8000_0000) }.eax;
+
+    // SAFETY: The leaf is supported.
+    max_leaf >= 0x8000_0007 && unsafe { __cpuid(0x8000_0007) }.edx & INVARIANT_TSC != 0
+}
@@ -1,17 +1,24 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-#![no_std]
+#![cfg_attr(not(test), no_std)]
 #![feature(naked_functions, abi_x86_interrupt)]
 #![deny(clippy::pedantic, clippy::all, unsafe_op_in_unsafe_fn)]
+// The host tests cover only the code which does not touch the hardware, so most of the kernel is
+// unused in them.
+#![cfg_attr(test, allow(dead_code, unused_imports))]
 
 extern crate alloc;
 
 mod acpi;
+mod cmdline;
+mod console;
 mod fs;
 mod gdt;
 mod interrupt;
 mod mem;
+#[cfg(not(test))]
 mod panic;
+mod pipe;
 mod process;
 mod qemu;
 mod syscall;
@@ -24,7 +31,7 @@ mod tss;
 use {
     aligned_ptr::ptr,
     interrupt::{apic, idt, timer},
-    log::info,
+    log::{info, warn},
     terminal::vram,
     x86_64::instructions::interrupts,
 };
@@ -32,6 +39,7 @@ use {
 /// # Safety
 ///
 /// `boot_info` must point to the correct address.
+#[cfg(not(test))]
 #[no_mangle]
 pub unsafe extern "sysv64" fn os_main(boot_info: *mut boot_info::Info) -> ! {
     init(unsafe { ptr::get(boot_info) });
@@ -45,6 +53,7 @@ fn init(mut boot_info: boot_info::Info) {
 
     info!("Hello Ramen OS!");
 
+    fs::init(boot_info.initrd_bytes());
     fs::list_names();
 
     // SAFETY: At this point, `TSS` is never touched.
@@ -54,12 +63,20 @@ fn init(mut boot_info: boot_info::Info) {
 
     mem::init(boot_info.mem_map_mut());
 
+    init_log_levels();
+
     let acpi = unsafe { acpi::get(boot_info.rsdp()) };
 
     apic::io::init(&acpi);
 
+    console::init();
+
     timer::init(&acpi);
 
+    terminal::log::set_clock(timer::realtime);
+
+    acpi::init(&acpi);
+
     vram::print_info();
 
     syscall::init();
@@ -67,8 +84,28 @@ fn init(mut boot_info: boot_info::Info) {
     process::init();
 }
 
+/// Applies the `log=<target>=<level>,...` boot option. An entry without a target sets the default
+/// level.
+fn init_log_levels() {
+    let Some(spec) = cmdline::value("log") else {
+        return;
+    };
+
+    for entry in spec.split(',') {
+        let (target, level) = entry.rsplit_once('=').unwrap_or(("", entry));
+
+        match level.parse() {
+            Ok(level) => terminal::log::set_level(target, level),
+            Err(_) => warn!("Invalid log level: {}", entry),
+        }
+    }
+}
+
 fn idle() -> ! {
     loop {
         interrupts::enable_and_hlt();
+
+        // The timer may be stopped, so switch to the process woken by the interrupt now.
+        interrupts::without_interrupts(process::switch);
     }
 }
@@ -10,7 +10,7 @@ extern "C" {
     static HEAP_END: usize;
 }
 
-#[global_allocator]
+#[cfg_attr(not(test), global_allocator)]
 pub(crate) static ALLOCATOR: LockedHeap = LockedHeap::empty();
 
 // Using UEFI's `allocate_pages` doesn't work for allocating larger memory. It returns out of
@@ -1,9 +1,11 @@
 use {
-    super::paging,
+    super::{paging, shared, swap, vma},
     core::convert::TryFrom,
+    frame_manager::Constraints,
     os_units::NumOfPages,
+    syscalls::DmaConstraints,
     x86_64::{
-        structures::paging::{Page, PageSize, Size4KiB},
+        structures::paging::{Page, PageSize, PageTableFlags, Size4KiB},
         PhysAddr, VirtAddr,
     },
 };
@@ -15,11 +17,43 @@ pub(crate) mod phys;
 pub(crate) mod virt;
 
 pub(crate) fn allocate_pages_for_user(num_of_pages: NumOfPages<Size4KiB>) -> Option<VirtAddr> {
-    let phys_addr = allocate_phys(num_of_pages)?;
+    let phys_addr = allocate_phys_for_user(num_of_pages)?;
 
-    let virt_addr = super::map_pages_for_user(phys_addr, num_of_pages.as_bytes());
+    map_frames_for_user(
+        phys_addr,
+        num_of_pages,
+        user_rw_flags(),
+        vma::Backing::Anonymous,
+    )
+}
 
-    Some(virt_addr)
+/// Allocates non-executable pages for a user stack, placed in the stack region of the current
+/// address space.
+pub(crate) fn allocate_stack_for_user(num_of_pages: NumOfPages<Size4KiB>) -> Option<VirtAddr> {
+    let phys_addr = allocate_phys_for_user(num_of_pages)?;
+
+    map_frames_for_user(
+        phys_addr,
+        num_of_pages,
+        user_rw_flags() | PageTableFlags::NO_EXECUTE,
+        vma::Backing::Stack,
+    )
+}
+
+/// Allocates physically contiguous pages which satisfy `constraints` for DMA.
+pub(crate) fn allocate_dma_pages_for_user(
+    num_of_pages: NumOfPages<Size4KiB>,
+    constraints: &DmaConstraints,
+) -> Option<VirtAddr> {
+    let constraints = Constraints {
+        align: constraints.alignment(),
+        boundary: constraints.boundary_to_avoid(),
+        limit: constraints.address_limit(),
+    };
+
+    let phys_addr = phys::alloc_with_constraints(num_of_pages, &constraints)?;
+
+    map_frames_for_user(phys_addr, num_of_pages, user_rw_flags(), vma::Backing::Dma)
 }
 
 pub(crate) fn allocate_pages_for_kernel(num_of_pages: NumOfPages<Size4KiB>) -> Option<VirtAddr> {
@@ -35,19 +69,119 @@ pub(crate) fn deallocate_pages(virt: VirtAddr, num_of_pages: NumOfPages<Size4KiB
     deallocate_virt(virt, num_of_pages);
 }
 
+/// Frees the pages allocated by [`allocate_pages_for_user`] or [`allocate_dma_pages_for_user`].
+///
+/// Returns [`None`] if `virt..virt+num_of_pages` is not exactly an area allocated by them.
+pub(crate) fn deallocate_pages_for_user(
+    virt: VirtAddr,
+    num_of_pages: NumOfPages<Size4KiB>,
+) -> Option<()> {
+    if vma::find_exact(virt, num_of_pages, vma::Backing::Anonymous).is_some() {
+        deallocate_anonymous_pages(virt, num_of_pages);
+    } else if vma::find_exact(virt, num_of_pages, vma::Backing::Dma).is_some() {
+        deallocate_pages(virt, num_of_pages);
+    } else {
+        return None;
+    }
+
+    vma::remove(virt);
+
+    Some(())
+}
+
+/// Unmaps all user pages of the current address space, and frees the frames, the swap slots and
+/// the page tables.
+///
+/// The current process calls this function when it exits.
+pub(crate) fn free_current_user_space() {
+    for area in vma::take_all() {
+        let (start, num_of_pages) = (area.start(), area.num_of_pages());
+
+        match area.backing() {
+            vma::Backing::Anonymous | vma::Backing::Stack | vma::Backing::Elf => {
+                deallocate_anonymous_pages(start, num_of_pages);
+            }
+            vma::Backing::Dma => deallocate_pages(start, num_of_pages),
+            vma::Backing::Mmio => {
+                let len = u64::try_from(num_of_pages.as_bytes().as_usize()).unwrap();
+
+                paging::unmap_range(start, len).expect("Failed to unmap the memory-mapped I/O.");
+            }
+            vma::Backing::Shared(_) => {
+                shared::unmap(start).expect("The shared memory is not mapped.");
+            }
+        }
+    }
+
+    paging::free_unused_user_page_tables();
+}
+
+/// Frees the anonymous pages one by one because some of them may be swapped out.
+fn deallocate_anonymous_pages(virt: VirtAddr, num_of_pages: NumOfPages<Size4KiB>) {
+    let end = virt + u64::try_from(num_of_pages.as_bytes().as_usize()).unwrap();
+    let mut addr = virt;
+
+    while addr < end {
+        let page = Page::from_start_address(addr).expect("The address is not page-aligned.");
+
+        match paging::take_level_1_entry(page) {
+            Some(e) if e.flags().contains(PageTableFlags::PRESENT) => {
+                phys::free_range(e.addr(), NumOfPages::new(1));
+            }
+            Some(e) => swap::release(&e),
+            None => {
+                if let Some((phys_addr, size)) = paging::take_huge_page(addr) {
+                    let num_of_frames = usize::try_from(size / Size4KiB::SIZE).unwrap();
+
+                    phys::free_range(phys_addr, NumOfPages::new(num_of_frames));
+
+                    addr += size;
+
+                    continue;
+                }
+            }
+        }
+
+        addr += Size4KiB::SIZE;
+    }
+}
+
+fn map_frames_for_user(
+    phys_addr: PhysAddr,
+    num_of_pages: NumOfPages<Size4KiB>,
+    flags: PageTableFlags,
+    backing: vma::Backing,
+) -> Option<VirtAddr> {
+    let virt_addr =
+        super::map_pages_for_user_with_flags(phys_addr, num_of_pages.as_bytes(), flags, backing);
+
+    if virt_addr.is_none() {
+        phys::free(phys_addr);
+    }
+
+    virt_addr
+}
+
+fn user_rw_flags() -> PageTableFlags {
+    PageTableFlags::PRESENT | PageTableFlags::WRITABLE | PageTableFlags::USER_ACCESSIBLE
+}
+
 fn allocate_phys(num_of_pages: NumOfPages<Size4KiB>) -> Option<PhysAddr> {
     phys::alloc(num_of_pages)
 }
 
+/// Swaps out the pages of the current process if the physical memory is short.
+fn allocate_phys_for_user(num_of_pages: NumOfPages<Size4KiB>) -> Option<PhysAddr> {
+    swap::alloc_with_eviction(|| allocate_phys(num_of_pages))
+}
+
 fn deallocate_phys(virt: VirtAddr) {
     let phys = paging::translate_addr(virt).unwrap();
     phys::free(phys);
 }
 
 fn deallocate_virt(virt: VirtAddr, num_of_pages: NumOfPages<Size4KiB>) {
-    for i in 0..u64::try_from(num_of_pages.as_usize()).unwrap() {
-        let page = Page::<Size4KiB>::from_start_address(virt + Size4KiB::SIZE * i).unwrap();
+    let len = u64::try_from(num_of_pages.as_bytes().as_usize()).unwrap();
 
-        paging::unmap(page).unwrap();
-    }
+    paging::unmap_range(virt, len).unwrap();
 }
@@ -3,11 +3,11 @@
 use {
     boot_info::mem::MemoryDescriptor,
     core::ops::DerefMut,
-    frame_manager::FrameManager,
+    frame_manager::{Constraints, FrameManager, Stats},
     os_units::NumOfPages,
     spinning_top::Spinlock,
     x86_64::{
-        structures::paging::{FrameAllocator, FrameDeallocator, Size4KiB},
+        structures::paging::{FrameAllocator, FrameDeallocator, PageSize, Size2MiB, Size4KiB},
         PhysAddr,
     },
 };
@@ -23,14 +23,47 @@ pub(in super::super) fn allocator(
     lock_manager()
 }
 
-pub(super) fn alloc(num_of_pages: NumOfPages<Size4KiB>) -> Option<PhysAddr> {
-    lock_manager().deref_mut().alloc(num_of_pages)
+/// Allocates `num_of_pages` frames.
+///
+/// Runs of 2 MiB or more are aligned to 2 MiB if possible so that they can be mapped with huge
+/// pages.
+pub(in super::super) fn alloc(num_of_pages: NumOfPages<Size4KiB>) -> Option<PhysAddr> {
+    let mut manager = lock_manager();
+
+    if num_of_pages.as_bytes().as_usize() as u64 >= Size2MiB::SIZE {
+        if let Some(a) = manager.alloc_aligned(num_of_pages, Size2MiB::SIZE) {
+            return Some(a);
+        }
+    }
+
+    manager.alloc(num_of_pages)
 }
 
-pub(super) fn free(addr: PhysAddr) {
+pub(in super::super) fn alloc_with_constraints(
+    num_of_pages: NumOfPages<Size4KiB>,
+    constraints: &Constraints,
+) -> Option<PhysAddr> {
+    lock_manager().alloc_with_constraints(num_of_pages, constraints)
+}
+
+pub(in super::super) fn free(addr: PhysAddr) {
     lock_manager().deref_mut().free(addr);
 }
 
+/// Frees `num_of_pages` frames from `addr`, which may be a part of an allocation.
+pub(in super::super) fn free_range(addr: PhysAddr, num_of_pages: NumOfPages<Size4KiB>) {
+    lock_manager().free_range(addr, num_of_pages);
+}
+
+pub(crate) fn stats() -> Stats {
+    lock_manager().stats()
+}
+
+/// Returns [`None`] instead of panicking if the frame manager is locked.
+pub(crate) fn try_stats() -> Option<Stats> {
+    FRAME_MANAGER.try_lock().map(|m| m.stats())
+}
+
 fn lock_manager() -> impl DerefMut<Target = FrameManager> {
     FRAME_MANAGER
         .try_lock()
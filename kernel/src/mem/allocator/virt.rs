@@ -7,14 +7,20 @@ use {
     },
 };
 
+/// Searches `num_pages` free pages in `region` whose start address is aligned to `align`.
 pub(crate) fn search_free_addr_from(
     num_pages: NumOfPages<Size4KiB>,
     region: PageRange,
+    align: u64,
 ) -> Option<VirtAddr> {
     let mut cnt = 0;
     let mut start = None;
     for page in region {
         let addr = page.start_address();
+        if start.is_none() && !addr.is_aligned(align) {
+            continue;
+        }
+
         if available(addr) {
             if start.is_none() {
                 start = Some(addr);
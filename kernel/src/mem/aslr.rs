@@ -0,0 +1,54 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Address space layout randomization.
+//!
+//! The randomization is disabled if the `noaslr` boot option is given.
+
+use {
+    crate::cmdline,
+    conquer_once::spin::Lazy,
+    core::{
+        arch::x86_64::_rdtsc,
+        sync::atomic::{AtomicU64, Ordering},
+    },
+    x86_64::instructions::random::RdRand,
+};
+
+static ENABLED: Lazy<bool> = Lazy::new(|| !cmdline::has("noaslr"));
+
+pub(crate) fn enabled() -> bool {
+    *ENABLED
+}
+
+/// Returns a random multiple of `align` which is less than `limit`.
+///
+/// This function always returns 0 if the randomization is disabled.
+pub(crate) fn random_offset(limit: u64, align: u64) -> u64 {
+    if enabled() && limit >= align {
+        random() % (limit / align) * align
+    } else {
+        0
+    }
+}
+
+fn random() -> u64 {
+    RdRand::new()
+        .and_then(RdRand::get_u64)
+        .unwrap_or_else(random_from_tsc)
+}
+
+/// Generates a random number with SplitMix64 whose state is stirred by the TSC.
+fn random_from_tsc() -> u64 {
+    static STATE: AtomicU64 = AtomicU64::new(0);
+
+    // SAFETY: Every x86_64 processor has the `rdtsc` instruction.
+    let tsc = unsafe { _rdtsc() };
+
+    let mut z = STATE
+        .fetch_add(tsc | 1, Ordering::Relaxed)
+        .wrapping_add(0x9e37_79b9_7f4a_7c15);
+
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
@@ -1,9 +1,16 @@
-use elfloader::RelocationEntry;
-
 use {
-    super::paging,
+    super::{
+        allocator::{self, phys},
+        aslr, paging, user, vma,
+    },
     aligned_ptr::ptr,
-    elfloader::{ElfBinary, ElfLoader, ElfLoaderErr, Flags, LoadableHeaders, ProgramHeader, VAddr},
+    alloc::vec::Vec,
+    core::{convert::TryFrom, mem},
+    elfloader::{
+        arch::x86_64::RelocationTypes, ElfBinary, ElfLoader, ElfLoaderErr, Flags, LoadableHeaders,
+        ProgramHeader, RelocationEntry, RelocationType, VAddr,
+    },
+    os_units::{Bytes, NumOfPages},
     x86_64::{
         structures::paging::{
             mapper::{FlagUpdateError, MapToError},
@@ -12,36 +19,349 @@ use {
         },
         VirtAddr,
     },
+    xmas_elf::{
+        header,
+        program::{self, ProgramHeader as RawProgramHeader},
+        sections::SectionData,
+        symbol_table::{Binding, DynEntry64, Entry},
+        ElfFile,
+    },
 };
 
-pub(crate) unsafe fn map_to_current_address_space(binary: &[u8]) -> Result<VirtAddr, ElfLoaderErr> {
+/// The address where a position-independent executable is loaded if ASLR is disabled.
+pub(crate) const DEFAULT_LOAD_BASE: VirtAddr = VirtAddr::new_truncate(0x0040_0000);
+
+/// The range from [`DEFAULT_LOAD_BASE`] where a position-independent executable is randomly
+/// loaded.
+const LOAD_BASE_RANDOMIZATION_RANGE: u64 = 0x4000_0000;
+
+/// The type of the program header which tells whether the stack should be executable.
+const PT_GNU_STACK: u32 = 0x6474_e551;
+
+/// Returns [`DEFAULT_LOAD_BASE`] plus a random page-aligned offset.
+pub(crate) fn random_load_base() -> VirtAddr {
+    DEFAULT_LOAD_BASE + aslr::random_offset(LOAD_BASE_RANDOMIZATION_RANGE, Size4KiB::SIZE)
+}
+
+#[derive(Debug)]
+pub(crate) enum Error {
+    Parse(ElfLoaderErr),
+    UnalignedLoadBase(VirtAddr),
+    MapTo(MapToError<Size4KiB>),
+    FlagUpdate(FlagUpdateError),
+    UnsupportedRelocation(RelocationType),
+    MissingAddend { offset: u64 },
+    RelocationOutOfSegments { offset: u64 },
+    UndefinedSymbol(u32),
+    WritableAndExecutable { vaddr: VAddr },
+    ExecutableStack,
+    UnsupportedTlsAlignment(u64),
+}
+impl From<ElfLoaderErr> for Error {
+    fn from(e: ElfLoaderErr) -> Self {
+        Self::Parse(e)
+    }
+}
+impl From<&'static str> for Error {
+    fn from(e: &'static str) -> Self {
+        Self::Parse(ElfLoaderErr::ElfParser { source: e })
+    }
+}
+
+/// An executable mapped to an address space.
+#[derive(Clone, Debug)]
+pub(crate) struct Image {
+    pub(crate) entry: VirtAddr,
+
+    /// The thread pointer of the initial thread, which must be set to the FS base. This is zero if
+    /// the executable has no `PT_TLS` segment.
+    pub(crate) thread_pointer: VirtAddr,
+
+    segments: Vec<PageRange>,
+    tls: Option<Tls>,
+}
+impl Image {
+    /// Unmaps the image from the current address space and frees the frames of it.
+    ///
+    /// This must be called in the address space where the image is mapped.
+    pub(crate) fn free(self) {
+        free_segments(&self.segments);
+
+        if let Some(tls) = self.tls {
+            let r = allocator::deallocate_pages_for_user(tls.start, tls.num_of_pages);
+            r.expect("The TLS block is not allocated.");
+        }
+    }
+}
+
+/// The TLS block of the initial thread.
+#[derive(Copy, Clone, Debug)]
+struct Tls {
+    start: VirtAddr,
+    num_of_pages: NumOfPages<Size4KiB>,
+    thread_pointer: VirtAddr,
+}
+
+/// Maps `binary` to the current address space.
+///
+/// No page is mapped as both writable and executable. The user stack is always non-executable, so
+/// an executable which requests an executable stack with `PT_GNU_STACK` is rejected.
+///
+/// A position-independent executable is loaded at `base` and relocated. Other executables are
+/// loaded at the addresses written in their headers, and `base` is ignored.
+///
+/// On error, the pages mapped by this function are unmapped and freed.
+///
+/// # Safety
+///
+/// The caller must ensure that the ranges of the loadable segments are not used by the others.
+pub(crate) unsafe fn map_to_current_address_space(
+    binary: &[u8],
+    base: VirtAddr,
+) -> Result<Image, Error> {
+    // The loader writes the segments and the TLS block to the user pages.
+    user::with_user_access(|| {
+        // SAFETY: The caller must ensure the all safety requirements.
+        unsafe { load(binary, base) }
+    })
+}
+
+/// # Safety
+///
+/// See [`map_to_current_address_space`].
+unsafe fn load(binary: &[u8], base: VirtAddr) -> Result<Image, Error> {
     let elf = ElfBinary::new(binary)?;
+    let file = ElfFile::new(binary)?;
 
-    elf.load(&mut Loader)?;
+    if requests_executable_stack(&file)? {
+        return Err(Error::ExecutableStack);
+    }
+
+    let base = if is_pie(&file) {
+        base
+    } else {
+        VirtAddr::zero()
+    };
 
-    Ok(VirtAddr::new(elf.entry_point()))
+    if !base.is_aligned(Size4KiB::SIZE) {
+        return Err(Error::UnalignedLoadBase(base));
+    }
+
+    let mut loader = Loader::new(base, dynamic_symbols(&file)?);
+
+    let r = load_segments_and_tls(&elf, &file, &mut loader);
+
+    let segments: Vec<_> = loader.segments.iter().map(|(range, _)| *range).collect();
+
+    match r {
+        Ok(tls) => Ok(Image {
+            entry: base + elf.entry_point(),
+            thread_pointer: tls.map_or_else(VirtAddr::zero, |tls| tls.thread_pointer),
+            segments,
+            tls,
+        }),
+        Err(e) => {
+            free_segments(&segments);
+
+            Err(e)
+        }
+    }
+}
+
+/// Loads the segments and allocates the TLS block.
+///
+/// `loader` records the loaded segments even if this function fails.
+fn load_segments_and_tls(
+    elf: &ElfBinary<'_>,
+    file: &ElfFile<'_>,
+    loader: &mut Loader<'_>,
+) -> Result<Option<Tls>, Error> {
+    if let Err(e) = elf.load(loader) {
+        return Err(loader.error.take().unwrap_or(Error::Parse(e)));
+    }
+
+    // SAFETY: The segments are mapped by `loader`.
+    unsafe {
+        loader.apply_flags()?;
+    }
+
+    match tls_header(file)? {
+        // SAFETY: The initialization image is in a segment which `loader` loaded.
+        Some(tls) => unsafe { allocate_tls(loader.base, &tls).map(Some) },
+        None => Ok(None),
+    }
+}
+
+/// Unmaps the loaded segments and frees the frames mapped to them.
+fn free_segments(segments: &[PageRange]) {
+    for range in segments {
+        for page in *range {
+            match paging::take_level_1_entry(page) {
+                Some(e) if e.flags().contains(PageTableFlags::PRESENT) => {
+                    phys::free_range(e.addr(), NumOfPages::new(1));
+                }
+                _ => {}
+            }
+        }
+
+        vma::remove(range.start.start_address());
+    }
 }
 
-struct Loader;
-impl Loader {
-    fn allocate_for_header(header: ProgramHeader<'_>) -> Result<(), MapToError<Size4KiB>> {
-        let page_range = Self::page_range_from_header(header);
+fn requests_executable_stack(file: &ElfFile<'_>) -> Result<bool, Error> {
+    for header in file.program_iter() {
+        if header.get_type()? == program::Type::OsSpecific(PT_GNU_STACK) {
+            return Ok(header.flags().is_execute());
+        }
+    }
+
+    Ok(false)
+}
 
+fn tls_header<'a>(file: &ElfFile<'a>) -> Result<Option<RawProgramHeader<'a>>, Error> {
+    for header in file.program_iter() {
+        if header.get_type()? == program::Type::Tls {
+            return Ok(Some(header));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Allocates the TLS block of the initial thread.
+///
+/// The block follows the variant II layout of the x86-64 ABI. The TLS block is placed just below
+/// the thread pointer, and the thread pointer points to the thread control block whose first word
+/// is the thread pointer itself.
+///
+/// # Safety
+///
+/// The initialization image of `tls` must be loaded at `base`.
+unsafe fn allocate_tls(base: VirtAddr, tls: &RawProgramHeader<'_>) -> Result<Tls, Error> {
+    let align = tls.align().max(1);
+
+    if align > Size4KiB::SIZE || !align.is_power_of_two() {
+        return Err(Error::UnsupportedTlsAlignment(align));
+    }
+
+    // The linker computes the offsets of TLS variables from the thread pointer with this size.
+    let block_size = x86_64::align_up(tls.mem_size(), align);
+    let tcb_size = u64::try_from(mem::size_of::<u64>()).unwrap();
+
+    // The thread pointer must be aligned to `align` so that the TLS block below it is aligned, and
+    // to 8 bytes for the thread control block.
+    let tp_align = align.max(tcb_size);
+
+    // Extra space to align the thread pointer.
+    let bytes = Bytes::new(usize::try_from(block_size + tp_align + tcb_size).unwrap());
+    let num_of_pages = bytes.as_num_of_pages::<Size4KiB>();
+
+    let start = allocator::allocate_pages_for_user(num_of_pages)
+        .ok_or(Error::MapTo(MapToError::FrameAllocationFailed))?;
+
+    let thread_pointer = (start + block_size).align_up(tp_align);
+    let block_start = thread_pointer - block_size;
+
+    let image = base + tls.virtual_addr();
+    let image_size = usize::try_from(tls.file_size()).unwrap();
+
+    // SAFETY: The pages are newly allocated, and the caller ensures that the image is loaded.
+    unsafe {
+        core::ptr::write_bytes(
+            start.as_mut_ptr::<u8>(),
+            0,
+            num_of_pages.as_bytes().as_usize(),
+        );
+
+        core::ptr::copy_nonoverlapping(image.as_ptr::<u8>(), block_start.as_mut_ptr(), image_size);
+
+        core::ptr::write(thread_pointer.as_mut_ptr(), thread_pointer.as_u64());
+    }
+
+    Ok(Tls {
+        start,
+        num_of_pages,
+        thread_pointer,
+    })
+}
+
+fn is_pie(file: &ElfFile<'_>) -> bool {
+    file.header.pt2.type_().as_type() == header::Type::SharedObject
+}
+
+fn dynamic_symbols<'a>(file: &ElfFile<'a>) -> Result<&'a [DynEntry64], Error> {
+    let Some(section) = file.find_section_by_name(".dynsym") else {
+        return Ok(&[]);
+    };
+
+    match section.get_data(file)? {
+        SectionData::DynSymbolTable64(symbols) => Ok(symbols),
+        _ => Err(ElfLoaderErr::UnsupportedSectionData.into()),
+    }
+}
+
+struct Loader<'a> {
+    base: VirtAddr,
+    symbols: &'a [DynEntry64],
+    segments: Vec<(PageRange, Flags)>,
+    error: Option<Error>,
+}
+impl<'a> Loader<'a> {
+    fn new(base: VirtAddr, symbols: &'a [DynEntry64]) -> Self {
+        Self {
+            base,
+            symbols,
+            segments: Vec::new(),
+            error: None,
+        }
+    }
+
+    fn allocate_for_header(&mut self, header: ProgramHeader<'_>) -> Result<(), Error> {
+        if header.flags().is_write() && header.flags().is_execute() {
+            return Err(Error::WritableAndExecutable {
+                vaddr: header.virtual_addr(),
+            });
+        }
+
+        let page_range = self.page_range_from_header(header);
+
+        // The pages are kept writable until `apply_flags` is called.
         let flags =
             PageTableFlags::PRESENT | PageTableFlags::WRITABLE | PageTableFlags::USER_ACCESSIBLE;
 
-        paging::map_range_to_unused_phys_range(page_range, flags)
+        paging::map_range_to_unused_phys_range(page_range, flags).map_err(Error::MapTo)?;
+
+        let start = page_range.start.start_address();
+        let len = usize::try_from(page_range.end.start_address() - start).unwrap();
+
+        // The frames may contain the data of the other processes. This also zero-fills the part
+        // from `file_size` to `mem_size`, that is, `.bss`.
+        //
+        // SAFETY: The pages are mapped as writable above.
+        unsafe {
+            core::ptr::write_bytes(start.as_mut_ptr::<u8>(), 0, len);
+        }
+
+        self.segments.push((page_range, header.flags()));
+
+        vma::add(vma::Area::new(
+            page_range,
+            Self::elf_flags_to_page_table_flags(header.flags()),
+            vma::Backing::Elf,
+        ));
+
+        Ok(())
     }
 
-    fn page_range_from_header<S: PageSize>(header: ProgramHeader<'_>) -> PageRange<S> {
-        Self::page_range_from_vaddr_and_len(
+    fn page_range_from_header<S: PageSize>(&self, header: ProgramHeader<'_>) -> PageRange<S> {
+        self.page_range_from_vaddr_and_len(
             header.virtual_addr(),
             header.mem_size().try_into().unwrap(),
         )
     }
 
-    fn page_range_from_vaddr_and_len<S: PageSize>(base: VAddr, len: usize) -> PageRange<S> {
-        let start = VirtAddr::new(base);
+    fn page_range_from_vaddr_and_len<S: PageSize>(&self, vaddr: VAddr, len: usize) -> PageRange<S> {
+        let start = self.base + vaddr;
 
         let end = start + len;
         let end = end.align_up(S::SIZE);
@@ -52,12 +372,92 @@ impl Loader {
         PageRange { start, end }
     }
 
-    unsafe fn update_flags(page_range: PageRange, flags: Flags) -> Result<(), FlagUpdateError> {
-        unsafe {
-            paging::update_flags_for_range(page_range, Self::elf_flags_to_page_table_flags(flags))
+    /// Sets the final flags of the loaded segments.
+    ///
+    /// The segments are kept writable until the relocations are applied.
+    unsafe fn apply_flags(&self) -> Result<(), Error> {
+        for (page_range, flags) in &self.segments {
+            unsafe {
+                paging::update_flags_for_range(
+                    *page_range,
+                    Self::elf_flags_to_page_table_flags(*flags),
+                )
+                .map_err(Error::FlagUpdate)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the address of the 8 bytes at `offset` from the load base, which must be in a
+    /// loaded segment.
+    fn relocation_target(&self, offset: u64) -> Result<VirtAddr, Error> {
+        let start = self
+            .base
+            .as_u64()
+            .checked_add(offset)
+            .and_then(|a| VirtAddr::try_new(a).ok());
+        let end = start.and_then(|s| s.as_u64().checked_add(8));
+
+        let in_segments = |start: VirtAddr, end: u64| {
+            self.segments.iter().any(|(range, _)| {
+                range.start.start_address() <= start && end <= range.end.start_address().as_u64()
+            })
+        };
+
+        start
+            .zip(end)
+            .filter(|&(start, end)| in_segments(start, end))
+            .map(|(start, _)| start)
+            .ok_or(Error::RelocationOutOfSegments { offset })
+    }
+
+    fn relocated_value(&self, entry: RelocationEntry) -> Result<u64, Error> {
+        let offset = entry.offset;
+        let addend = || entry.addend.ok_or(Error::MissingAddend { offset });
+
+        match entry.rtype {
+            RelocationType::x86_64(RelocationTypes::R_AMD64_RELATIVE) => {
+                Ok(self.base.as_u64().wrapping_add(addend()?))
+            }
+            RelocationType::x86_64(
+                RelocationTypes::R_AMD64_GLOB_DAT | RelocationTypes::R_AMD64_JUMP_SLOT,
+            ) => self.symbol_value(entry.symbol),
+            RelocationType::x86_64(RelocationTypes::R_AMD64_64) => {
+                Ok(self.symbol_value(entry.symbol)?.wrapping_add(addend()?))
+            }
+            t => Err(Error::UnsupportedRelocation(t)),
+        }
+    }
+
+    fn symbol_value(&self, index: u32) -> Result<u64, Error> {
+        let symbol = self
+            .symbols
+            .get(usize::try_from(index).unwrap())
+            .ok_or(Error::UndefinedSymbol(index))?;
+
+        if symbol.shndx() != 0 {
+            Ok(self.base.as_u64().wrapping_add(symbol.value()))
+        } else if symbol.get_binding()? == Binding::Weak {
+            Ok(0)
+        } else {
+            Err(Error::UndefinedSymbol(index))
         }
     }
 
+    fn fail(&mut self, e: Error) -> ElfLoaderErr {
+        // `map_to_current_address_space` returns `self.error` instead of the returned value.
+        let r = if matches!(e, Error::MapTo(MapToError::FrameAllocationFailed)) {
+            ElfLoaderErr::OutOfMemory
+        } else {
+            ElfLoaderErr::UnsupportedRelocationEntry
+        };
+
+        self.error = Some(e);
+
+        r
+    }
+
     fn elf_flags_to_page_table_flags(flags: Flags) -> PageTableFlags {
         let mut page_table_flags = PageTableFlags::PRESENT | PageTableFlags::USER_ACCESSIBLE;
 
@@ -72,10 +472,12 @@ impl Loader {
         page_table_flags
     }
 }
-impl ElfLoader for Loader {
+impl ElfLoader for Loader<'_> {
     fn allocate(&mut self, load_headers: LoadableHeaders<'_, '_>) -> Result<(), ElfLoaderErr> {
         for header in load_headers {
-            Self::allocate_for_header(header).expect("Failed to allocate memory.");
+            if let Err(e) = self.allocate_for_header(header) {
+                return Err(self.fail(e));
+            }
         }
 
         Ok(())
@@ -86,24 +488,33 @@ impl ElfLoader for Loader {
     /// **This method is actually an unsafe one.**
     ///
     /// The caller must ensure that the addresses `base..(base + region.len())` must be allocated.
-    fn load(&mut self, flags: Flags, base: VAddr, region: &[u8]) -> Result<(), ElfLoaderErr> {
-        let base = VirtAddr::new(base);
+    fn load(&mut self, _: Flags, base: VAddr, region: &[u8]) -> Result<(), ElfLoaderErr> {
+        let start = self.base + base;
 
         // SAFETY: The caller ensures that the addresses `base..(base+region.len())` are allocated.
         unsafe {
-            ptr::copy_nonoverlapping(region.as_ptr(), base.as_mut_ptr(), region.len());
+            ptr::copy_nonoverlapping(region.as_ptr(), start.as_mut_ptr(), region.len());
         }
 
-        let page_range = Self::page_range_from_vaddr_and_len(base.as_u64(), region.len());
+        Ok(())
+    }
+
+    fn relocate(&mut self, entry: RelocationEntry) -> Result<(), ElfLoaderErr> {
+        let r = self
+            .relocation_target(entry.offset)
+            .and_then(|addr| self.relocated_value(entry).map(|v| (addr, v)));
 
+        let (addr, value) = match r {
+            Ok(r) => r,
+            Err(e) => return Err(self.fail(e)),
+        };
+
+        // SAFETY: `addr` is in a segment which `allocate` mapped as writable. `apply_flags`
+        // changes the flags after all relocations are done.
         unsafe {
-            Self::update_flags(page_range, flags).expect("Failed to update flags.");
+            core::ptr::write_unaligned(addr.as_mut_ptr(), value);
         }
 
         Ok(())
     }
-
-    fn relocate(&mut self, _: RelocationEntry) -> Result<(), ElfLoaderErr> {
-        todo!()
-    }
 }
@@ -2,11 +2,12 @@ use {
     allocator::virt,
     boot_info::mem::MemoryDescriptor,
     core::convert::TryFrom,
-    os_units::Bytes,
-    predefined_mmap::{KERNEL_ADDR, STACK_BASE},
+    log::info,
+    os_units::{Bytes, NumOfPages},
+    predefined_mmap::STACK_BASE,
     x86_64::{
         structures::paging::{
-            page::PageRange, Page, PageSize, PageTableFlags, PhysFrame, Size4KiB,
+            mapper::MapToError, page::PageRange, Page, PageSize, PageTableFlags, Size4KiB,
         },
         PhysAddr, VirtAddr,
     },
@@ -14,74 +15,143 @@ use {
 
 pub(crate) mod accessor;
 pub(crate) mod allocator;
+pub(crate) mod aslr;
 pub(crate) mod elf;
 pub(crate) mod paging;
+pub(crate) mod protection;
+pub(crate) mod shared;
+pub(crate) mod swap;
+pub(crate) mod user;
+pub(crate) mod vma;
 
 pub(super) fn init(mem_map: &[MemoryDescriptor]) {
     allocator::heap::init();
     allocator::phys::init(mem_map);
     paging::mark_pages_as_unused();
+    protection::init();
+
+    info!(
+        "ASLR is {}.",
+        if aslr::enabled() {
+            "enabled"
+        } else {
+            "disabled"
+        }
+    );
 }
 
-pub(super) fn map_pages_for_user(start: PhysAddr, object_size: Bytes) -> VirtAddr {
-    map_pages_from(
+pub(super) fn map_pages_for_user(start: PhysAddr, object_size: Bytes) -> Option<VirtAddr> {
+    map_pages_for_user_with_flags(
         start,
         object_size,
-        PageRange {
-            start: Page::from_start_address(VirtAddr::new(0x1000)).unwrap(),
-            end: Page::from_start_address(KERNEL_ADDR).unwrap(),
-        },
+        PageTableFlags::PRESENT | PageTableFlags::WRITABLE | PageTableFlags::USER_ACCESSIBLE,
+        vma::Backing::Mmio,
     )
 }
 
+pub(super) fn map_pages_for_user_with_flags(
+    start: PhysAddr,
+    object_size: Bytes,
+    flags: PageTableFlags,
+    backing: vma::Backing,
+) -> Option<VirtAddr> {
+    let num_pages = num_of_pages_covering(start.as_u64(), object_size)?;
+    let align = alignment_for(start, num_pages);
+
+    let range = vma::reserve(num_pages, align, flags, backing)?;
+    let virt = range.start.start_address();
+
+    let r = map_pages_to(start, virt, num_pages, flags);
+
+    if r.is_err() {
+        vma::remove(virt);
+    }
+
+    r.ok()
+}
+
 pub(super) fn map_pages_for_kernel(start: PhysAddr, object_size: Bytes) -> VirtAddr {
-    map_pages_from(
+    let region = PageRange {
+        start: Page::from_start_address(STACK_BASE).unwrap(),
+        end: Page::from_start_address(VirtAddr::new(0xffff_ffff_ffff_f000)).unwrap(),
+    };
+
+    let num_pages = num_of_pages_covering(start.as_u64(), object_size);
+    let num_pages = num_pages.expect("The object exceeds the address space.");
+    let align = alignment_for(start, num_pages);
+
+    let virt = virt::search_free_addr_from(num_pages, region, align)
+        .expect("OOM during creating a new accessor to a register.");
+
+    map_pages_to(
         start,
-        object_size,
-        PageRange {
-            start: Page::from_start_address(STACK_BASE).unwrap(),
-            end: Page::from_start_address(VirtAddr::new(0xffff_ffff_ffff_f000)).unwrap(),
-        },
+        virt,
+        num_pages,
+        PageTableFlags::PRESENT | PageTableFlags::WRITABLE,
     )
+    .expect("Failed to map pages for the kernel.")
 }
 
 pub(super) fn unmap_pages(start: VirtAddr, object_size: Bytes) {
     let start_frame_addr = start.align_down(Size4KiB::SIZE);
-    let end_frame_addr = (start + object_size.as_usize()).align_down(Size4KiB::SIZE);
+    let num_pages = num_of_pages_covering(start.as_u64(), object_size);
+    let num_pages = num_pages.expect("The object exceeds the address space.");
 
-    let num_pages = Bytes::new(usize::try_from(end_frame_addr - start_frame_addr).unwrap())
-        .as_num_of_pages::<Size4KiB>();
+    paging::unmap_range(start_frame_addr, num_pages.as_bytes().as_usize() as u64).unwrap();
+}
 
-    for i in 0..num_pages.as_usize() {
-        let page =
-            Page::<Size4KiB>::containing_address(start_frame_addr + Size4KiB::SIZE * i as u64);
+/// Unmaps the pages mapped by [`map_pages_for_user`].
+///
+/// Returns [`None`] if `start..start+object_size` is not exactly an area mapped by
+/// [`map_pages_for_user`].
+pub(super) fn unmap_pages_for_user(start: VirtAddr, object_size: Bytes) -> Option<()> {
+    let page_start = start.align_down(Size4KiB::SIZE);
+    let num_pages = num_of_pages_covering(start.as_u64(), object_size)?;
 
-        paging::unmap(page).unwrap();
-    }
-}
+    vma::find_exact(page_start, num_pages, vma::Backing::Mmio)?;
 
-fn map_pages_from(start: PhysAddr, object_size: Bytes, region: PageRange) -> VirtAddr {
-    let start_frame_addr = start.align_down(Size4KiB::SIZE);
-    let end_frame_addr = (start + object_size.as_usize()).align_down(Size4KiB::SIZE);
+    unmap_pages(start, object_size);
 
-    let num_pages = Bytes::new(usize::try_from(end_frame_addr - start_frame_addr).unwrap() + 1)
-        .as_num_of_pages::<Size4KiB>();
+    vma::remove(page_start);
 
-    let virt = virt::search_free_addr_from(num_pages, region)
-        .expect("OOM during creating a new accessor to a register.");
+    Some(())
+}
 
-    for i in 0..num_pages.as_usize() {
-        let page = Page::<Size4KiB>::containing_address(virt + Size4KiB::SIZE * i as u64);
-        let frame = PhysFrame::containing_address(start_frame_addr + Size4KiB::SIZE * i as u64);
-        let flag =
-            PageTableFlags::PRESENT | PageTableFlags::WRITABLE | PageTableFlags::USER_ACCESSIBLE;
+fn map_pages_to(
+    start: PhysAddr,
+    virt: VirtAddr,
+    num_pages: NumOfPages<Size4KiB>,
+    flags: PageTableFlags,
+) -> Result<VirtAddr, MapToError<Size4KiB>> {
+    let start_frame_addr = start.align_down(Size4KiB::SIZE);
+    let len = num_pages.as_bytes().as_usize() as u64;
 
-        unsafe {
-            paging::map_to(page, frame, flag).unwrap();
-        }
+    unsafe {
+        paging::map_range_to(virt, start_frame_addr, len, flags)?;
     }
 
     let page_offset = start.as_u64() % Size4KiB::SIZE;
 
-    virt + page_offset
+    Ok(virt + page_offset)
+}
+
+/// Returns the alignment of the virtual address to map `num_pages` pages from `start` with the
+/// largest pages.
+fn alignment_for(start: PhysAddr, num_pages: NumOfPages<Size4KiB>) -> u64 {
+    let len = num_pages.as_bytes().as_usize() as u64;
+
+    paging::largest_page_size(VirtAddr::zero(), start.align_down(Size4KiB::SIZE), len)
+}
+
+/// Returns the number of pages which the `object_size` bytes from `start` span.
+///
+/// Returns [`None`] if the object exceeds the end of the address space.
+fn num_of_pages_covering(start: u64, object_size: Bytes) -> Option<NumOfPages<Size4KiB>> {
+    let first = x86_64::align_down(start, Size4KiB::SIZE);
+    let end = start
+        .checked_add(u64::try_from(object_size.as_usize()).ok()?)?
+        .checked_add(Size4KiB::SIZE - 1)?;
+    let end = x86_64::align_down(end, Size4KiB::SIZE);
+
+    Some(Bytes::new(usize::try_from(end - first).ok()?).as_num_of_pages())
 }
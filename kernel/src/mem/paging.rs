@@ -1,13 +1,21 @@
 use {
-    crate::mem::allocator::phys,
+    crate::mem::{allocator::phys, protection, user::USER_END},
     conquer_once::spin::Lazy,
+    core::{arch::x86_64::__cpuid, convert::TryFrom},
+    os_units::NumOfPages,
     predefined_mmap::RECUR_PML4_ADDR,
     spinning_top::Spinlock,
     x86_64::{
+        instructions::tlb,
+        registers::control::Cr3,
         structures::paging::{
-            mapper::{FlagUpdateError, MapToError, MapperFlush, UnmapError},
-            page::PageRange,
-            FrameAllocator, Mapper, Page, PageTable, PageTableFlags, PhysFrame, RecursivePageTable,
+            mapper::{
+                CleanUp, FlagUpdateError, MapToError, MappedFrame, MapperFlush, TranslateResult,
+                UnmapError,
+            },
+            page::{PageRange, PageRangeInclusive},
+            FrameAllocator, FrameDeallocator, Mapper, Page, PageSize, PageTable, PageTableEntry,
+            PageTableFlags, PageTableIndex, PhysFrame, RecursivePageTable, Size1GiB, Size2MiB,
             Size4KiB, Translate,
         },
         PhysAddr, VirtAddr,
@@ -30,12 +38,19 @@ pub(crate) fn mark_pages_as_unused() {
     }
 }
 
+/// Maps each page of `page_range` to a newly allocated frame.
+///
+/// On error, the pages mapped by this call are unmapped and their frames are freed.
 pub(crate) fn map_range_to_unused_phys_range(
     page_range: PageRange,
     flags: PageTableFlags,
 ) -> Result<(), MapToError<Size4KiB>> {
-    for p in page_range {
-        map_to_unused(p, flags)?;
+    for (i, p) in page_range.enumerate() {
+        if let Err(e) = map_to_unused(p, flags) {
+            page_range.take(i).for_each(unmap_and_free);
+
+            return Err(e);
+        }
     }
 
     Ok(())
@@ -73,20 +88,196 @@ pub(crate) unsafe fn map_to(
     // SAFETY: The caller must ensure the all safety requirements.
     unsafe {
         PML4.lock()
-            .map_to(page, frame, flags, &mut *phys::allocator())
+            .map_to(page, frame, supported(flags), &mut *phys::allocator())
             .map(MapperFlush::flush)
     }
 }
 
-pub(crate) fn unmap(page: Page) -> Result<PhysFrame, UnmapError> {
-    PML4.lock().unmap(page).map(|(frame, flush)| {
-        flush.flush();
-        frame
-    })
+/// Maps `len` bytes from `virt` to the physical memory from `phys`.
+///
+/// 2 MiB and 1 GiB pages are used where both addresses are aligned suitably and the remaining
+/// length is large enough.
+///
+/// On error, the pages mapped by this call are unmapped.
+///
+/// # Safety
+///
+/// Refer to [`x86_64::structures::paging::Mapper`].
+pub(crate) unsafe fn map_range_to(
+    virt: VirtAddr,
+    phys: PhysAddr,
+    len: u64,
+    flags: PageTableFlags,
+) -> Result<(), MapToError<Size4KiB>> {
+    let mut offset = 0;
+
+    while offset < len {
+        let (v, p) = (virt + offset, phys + offset);
+
+        // SAFETY: The caller must ensure the all safety requirements.
+        match unsafe { map_largest_page_to(v, p, len - offset, flags) } {
+            Ok(size) => offset += size,
+            Err(e) => {
+                unmap_range(virt, offset).expect("Failed to unmap the pages just mapped.");
+
+                return Err(e);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Unmaps `len` bytes from `virt`, including the huge pages.
+pub(crate) fn unmap_range(virt: VirtAddr, len: u64) -> Result<(), UnmapError> {
+    let mut offset = 0;
+
+    while offset < len {
+        offset += unmap_containing_page(virt + offset)?;
+    }
+
+    Ok(())
+}
+
+/// Returns the largest page size which can map `len` bytes from `virt` to `phys`.
+pub(crate) fn largest_page_size(virt: VirtAddr, phys: PhysAddr, len: u64) -> u64 {
+    let fits = |size| virt.is_aligned(size) && phys.is_aligned(size) && len >= size;
+
+    if gigabyte_pages_supported() && fits(Size1GiB::SIZE) {
+        Size1GiB::SIZE
+    } else if fits(Size2MiB::SIZE) {
+        Size2MiB::SIZE
+    } else {
+        Size4KiB::SIZE
+    }
+}
+
+/// Returns the number of the user pages mapped in the address space of `pml4`, counting a huge page
+/// as the 4 KiB pages it spans. The pages whose start address satisfies `exclude` are not counted.
+///
+/// Returns [`None`] if the page tables are locked.
+pub(crate) fn try_count_user_pages(
+    pml4: PhysFrame,
+    exclude: impl Fn(VirtAddr) -> bool,
+) -> Option<NumOfPages<Size4KiB>> {
+    // The lock prevents the others from modifying the page tables while the recursive entry points
+    // to those of `pml4`.
+    let _pml4 = PML4.try_lock()?;
+
+    let (current, flags) = Cr3::read();
+
+    // SAFETY: Every address space shares the kernel half, so the kernel keeps running after
+    // switching the address space.
+    unsafe {
+        Cr3::write(pml4, flags);
+    }
+
+    let n = count_user_pages(&exclude);
+
+    // SAFETY: Ditto.
+    unsafe {
+        Cr3::write(current, flags);
+    }
+
+    Some(NumOfPages::new(usize::try_from(n).unwrap()))
 }
 
+/// Frees the page tables which map no page in the user half of the current address space.
+pub(crate) fn free_unused_user_page_tables() {
+    let range = PageRangeInclusive {
+        start: Page::containing_address(VirtAddr::zero()),
+        end: Page::containing_address(VirtAddr::new(USER_END - 1)),
+    };
+
+    // SAFETY: Each page table is used only once in the address space, and only the tables which
+    // map no page are freed.
+    unsafe {
+        PML4.lock()
+            .clean_up_addr_range(range, &mut *phys::allocator());
+    }
+}
+
+/// Returns [`None`] if `a` is not mapped or the page is swapped out.
 pub(crate) fn translate_addr(a: VirtAddr) -> Option<PhysAddr> {
-    PML4.lock().translate_addr(a)
+    match PML4.lock().translate(a) {
+        TranslateResult::Mapped {
+            frame,
+            offset,
+            flags,
+        } if flags.contains(PageTableFlags::PRESENT) => Some(frame.start_address() + offset),
+        _ => None,
+    }
+}
+
+/// Calls `f` with the level 1 entry for `page` and flushes the TLB entry of `page`.
+///
+/// Unlike [`Mapper`], this function gives access to the entries which are not present, such as the
+/// entries of the swapped out pages.
+///
+/// This function returns [`None`] if there is no level 1 table for `page`, e.g., `page` is in a
+/// huge page.
+pub(crate) fn with_level_1_entry<T>(
+    page: Page,
+    f: impl FnOnce(&mut PageTableEntry) -> T,
+) -> Option<T> {
+    let _pml4 = PML4.lock();
+
+    let r = recursive_index();
+
+    // SAFETY: The recursive entry maps the page tables to these addresses, and the PML4 lock
+    // prevents the others from modifying them.
+    let table = |p: Page| unsafe { &mut *p.start_address().as_mut_ptr::<PageTable>() };
+    let points_to_table = |e: &PageTableEntry| {
+        e.flags().contains(PageTableFlags::PRESENT)
+            && !e.flags().contains(PageTableFlags::HUGE_PAGE)
+    };
+
+    let (p4, p3, p2) = (page.p4_index(), page.p3_index(), page.p2_index());
+
+    let p4_entry = &table(Page::from_page_table_indices(r, r, r, r))[p4];
+    let p3_entry = points_to_table(p4_entry)
+        .then(|| &table(Page::from_page_table_indices(r, r, r, p4))[p3])?;
+    let p2_entry = points_to_table(p3_entry)
+        .then(|| &table(Page::from_page_table_indices(r, r, p4, p3))[p2])?;
+
+    if !points_to_table(p2_entry) {
+        return None;
+    }
+
+    let p1_entry = &mut table(Page::from_page_table_indices(r, p4, p3, p2))[page.p1_index()];
+
+    let v = f(p1_entry);
+
+    tlb::flush(page.start_address());
+
+    Some(v)
+}
+
+/// Clears the level 1 entry for `page` and returns the old entry.
+pub(crate) fn take_level_1_entry(page: Page) -> Option<PageTableEntry> {
+    with_level_1_entry(page, |e| {
+        let old = e.clone();
+        e.set_unused();
+        old
+    })
+}
+
+/// Unmaps the 2 MiB or 1 GiB page starting at `virt`, and returns the start address and the size of
+/// the frame.
+///
+/// Returns [`None`] if `virt` is not the start of a huge page.
+pub(crate) fn take_huge_page(virt: VirtAddr) -> Option<(PhysAddr, u64)> {
+    let TranslateResult::Mapped { frame, .. } = PML4.lock().translate(virt) else {
+        return None;
+    };
+
+    let size = match frame {
+        MappedFrame::Size1GiB(_) => unmap_page::<Size1GiB>(virt),
+        MappedFrame::Size2MiB(_) => unmap_page::<Size2MiB>(virt),
+        MappedFrame::Size4KiB(_) => return None,
+    };
+
+    size.ok().map(|size| (frame.start_address(), size))
 }
 
 pub(crate) unsafe fn update_flags(
@@ -95,7 +286,7 @@ pub(crate) unsafe fn update_flags(
 ) -> Result<(), FlagUpdateError> {
     unsafe {
         PML4.lock()
-            .update_flags(page, flags)
+            .update_flags(page, supported(flags))
             .map(MapperFlush::flush)
     }
 }
@@ -103,3 +294,200 @@ pub(crate) unsafe fn update_flags(
 pub(crate) fn level_4_table() -> PageTable {
     PML4.lock().level_4_table().clone()
 }
+
+/// Maps a page of the largest possible size and returns the size of the mapped page.
+///
+/// A huge page cannot be mapped where a page table for the smaller pages is left, even if the
+/// table is empty. In that case, this function falls back to the smaller pages.
+///
+/// # Safety
+///
+/// Refer to [`x86_64::structures::paging::Mapper`].
+unsafe fn map_largest_page_to(
+    virt: VirtAddr,
+    phys: PhysAddr,
+    len: u64,
+    flags: PageTableFlags,
+) -> Result<u64, MapToError<Size4KiB>> {
+    let size = largest_page_size(virt, phys, len);
+
+    // SAFETY: The caller must ensure the all safety requirements.
+    unsafe {
+        if size == Size1GiB::SIZE {
+            match map_page_to::<Size1GiB>(virt, phys, flags) {
+                Err(MapToError::PageAlreadyMapped(_)) => {}
+                r => return r.map(|()| Size1GiB::SIZE),
+            }
+        }
+
+        if size >= Size2MiB::SIZE {
+            match map_page_to::<Size2MiB>(virt, phys, flags) {
+                Err(MapToError::PageAlreadyMapped(_)) => {}
+                r => return r.map(|()| Size2MiB::SIZE),
+            }
+        }
+
+        map_page_to::<Size4KiB>(virt, phys, flags)?;
+    }
+
+    Ok(Size4KiB::SIZE)
+}
+
+/// # Safety
+///
+/// Refer to [`x86_64::structures::paging::Mapper`].
+unsafe fn map_page_to<S: PageSize>(
+    virt: VirtAddr,
+    phys: PhysAddr,
+    flags: PageTableFlags,
+) -> Result<(), MapToError<Size4KiB>>
+where
+    for<'a> RecursivePageTable<'a>: Mapper<S>,
+{
+    let page = Page::<S>::from_start_address(virt).expect("The page is not aligned.");
+    let frame = PhysFrame::<S>::from_start_address(phys).expect("The frame is not aligned.");
+
+    // SAFETY: The caller must ensure the all safety requirements.
+    unsafe {
+        PML4.lock()
+            .map_to(page, frame, supported(flags), &mut *phys::allocator())
+            .map(MapperFlush::flush)
+            .map_err(into_4kib_error)
+    }
+}
+
+fn unmap_containing_page(virt: VirtAddr) -> Result<u64, UnmapError> {
+    let frame = match PML4.lock().translate(virt) {
+        TranslateResult::Mapped { frame, .. } => frame,
+        TranslateResult::NotMapped => return Err(UnmapError::PageNotMapped),
+        TranslateResult::InvalidFrameAddress(a) => return Err(UnmapError::InvalidFrameAddress(a)),
+    };
+
+    match frame {
+        MappedFrame::Size1GiB(_) => unmap_page::<Size1GiB>(virt),
+        MappedFrame::Size2MiB(_) => unmap_page::<Size2MiB>(virt),
+        MappedFrame::Size4KiB(_) => unmap_page::<Size4KiB>(virt),
+    }
+}
+
+fn unmap_page<S: PageSize>(virt: VirtAddr) -> Result<u64, UnmapError>
+where
+    for<'a> RecursivePageTable<'a>: Mapper<S>,
+{
+    let page = Page::<S>::containing_address(virt);
+
+    PML4.lock().unmap(page).map(|(_, flush)| {
+        flush.flush();
+        S::SIZE
+    })
+}
+
+fn unmap_and_free(page: Page) {
+    let (frame, flush) = PML4.lock().unmap(page).expect("The page is not mapped.");
+
+    flush.flush();
+
+    // SAFETY: The frame is unmapped above, and no one else uses it.
+    unsafe {
+        phys::allocator().deallocate_frame(frame);
+    }
+}
+
+fn into_4kib_error<S: PageSize>(e: MapToError<S>) -> MapToError<Size4KiB> {
+    match e {
+        MapToError::FrameAllocationFailed => MapToError::FrameAllocationFailed,
+        MapToError::ParentEntryHugePage => MapToError::ParentEntryHugePage,
+        MapToError::PageAlreadyMapped(f) => {
+            MapToError::PageAlreadyMapped(PhysFrame::containing_address(f.start_address()))
+        }
+    }
+}
+
+/// Removes the flags which the processor does not support.
+///
+/// Setting `NO_EXECUTE` without EFER.NXE causes a page fault with a reserved bit violation.
+fn supported(flags: PageTableFlags) -> PageTableFlags {
+    if protection::no_execute_enabled() {
+        flags
+    } else {
+        flags - PageTableFlags::NO_EXECUTE
+    }
+}
+
+/// Counts the pages mapped in the user half of the current address space with the recursive entry.
+fn count_user_pages(exclude: &impl Fn(VirtAddr) -> bool) -> u64 {
+    let r = recursive_index();
+
+    // SAFETY: The recursive entry maps the page tables to these addresses, and the caller holds
+    // the PML4 lock.
+    let table = |p: Page| unsafe { &*p.start_address().as_ptr::<PageTable>() };
+    let present = |e: &PageTableEntry| e.flags().contains(PageTableFlags::PRESENT);
+    let huge = |e: &PageTableEntry| e.flags().contains(PageTableFlags::HUGE_PAGE);
+    let indices = || (0..512).map(PageTableIndex::new);
+    let addr = |p4, p3, p2, p1| Page::from_page_table_indices(p4, p3, p2, p1).start_address();
+    let zero = PageTableIndex::new(0);
+
+    let mut n = 0;
+
+    let last_user_p4 = Page::<Size4KiB>::containing_address(VirtAddr::new(USER_END - 1));
+    let last_user_p4 = last_user_p4.p4_index();
+
+    for p4 in (0..=u16::from(last_user_p4)).map(PageTableIndex::new) {
+        if !present(&table(Page::from_page_table_indices(r, r, r, r))[p4]) {
+            continue;
+        }
+
+        for p3 in indices() {
+            let e3 = &table(Page::from_page_table_indices(r, r, r, p4))[p3];
+
+            if !present(e3) {
+                continue;
+            }
+
+            if huge(e3) {
+                if !exclude(addr(p4, p3, zero, zero)) {
+                    n += Size1GiB::SIZE / Size4KiB::SIZE;
+                }
+
+                continue;
+            }
+
+            for p2 in indices() {
+                let e2 = &table(Page::from_page_table_indices(r, r, p4, p3))[p2];
+
+                if !present(e2) {
+                    continue;
+                }
+
+                if huge(e2) {
+                    if !exclude(addr(p4, p3, p2, zero)) {
+                        n += Size2MiB::SIZE / Size4KiB::SIZE;
+                    }
+
+                    continue;
+                }
+
+                let p1_table = table(Page::from_page_table_indices(r, p4, p3, p2));
+
+                n += indices()
+                    .filter(|p1| present(&p1_table[*p1]) && !exclude(addr(p4, p3, p2, *p1)))
+                    .count() as u64;
+            }
+        }
+    }
+
+    n
+}
+
+fn recursive_index() -> PageTableIndex {
+    Page::<Size4KiB>::containing_address(RECUR_PML4_ADDR).p4_index()
+}
+
+fn gigabyte_pages_supported() -> bool {
+    const PDPE1GB: u32 = 1 << 26;
+
+    // SAFETY: Every x86_64 processor supports the extended function `0x8000_0001`.
+    let r = unsafe { __cpuid(0x8000_0001) };
+
+    r.edx & PDPE1GB != 0
+}
@@ -0,0 +1,96 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Hardware memory protections: NX, SMEP and SMAP.
+//!
+//! Each protection is enabled only if CPUID reports it.
+
+use {
+    core::arch::x86_64::{__cpuid, __cpuid_count, CpuidResult},
+    log::info,
+    x86_64::registers::{
+        control::{Cr4, Cr4Flags},
+        model_specific::{Efer, EferFlags},
+    },
+};
+
+pub(super) fn init() {
+    if no_execute_supported() {
+        // SAFETY: The processor supports the NX bit, and no page tables set reserved bits.
+        unsafe { Efer::update(|f| f.insert(EferFlags::NO_EXECUTE_ENABLE)) }
+    }
+
+    let mut flags = Cr4Flags::empty();
+    flags.set(
+        Cr4Flags::SUPERVISOR_MODE_EXECUTION_PROTECTION,
+        smep_supported(),
+    );
+    flags.set(
+        Cr4Flags::SUPERVISOR_MODE_ACCESS_PREVENTION,
+        smap_supported(),
+    );
+
+    // SAFETY: No kernel page is accessible from user mode, and every access to the user memory
+    // goes through `mem::user`.
+    unsafe { Cr4::update(|f| f.insert(flags)) }
+
+    info!(
+        "NX: {}, SMEP: {}, SMAP: {}",
+        state(no_execute_enabled()),
+        state(Cr4::read().contains(Cr4Flags::SUPERVISOR_MODE_EXECUTION_PROTECTION)),
+        state(smap_enabled())
+    );
+}
+
+pub(crate) fn no_execute_enabled() -> bool {
+    Efer::read().contains(EferFlags::NO_EXECUTE_ENABLE)
+}
+
+pub(crate) fn smap_enabled() -> bool {
+    Cr4::read().contains(Cr4Flags::SUPERVISOR_MODE_ACCESS_PREVENTION)
+}
+
+fn state(enabled: bool) -> &'static str {
+    if enabled {
+        "enabled"
+    } else {
+        "unsupported"
+    }
+}
+
+fn no_execute_supported() -> bool {
+    const NX: u32 = 1 << 20;
+
+    // SAFETY: Every x86_64 processor supports the extended function `0x8000_0001`.
+    let r = unsafe { __cpuid(0x8000_0001) };
+
+    r.edx & NX != 0
+}
+
+fn smep_supported() -> bool {
+    const SMEP: u32 = 1 << 7;
+
+    structured_extended_features().ebx & SMEP != 0
+}
+
+fn smap_supported() -> bool {
+    const SMAP: u32 = 1 << 20;
+
+    structured_extended_features().ebx & SMAP != 0
+}
+
+fn structured_extended_features() -> CpuidResult {
+    // SAFETY: Every x86_64 processor supports the function `0`.
+    let max_leaf = unsafe { __cpuid(0) }.eax;
+
+    if max_leaf >= 7 {
+        // SAFETY: The leaf `7` is supported.
+        unsafe { __cpuid_count(7, 0) }
+    } else {
+        CpuidResult {
+            eax: 0,
+            ebx: 0,
+            ecx: 0,
+            edx: 0,
+        }
+    }
+}
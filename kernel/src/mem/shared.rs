@@ -0,0 +1,250 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use {
+    super::{allocator::phys, paging, vma},
+    crate::process::{self, Pid},
+    alloc::{collections::BTreeMap, vec::Vec},
+    conquer_once::spin::Lazy,
+    core::{convert::TryFrom, ops::DerefMut, ptr},
+    os_units::{Bytes, NumOfPages},
+    spinning_top::Spinlock,
+    syscalls::{Permission, SharedMemoryHandle},
+    x86_64::{
+        structures::paging::{PageTableFlags, Size4KiB},
+        PhysAddr, VirtAddr,
+    },
+};
+
+static OBJECTS: Lazy<Spinlock<Objects>> = Lazy::new(|| Spinlock::new(Objects::new()));
+
+/// Creates an object which only the current process can map until it grants the access to others.
+pub(crate) fn create(bytes: Bytes) -> Option<SharedMemoryHandle> {
+    let num_of_pages = bytes.as_num_of_pages::<Size4KiB>();
+
+    if num_of_pages.as_usize() == 0 {
+        return None;
+    }
+
+    let phys = phys::alloc(num_of_pages)?;
+
+    fill_with_zero(phys, num_of_pages);
+
+    let creator = process::scheduler::current_pid();
+
+    Some(lock_objects().add(Object::new(phys, num_of_pages, creator)))
+}
+
+/// Allows the process `pid` to map the object `handle`.
+///
+/// Returns [`None`] if `handle` is invalid or the current process did not create it.
+pub(crate) fn grant(handle: SharedMemoryHandle, pid: Pid) -> Option<()> {
+    let current = process::scheduler::current_pid();
+
+    lock_objects().grant(handle, current, pid)
+}
+
+/// Maps the object `handle` to the current process.
+///
+/// Returns [`None`] if `handle` is invalid, the current process is neither the creator nor granted
+/// the access, or the system is out of memory.
+pub(crate) fn map(handle: SharedMemoryHandle, permission: Permission) -> Option<VirtAddr> {
+    let pid = process::scheduler::current_pid();
+
+    lock_objects().map(handle, permission, pid)
+}
+
+pub(crate) fn unmap(virt: VirtAddr) -> Option<()> {
+    let pid = process::scheduler::current_pid();
+
+    lock_objects().unmap(virt, pid)
+}
+
+/// Frees the objects which the process `pid` created and which are not mapped anywhere.
+///
+/// This must be called after `pid` unmaps all of its objects. The mapped objects are freed when the
+/// last mapping to them is unmapped.
+pub(crate) fn release(pid: Pid) {
+    lock_objects().release(pid);
+}
+
+fn fill_with_zero(phys: PhysAddr, num_of_pages: NumOfPages<Size4KiB>) {
+    let bytes = num_of_pages.as_bytes();
+    let virt = super::map_pages_for_kernel(phys, bytes);
+
+    // SAFETY: `virt..virt+bytes` is mapped to the newly allocated frames which no one else uses.
+    unsafe {
+        ptr::write_bytes(virt.as_mut_ptr::<u8>(), 0, bytes.as_usize());
+    }
+
+    super::unmap_pages(virt, bytes);
+}
+
+fn lock_objects() -> impl DerefMut<Target = Objects> {
+    OBJECTS
+        .try_lock()
+        .expect("Failed to lock the shared memory objects.")
+}
+
+struct Objects {
+    objects: BTreeMap<SharedMemoryHandle, Object>,
+    next_handle: SharedMemoryHandle,
+}
+impl Objects {
+    fn new() -> Self {
+        Self {
+            objects: BTreeMap::new(),
+            // 0 is used to tell the failure of creating an object.
+            next_handle: 1,
+        }
+    }
+
+    fn add(&mut self, object: Object) -> SharedMemoryHandle {
+        let handle = self.next_handle;
+
+        self.next_handle += 1;
+
+        let r = self.objects.insert(handle, object);
+        assert!(r.is_none(), "Duplicated shared memory handle {handle}.");
+
+        handle
+    }
+
+    fn grant(&mut self, handle: SharedMemoryHandle, creator: Pid, pid: Pid) -> Option<()> {
+        let object = self.objects.get_mut(&handle)?;
+
+        if object.creator != creator {
+            return None;
+        }
+
+        if !object.grantees.contains(&pid) {
+            object.grantees.push(pid);
+        }
+
+        Some(())
+    }
+
+    fn map(
+        &mut self,
+        handle: SharedMemoryHandle,
+        permission: Permission,
+        pid: Pid,
+    ) -> Option<VirtAddr> {
+        let object = self
+            .objects
+            .get_mut(&handle)
+            .filter(|o| o.may_be_mapped_by(pid))?;
+
+        object.map(handle, permission, pid)
+    }
+
+    fn unmap(&mut self, virt: VirtAddr, pid: Pid) -> Option<()> {
+        let handle = self.find_mapping(virt, pid)?;
+
+        let object = self.objects.get_mut(&handle)?;
+        object.unmap(virt, pid);
+
+        if object.mappings.is_empty() {
+            let object = self.objects.remove(&handle);
+            object.expect("The object is already removed.").free();
+        }
+
+        Some(())
+    }
+
+    fn release(&mut self, pid: Pid) {
+        let unused = self
+            .objects
+            .iter()
+            .filter(|(_, o)| o.creator == pid && o.mappings.is_empty())
+            .map(|(h, _)| *h)
+            .collect::<Vec<_>>();
+
+        for handle in unused {
+            let object = self.objects.remove(&handle);
+            object.expect("The object is already removed.").free();
+        }
+    }
+
+    fn find_mapping(&self, virt: VirtAddr, pid: Pid) -> Option<SharedMemoryHandle> {
+        self.objects
+            .iter()
+            .find(|(_, o)| o.is_mapped_at(virt, pid))
+            .map(|(h, _)| *h)
+    }
+}
+
+struct Object {
+    phys: PhysAddr,
+    num_of_pages: NumOfPages<Size4KiB>,
+    mappings: Vec<Mapping>,
+    creator: Pid,
+    /// The processes which the creator allowed to map this object.
+    grantees: Vec<Pid>,
+}
+impl Object {
+    fn new(phys: PhysAddr, num_of_pages: NumOfPages<Size4KiB>, creator: Pid) -> Self {
+        Self {
+            phys,
+            num_of_pages,
+            mappings: Vec::new(),
+            creator,
+            grantees: Vec::new(),
+        }
+    }
+
+    fn may_be_mapped_by(&self, pid: Pid) -> bool {
+        self.creator == pid || self.grantees.contains(&pid)
+    }
+
+    fn map(
+        &mut self,
+        handle: SharedMemoryHandle,
+        permission: Permission,
+        pid: Pid,
+    ) -> Option<VirtAddr> {
+        let virt = super::map_pages_for_user_with_flags(
+            self.phys,
+            self.num_of_pages.as_bytes(),
+            permission_to_flags(permission),
+            vma::Backing::Shared(handle),
+        )?;
+
+        self.mappings.push(Mapping { pid, virt });
+
+        Some(virt)
+    }
+
+    fn unmap(&mut self, virt: VirtAddr, pid: Pid) {
+        let len = u64::try_from(self.num_of_pages.as_bytes().as_usize()).unwrap();
+
+        paging::unmap_range(virt, len).expect("Failed to unmap shared pages.");
+
+        vma::remove(virt);
+
+        self.mappings.retain(|m| *m != Mapping { pid, virt });
+    }
+
+    fn is_mapped_at(&self, virt: VirtAddr, pid: Pid) -> bool {
+        self.mappings.contains(&Mapping { pid, virt })
+    }
+
+    fn free(self) {
+        phys::free(self.phys);
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct Mapping {
+    pid: Pid,
+    virt: VirtAddr,
+}
+
+fn permission_to_flags(permission: Permission) -> PageTableFlags {
+    let flags =
+        PageTableFlags::PRESENT | PageTableFlags::USER_ACCESSIBLE | PageTableFlags::NO_EXECUTE;
+
+    match permission {
+        Permission::ReadOnly => flags,
+        Permission::ReadWrite => flags | PageTableFlags::WRITABLE,
+    }
+}
@@ -0,0 +1,374 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Swapping out the anonymous user pages to a block device.
+//!
+//! A user process which serves a block device registers itself as the swap device, and the kernel
+//! asks it to read and write pages with IPC. The swap area is given by the
+//! `swap=<first block>,<number of blocks>` boot option, and swapping is disabled without it.
+//!
+//! Only the pages of the current process are swapped out because the page tables of the other
+//! processes are not mapped. The pages of the swap device itself are never swapped out, as the
+//! device could not swap them in.
+
+use {
+    super::{allocator::phys, paging, vma},
+    crate::{
+        cmdline,
+        process::{self, ipc, Pid},
+    },
+    alloc::{vec, vec::Vec},
+    core::{convert::TryFrom, mem::MaybeUninit, ops::DerefMut},
+    frame_manager::Constraints,
+    log::{info, warn},
+    message::Message,
+    os_units::NumOfPages,
+    spinning_top::Spinlock,
+    syscalls::SwapOperation,
+    x86_64::{
+        structures::paging::{Page, PageSize, PageTableEntry, PageTableFlags, Size4KiB},
+        PhysAddr, VirtAddr,
+    },
+};
+
+/// A level 1 entry with this flag and without `PRESENT` holds the index of a swap slot in its
+/// address field.
+const SWAPPED: PageTableFlags = PageTableFlags::BIT_9;
+
+static SWAP: Spinlock<Option<Swap>> = Spinlock::new(None);
+
+/// The maximum number of pages swapped out for one allocation.
+///
+/// Each eviction frees only one page, which may not help an allocation of contiguous pages at all.
+const MAX_EVICTIONS: usize = 16;
+
+/// The reasons why a page cannot be swapped in.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Error {
+    /// A page is swapped out, but no swap device is registered.
+    NoDevice,
+    OutOfMemory,
+    /// The swap device failed to read the page.
+    Transfer,
+}
+
+/// Registers the current process as the swap device.
+///
+/// Returns `false` if swapping is disabled, `block_size` is not supported, or a device is already
+/// registered. The caller must check that the current process is privileged.
+pub(crate) fn register(block_size: u64, address_limit: u64) -> bool {
+    let mut swap = lock();
+
+    if swap.is_some() {
+        return false;
+    }
+
+    let Some((first_block, num_of_blocks)) = area() else {
+        return false;
+    };
+
+    if !block_size.is_power_of_two() || block_size > Size4KiB::SIZE {
+        warn!("Swap: unsupported block size {}.", block_size);
+        return false;
+    }
+
+    let blocks_per_page = Size4KiB::SIZE / block_size;
+    let num_of_slots = usize::try_from(num_of_blocks / blocks_per_page).unwrap();
+
+    info!("Swap: {} pages from block {}.", num_of_slots, first_block);
+
+    *swap = Some(Swap {
+        device: process::scheduler::current_pid(),
+        first_block,
+        blocks_per_page,
+        address_limit,
+        slots: vec![false; num_of_slots],
+        hand: VirtAddr::zero(),
+    });
+
+    true
+}
+
+/// Calls `alloc`, swapping out the pages of the current process until it succeeds.
+///
+/// Returns [`None`] if `alloc` still fails after [`MAX_EVICTIONS`] pages are swapped out or no
+/// page can be swapped out.
+pub(crate) fn alloc_with_eviction<T>(mut alloc: impl FnMut() -> Option<T>) -> Option<T> {
+    for _ in 0..MAX_EVICTIONS {
+        if let Some(t) = alloc() {
+            return Some(t);
+        }
+
+        if !evict() {
+            return None;
+        }
+    }
+
+    alloc()
+}
+
+/// Swaps out a cold anonymous page of the current process.
+///
+/// Returns `false` if no page can be swapped out.
+fn evict() -> bool {
+    let Some(eviction) = prepare_eviction() else {
+        return false;
+    };
+
+    let Eviction {
+        device,
+        page,
+        frame,
+        slot,
+        first_block,
+        num_of_blocks,
+    } = eviction;
+
+    if !transfer(
+        device,
+        SwapOperation::Write,
+        first_block,
+        num_of_blocks,
+        frame,
+    ) {
+        warn!("Swap: failed to write {:?}.", page);
+        release_slot(slot);
+        return false;
+    }
+
+    paging::with_level_1_entry(page, |e| {
+        e.set_addr(PhysAddr::new(slot_to_u64(slot) << 12), SWAPPED);
+    })
+    .expect("The evicted page is not mapped.");
+
+    phys::free_range(frame, NumOfPages::new(1));
+
+    true
+}
+
+/// Swaps in the page containing `addr` of the current process.
+///
+/// Returns `Ok(false)` if the page is not swapped out.
+///
+/// # Errors
+///
+/// This function returns an error if the page is swapped out but cannot be swapped in.
+pub(crate) fn swap_in(addr: VirtAddr) -> Result<bool, Error> {
+    let page = Page::containing_address(addr);
+
+    let Some(slot) = paging::with_level_1_entry(page, |e| swapped_slot(e)).flatten() else {
+        return Ok(false);
+    };
+
+    let (device, first_block, num_of_blocks, address_limit) = {
+        let swap = lock();
+        let swap = swap.as_ref().ok_or(Error::NoDevice)?;
+
+        (
+            swap.device,
+            swap.first_block_of(slot),
+            swap.blocks_per_page,
+            swap.address_limit,
+        )
+    };
+
+    let frame = alloc_frame(address_limit).ok_or(Error::OutOfMemory)?;
+
+    if !transfer(
+        device,
+        SwapOperation::Read,
+        first_block,
+        num_of_blocks,
+        frame,
+    ) {
+        phys::free_range(frame, NumOfPages::new(1));
+
+        return Err(Error::Transfer);
+    }
+
+    let area = vma::find(addr).expect("The swapped out page does not belong to any area.");
+
+    paging::with_level_1_entry(page, |e| e.set_addr(frame, area.flags()))
+        .expect("The swapped out page is not mapped.");
+
+    release_slot(slot);
+
+    Ok(true)
+}
+
+/// Frees the swap slot which `entry` holds, if any.
+pub(crate) fn release(entry: &PageTableEntry) {
+    if let Some(slot) = swapped_slot(entry) {
+        release_slot(slot);
+    }
+}
+
+fn prepare_eviction() -> Option<Eviction> {
+    let mut swap = lock();
+    let swap = swap.as_mut()?;
+
+    if process::scheduler::current_pid() == swap.device {
+        return None;
+    }
+
+    let slot = swap.slots.iter().position(|used| !used)?;
+    let (page, frame) = swap.find_victim()?;
+
+    swap.slots[slot] = true;
+
+    Some(Eviction {
+        device: swap.device,
+        page,
+        frame,
+        slot,
+        first_block: swap.first_block_of(slot),
+        num_of_blocks: swap.blocks_per_page,
+    })
+}
+
+fn alloc_frame(address_limit: u64) -> Option<PhysAddr> {
+    let constraints = Constraints {
+        align: Size4KiB::SIZE,
+        boundary: None,
+        limit: address_limit,
+    };
+
+    alloc_with_eviction(|| phys::alloc_with_constraints(NumOfPages::new(1), &constraints))
+}
+
+/// Asks the swap device to transfer a page and returns `true` on success.
+fn transfer(
+    device: Pid,
+    operation: SwapOperation,
+    first_block: u64,
+    num_of_blocks: u64,
+    frame: PhysAddr,
+) -> bool {
+    let body = message::Body(
+        operation as u64,
+        first_block,
+        num_of_blocks,
+        frame.as_u64(),
+        0,
+    );
+    let request = Message::new(message::Header::default(), body);
+
+    if !ipc::send(VirtAddr::from_ptr(&request), device) {
+        return false;
+    }
+
+    let mut reply = MaybeUninit::<Message>::uninit();
+
+    if !ipc::receive_from(VirtAddr::from_ptr(reply.as_mut_ptr()), device) {
+        return false;
+    }
+
+    // SAFETY: `receive_from` writes the reply.
+    unsafe { reply.assume_init() }.body.0 == 0
+}
+
+fn release_slot(slot: usize) {
+    if let Some(swap) = lock().as_mut() {
+        swap.slots[slot] = false;
+    }
+}
+
+fn lock() -> impl DerefMut<Target = Option<Swap>> {
+    SWAP.try_lock().expect("Failed to lock the swap state.")
+}
+
+fn swapped_slot(entry: &PageTableEntry) -> Option<usize> {
+    let flags = entry.flags();
+
+    (flags.contains(SWAPPED) && !flags.contains(PageTableFlags::PRESENT))
+        .then(|| usize::try_from(entry.addr().as_u64() >> 12).unwrap())
+}
+
+fn slot_to_u64(slot: usize) -> u64 {
+    u64::try_from(slot).unwrap()
+}
+
+/// Parses the `swap=<first block>,<number of blocks>` boot option.
+fn area() -> Option<(u64, u64)> {
+    let v = cmdline::value("swap")?;
+
+    let parsed = v
+        .split_once(',')
+        .and_then(|(first, num)| Some((first.parse().ok()?, num.parse().ok()?)));
+
+    if parsed.is_none() {
+        warn!("Swap: invalid option `swap={}`.", v);
+    }
+
+    parsed
+}
+
+struct Swap {
+    device: Pid,
+    first_block: u64,
+    blocks_per_page: u64,
+    address_limit: u64,
+    /// `true` if the slot is used.
+    slots: Vec<bool>,
+    /// The page which was evicted last time. The search for the next victim starts after this.
+    hand: VirtAddr,
+}
+impl Swap {
+    fn first_block_of(&self, slot: usize) -> u64 {
+        self.first_block + slot_to_u64(slot) * self.blocks_per_page
+    }
+
+    /// Finds a page to evict with the clock algorithm.
+    ///
+    /// A page which was accessed recently gets the second chance, and its accessed flag is cleared.
+    fn find_victim(&mut self) -> Option<(Page, PhysAddr)> {
+        let ranges = vma::ranges_of(vma::Backing::Anonymous);
+        let pages = || ranges.iter().copied().flatten();
+
+        let num_of_pages = pages().count();
+        let start = pages()
+            .position(|p| p.start_address() > self.hand)
+            .unwrap_or(0);
+
+        let victim = pages()
+            .chain(pages())
+            .chain(pages())
+            .skip(start)
+            .take(num_of_pages * 2)
+            .find_map(|page| {
+                paging::with_level_1_entry(page, |e| self.second_chance(e))
+                    .flatten()
+                    .map(|frame| (page, frame))
+            })?;
+
+        self.hand = victim.0.start_address();
+
+        Some(victim)
+    }
+
+    /// Returns the frame of the page if the page can be evicted.
+    fn second_chance(&self, entry: &mut PageTableEntry) -> Option<PhysAddr> {
+        let flags = entry.flags();
+
+        if !flags.contains(PageTableFlags::PRESENT) {
+            return None;
+        }
+
+        if flags.contains(PageTableFlags::ACCESSED) {
+            entry.set_flags(flags - PageTableFlags::ACCESSED);
+            return None;
+        }
+
+        let frame = entry.addr();
+
+        (frame.as_u64() + Size4KiB::SIZE <= self.address_limit).then_some(frame)
+    }
+}
+
+struct Eviction {
+    device: Pid,
+    page: Page,
+    frame: PhysAddr,
+    slot: usize,
+    first_block: u64,
+    num_of_blocks: u64,
+}
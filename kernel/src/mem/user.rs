@@ -0,0 +1,101 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Accesses to the user memory from the kernel.
+//!
+//! If SMAP is enabled, the kernel faults when it touches a user page outside of
+//! [`with_user_access`].
+
+use {
+    super::protection,
+    alloc::vec::Vec,
+    core::{arch::asm, convert::TryFrom, mem, slice},
+    x86_64::registers::rflags::{self, RFlags},
+};
+
+/// The end of the lower half of the address space.
+pub(crate) const USER_END: u64 = 0x0000_8000_0000_0000;
+
+/// Calls `f` with the accesses to the user pages allowed.
+///
+/// This function may be nested. The accesses stay allowed after an inner call returns.
+pub(crate) fn with_user_access<T>(f: impl FnOnce() -> T) -> T {
+    // RFLAGS.AC is already set if the caller is in another `with_user_access`.
+    let toggle = protection::smap_enabled() && !rflags::read().contains(RFlags::ALIGNMENT_CHECK);
+
+    if toggle {
+        // SAFETY: `stac` only sets RFLAGS.AC, which is cleared right after `f` returns.
+        unsafe { asm!("stac", options(nomem, nostack)) }
+    }
+
+    let r = f();
+
+    if toggle {
+        // SAFETY: `clac` only clears RFLAGS.AC, which was cleared before `f` was called.
+        unsafe { asm!("clac", options(nomem, nostack)) }
+    }
+
+    r
+}
+
+/// Copies a value from the user memory.
+///
+/// Returns [`None`] if `src` points outside the user memory.
+///
+/// # Safety
+///
+/// `src` must point to a mapped, valid value of `T`.
+pub(crate) unsafe fn read<T: Copy>(src: *const T) -> Option<T> {
+    is_user_range(src as u64, mem::size_of::<T>()).then(|| {
+        // SAFETY: The caller must ensure the all safety requirements.
+        with_user_access(|| unsafe { src.read_unaligned() })
+    })
+}
+
+/// Writes `value` to the user memory.
+///
+/// Returns [`None`] if `dst` points outside the user memory.
+///
+/// # Safety
+///
+/// `dst` must point to a mapped, writable memory region for `T`.
+pub(crate) unsafe fn write<T>(dst: *mut T, value: T) -> Option<()> {
+    is_user_range(dst as u64, mem::size_of::<T>()).then(|| {
+        // SAFETY: The caller must ensure the all safety requirements.
+        with_user_access(|| unsafe { dst.write_unaligned(value) });
+    })
+}
+
+/// Copies `src` to the user memory.
+///
+/// Returns [`None`] if the range points outside the user memory.
+///
+/// # Safety
+///
+/// The `src.len()` bytes from `dst` must be mapped and writable.
+pub(crate) unsafe fn write_bytes(dst: *mut u8, src: &[u8]) -> Option<()> {
+    is_user_range(dst as u64, src.len()).then(|| {
+        // SAFETY: The caller must ensure the all safety requirements.
+        with_user_access(|| unsafe { dst.copy_from_nonoverlapping(src.as_ptr(), src.len()) });
+    })
+}
+
+/// Copies `len` bytes from the user memory.
+///
+/// Returns [`None`] if the range points outside the user memory.
+///
+/// # Safety
+///
+/// The `len` bytes from `src` must be mapped.
+pub(crate) unsafe fn read_bytes(src: *const u8, len: usize) -> Option<Vec<u8>> {
+    is_user_range(src as u64, len).then(|| {
+        // SAFETY: The caller must ensure the all safety requirements.
+        with_user_access(|| unsafe { slice::from_raw_parts(src, len) }.to_vec())
+    })
+}
+
+fn is_user_range(start: u64, len: usize) -> bool {
+    u64::try_from(len)
+        .ok()
+        .and_then(|len| start.checked_add(len))
+        .map_or(false, |end| end <= USER_END)
+}
@@ -0,0 +1,327 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use {
+    super::{aslr, paging},
+    alloc::{collections::BTreeMap, vec::Vec},
+    conquer_once::spin::Lazy,
+    core::{convert::TryFrom, fmt, ops::DerefMut},
+    log::info,
+    os_units::NumOfPages,
+    spinning_top::Spinlock,
+    syscalls::SharedMemoryHandle,
+    x86_64::{
+        registers::control::Cr3,
+        structures::paging::{
+            page::PageRange, Page, PageSize, PageTableFlags, PhysFrame, Size4KiB,
+        },
+        VirtAddr,
+    },
+};
+
+/// Virtual memory areas of each address space, keyed with the frame of its PML4.
+static ADDRESS_SPACES: Lazy<Spinlock<BTreeMap<PhysFrame, Areas>>> =
+    Lazy::new(|| Spinlock::new(BTreeMap::new()));
+
+/// The range where the start of each region is randomly placed.
+const RANDOMIZATION_RANGE: u64 = 0x100_0000_0000;
+
+/// Finds a free range of `num_of_pages` pages whose start address is aligned to `align` in the
+/// region for `backing` of the current address space, and records it as a new area.
+pub(crate) fn reserve(
+    num_of_pages: NumOfPages<Size4KiB>,
+    align: u64,
+    flags: PageTableFlags,
+    backing: Backing,
+) -> Option<PageRange> {
+    let mut spaces = lock_address_spaces();
+    let areas = spaces.entry(current_pml4()).or_default();
+
+    let region = Region::for_backing(backing);
+    let base = areas.base_of(region);
+
+    let range = areas
+        .search_free_range(num_of_pages, region.range_from(base), align)
+        .or_else(|| areas.search_free_range(num_of_pages, region.range(), align))?;
+
+    areas.insert(Area::new(range, flags, backing));
+
+    Some(range)
+}
+
+/// Records `area` to the current address space.
+///
+/// # Panics
+///
+/// This function panics if `area` overlaps with another area.
+pub(crate) fn add(area: Area) {
+    let mut spaces = lock_address_spaces();
+    let areas = spaces.entry(current_pml4()).or_default();
+
+    assert!(
+        !areas.overlaps(area.range),
+        "{area} overlaps with another area."
+    );
+
+    areas.insert(area);
+}
+
+/// Removes the area starting at `start` from the current address space.
+pub(crate) fn remove(start: VirtAddr) -> Option<Area> {
+    lock_address_spaces()
+        .get_mut(&current_pml4())?
+        .remove(start)
+}
+
+pub(crate) fn find(addr: VirtAddr) -> Option<Area> {
+    lock_address_spaces().get(&current_pml4())?.find(addr)
+}
+
+/// Returns the area of the current address space which starts at `start`, spans `num_of_pages`
+/// pages and is backed by `backing`.
+///
+/// Returns [`None`] if there is no such area, for example, if the range covers only a part of an
+/// area or more than one area.
+pub(crate) fn find_exact(
+    start: VirtAddr,
+    num_of_pages: NumOfPages<Size4KiB>,
+    backing: Backing,
+) -> Option<Area> {
+    let area = find(start)?;
+
+    (area.start() == start && area.num_of_pages() == num_of_pages && area.backing == backing)
+        .then_some(area)
+}
+
+/// Removes all areas of the current address space and returns them.
+pub(crate) fn take_all() -> Vec<Area> {
+    lock_address_spaces()
+        .remove(&current_pml4())
+        .map_or_else(Vec::new, |areas| areas.areas.into_values().collect())
+}
+
+/// Returns the ranges of the areas backed by `backing` in the current address space.
+pub(crate) fn ranges_of(backing: Backing) -> Vec<PageRange> {
+    lock_address_spaces()
+        .get(&current_pml4())
+        .map_or_else(Vec::new, |areas| {
+            areas
+                .iter()
+                .filter(|a| a.backing == backing)
+                .map(|a| a.range)
+                .collect()
+        })
+}
+
+/// Prints the all areas of the current address space.
+pub(crate) fn dump() {
+    let spaces = lock_address_spaces();
+
+    if let Some(areas) = spaces.get(&current_pml4()) {
+        for area in areas.iter() {
+            info!("{}", area);
+        }
+    }
+}
+
+/// Returns the number of the user pages in the address space of `pml4` which are mapped to the
+/// physical memory. Memory-mapped I/O and the swapped out pages are not counted.
+///
+/// Returns [`None`] if the address spaces or the page tables are locked, e.g., when the kernel
+/// panics while modifying them.
+pub(crate) fn resident_pages(pml4: PhysFrame) -> Option<NumOfPages<Size4KiB>> {
+    let spaces = ADDRESS_SPACES.try_lock()?;
+    let areas = spaces.get(&pml4);
+
+    let is_mmio = |addr| {
+        areas
+            .and_then(|areas| areas.find(addr))
+            .map_or(false, |a| a.backing == Backing::Mmio)
+    };
+
+    paging::try_count_user_pages(pml4, is_mmio)
+}
+
+fn current_pml4() -> PhysFrame {
+    Cr3::read().0
+}
+
+fn lock_address_spaces() -> impl DerefMut<Target = BTreeMap<PhysFrame, Areas>> {
+    ADDRESS_SPACES
+        .try_lock()
+        .expect("Failed to lock the address spaces.")
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Backing {
+    Anonymous,
+    Stack,
+    Mmio,
+    Shared(SharedMemoryHandle),
+    Elf,
+    Dma,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Area {
+    range: PageRange,
+    flags: PageTableFlags,
+    backing: Backing,
+}
+impl Area {
+    pub(crate) fn new(range: PageRange, flags: PageTableFlags, backing: Backing) -> Self {
+        Self {
+            range,
+            flags,
+            backing,
+        }
+    }
+
+    pub(crate) fn backing(&self) -> Backing {
+        self.backing
+    }
+
+    pub(crate) fn flags(&self) -> PageTableFlags {
+        self.flags
+    }
+
+    pub(crate) fn start(&self) -> VirtAddr {
+        self.range.start.start_address()
+    }
+
+    fn end(&self) -> VirtAddr {
+        self.range.end.start_address()
+    }
+
+    pub(crate) fn num_of_pages(&self) -> NumOfPages<Size4KiB> {
+        NumOfPages::new(usize::try_from((self.end() - self.start()) / Size4KiB::SIZE).unwrap())
+    }
+
+    fn contains(&self, addr: VirtAddr) -> bool {
+        (self.start()..self.end()).contains(&addr)
+    }
+}
+impl fmt::Display for Area {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?} .. {:?} {:?} {:?}",
+            self.start(),
+            self.end(),
+            self.backing,
+            self.flags
+        )
+    }
+}
+
+/// A part of the user region where the areas of the same kind are placed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Region {
+    Heap,
+    Mapped,
+    Stack,
+}
+impl Region {
+    fn for_backing(backing: Backing) -> Self {
+        match backing {
+            Backing::Anonymous | Backing::Elf => Self::Heap,
+            Backing::Mmio | Backing::Shared(_) | Backing::Dma => Self::Mapped,
+            Backing::Stack => Self::Stack,
+        }
+    }
+
+    fn range(self) -> PageRange {
+        let (start, end) = match self {
+            Self::Heap => (0x1000_0000_0000, 0x3000_0000_0000),
+            Self::Mapped => (0x3000_0000_0000, 0x6000_0000_0000),
+            Self::Stack => (0x6000_0000_0000, 0x7fff_ffff_f000),
+        };
+
+        PageRange {
+            start: Page::from_start_address(VirtAddr::new(start)).unwrap(),
+            end: Page::from_start_address(VirtAddr::new(end)).unwrap(),
+        }
+    }
+
+    fn range_from(self, base: VirtAddr) -> PageRange {
+        PageRange {
+            start: Page::containing_address(base),
+            ..self.range()
+        }
+    }
+
+    fn random_base(self) -> VirtAddr {
+        let offset = aslr::random_offset(RANDOMIZATION_RANGE, Size4KiB::SIZE);
+
+        self.range().start.start_address() + offset
+    }
+}
+
+#[derive(Default)]
+struct Areas {
+    areas: BTreeMap<VirtAddr, Area>,
+    bases: BTreeMap<Region, VirtAddr>,
+}
+impl Areas {
+    /// Returns the start address of `region` in this address space, which is randomized when the
+    /// region is used for the first time.
+    fn base_of(&mut self, region: Region) -> VirtAddr {
+        *self
+            .bases
+            .entry(region)
+            .or_insert_with(|| region.random_base())
+    }
+
+    fn search_free_range(
+        &self,
+        num_of_pages: NumOfPages<Size4KiB>,
+        region: PageRange,
+        align: u64,
+    ) -> Option<PageRange> {
+        let len = num_of_pages.as_bytes().as_usize();
+
+        let mut candidate = region.start.start_address().align_up(align);
+
+        for area in self.areas.values() {
+            if area.end() <= candidate {
+                continue;
+            }
+
+            if area.start() >= candidate + len {
+                break;
+            }
+
+            candidate = area.end().align_up(align);
+        }
+
+        let end = candidate + len;
+
+        (end <= region.end.start_address()).then(|| PageRange {
+            start: Page::containing_address(candidate),
+            end: Page::containing_address(end),
+        })
+    }
+
+    fn overlaps(&self, range: PageRange) -> bool {
+        self.areas
+            .values()
+            .any(|a| a.range.start < range.end && range.start < a.range.end)
+    }
+
+    fn insert(&mut self, area: Area) {
+        self.areas.insert(area.start(), area);
+    }
+
+    fn remove(&mut self, start: VirtAddr) -> Option<Area> {
+        self.areas.remove(&start)
+    }
+
+    fn find(&self, addr: VirtAddr) -> Option<Area> {
+        let (_, area) = self.areas.range(..=addr).next_back()?;
+
+        area.contains(addr).then_some(*area)
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &Area> {
+        self.areas.values()
+    }
+}
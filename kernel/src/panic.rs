@@ -1,19 +1,25 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 use {
-    crate::qemu,
-    core::{fmt::Write, format_args},
+    crate::{interrupt::timer, mem::allocator::phys, process::scheduler, qemu},
     log::error,
-    uart_16550::SerialPort,
     x86_64::instructions::interrupts,
 };
 
+/// The number of the log records sent to the serial port on panic.
+const LAST_RECORDS: usize = 32;
+
 #[panic_handler]
 fn panic(i: &core::panic::PanicInfo<'_>) -> ! {
     interrupts::disable();
 
+    // The screen may be unusable, so send the log before the panic to the serial port.
+    terminal::log::dump_last_records(LAST_RECORDS);
+
     print_banner();
     print_info(i);
+    print_uptime();
+    print_memory_stats();
 
     fini()
 }
@@ -25,13 +31,45 @@ fn print_banner() {
 }
 
 fn print_info(i: &core::panic::PanicInfo<'_>) {
-    let mut s = unsafe { SerialPort::new(0x3f8) };
-    s.init();
-    s.write_fmt(format_args!("{i}\n")).unwrap();
-
     error!("{}", i);
 }
 
+fn print_uptime() {
+    if let Some(t) = timer::uptime() {
+        error!("Uptime: {}.{:06} s", t.as_secs(), t.subsec_micros());
+    }
+}
+
+/// Prints the memory usage. Locked statistics are skipped because the panic may happen while
+/// they are being modified.
+fn print_memory_stats() {
+    if let Some(s) = phys::try_stats() {
+        error!(
+            "Frames: total {}, used {}, free {}, reserved {}, largest free run {}",
+            s.total.as_usize(),
+            s.used().as_usize(),
+            s.free.as_usize(),
+            s.reserved.as_usize(),
+            s.largest_free_run.as_usize()
+        );
+    } else {
+        error!("Frames: the frame manager is locked.");
+    }
+
+    let r = scheduler::try_for_each_memory_usage(|pid, name, pages| {
+        error!(
+            "PID {} ({}): {} resident pages",
+            pid,
+            name,
+            pages.as_usize()
+        );
+    });
+
+    if r.is_none() {
+        error!("Processes: the scheduler or the address spaces are locked.");
+    }
+}
+
 fn fini() -> ! {
     if cfg!(feature = "qemu_test") {
         qemu::exit_failure();
@@ -0,0 +1,115 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Byte streams from a process to another.
+
+use {
+    crate::process::{scheduler, WaitQueue},
+    alloc::{collections::VecDeque, sync::Arc, vec::Vec},
+    core::ops::DerefMut,
+    spinning_top::Spinlock,
+};
+
+/// The maximum number of the bytes which are written but not read yet.
+const CAPACITY: usize = 4096;
+
+pub(crate) fn new() -> (Reader, Writer) {
+    let pipe = Arc::new(Spinlock::new(Pipe::default()));
+
+    (Reader(Arc::clone(&pipe)), Writer(pipe))
+}
+
+#[derive(Debug)]
+pub(crate) struct Reader(Arc<Spinlock<Pipe>>);
+impl Reader {
+    /// Reads at most `len` bytes, waiting until some bytes are written.
+    ///
+    /// Returns an empty vector if the writer is closed and all bytes are read.
+    pub(crate) fn read(&self, len: usize) -> Vec<u8> {
+        loop {
+            let mut pipe = lock(&self.0);
+
+            if !pipe.bytes.is_empty() || pipe.writer_closed {
+                let len = len.min(pipe.bytes.len());
+                let read = pipe.bytes.drain(..len).collect();
+
+                pipe.writers.wake_all();
+
+                return read;
+            }
+
+            pipe.readers.add_current();
+
+            drop(pipe);
+
+            scheduler::block();
+        }
+    }
+}
+impl Drop for Reader {
+    fn drop(&mut self) {
+        let mut pipe = lock(&self.0);
+
+        pipe.reader_closed = true;
+        pipe.writers.wake_all();
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Writer(Arc<Spinlock<Pipe>>);
+impl Writer {
+    /// Writes `buf`, waiting while the pipe is full.
+    ///
+    /// Returns the number of the written bytes, which is smaller than `buf.len()` if the reader is
+    /// closed while writing. This method returns [`None`] if the reader is closed before writing
+    /// any byte.
+    pub(crate) fn write(&self, buf: &[u8]) -> Option<usize> {
+        let mut written = 0;
+
+        while written < buf.len() {
+            let mut pipe = lock(&self.0);
+
+            if pipe.reader_closed {
+                return (written > 0).then_some(written);
+            }
+
+            let len = (CAPACITY - pipe.bytes.len()).min(buf.len() - written);
+            pipe.bytes.extend(&buf[written..written + len]);
+            written += len;
+
+            pipe.readers.wake_all();
+
+            if written < buf.len() {
+                pipe.writers.add_current();
+
+                drop(pipe);
+
+                scheduler::block();
+            }
+        }
+
+        Some(written)
+    }
+}
+impl Drop for Writer {
+    fn drop(&mut self) {
+        let mut pipe = lock(&self.0);
+
+        pipe.writer_closed = true;
+        pipe.readers.wake_all();
+    }
+}
+
+#[derive(Debug, Default)]
+struct Pipe {
+    bytes: VecDeque<u8>,
+    reader_closed: bool,
+    writer_closed: bool,
+    /// The processes waiting for bytes.
+    readers: WaitQueue,
+    /// The processes waiting for space.
+    writers: WaitQueue,
+}
+
+fn lock(pipe: &Spinlock<Pipe>) -> impl DerefMut<Target = Pipe> + '_ {
+    pipe.try_lock().expect("Failed to lock a pipe.")
+}
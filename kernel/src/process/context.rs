@@ -40,7 +40,10 @@ pub(super) struct Context {
     cr3: u64,
     rip: u64,
     rflags: u64,
-    _fxsave_requires_16_bytes_alinged_address: u64,
+
+    // This field also makes `fxsave_area` 16-byte aligned. `switch` restores the FS base after
+    // loading the FS selector because loading the selector clears the base.
+    fs_base: u64,
 
     fxsave_area: FxsaveArea,
 }
@@ -56,14 +59,17 @@ impl Context {
         )
     }
 
-    pub(super) fn user(entry: VirtAddr, pml4: PhysFrame, rsp: VirtAddr) -> Self {
-        Self::new(
-            entry,
-            pml4,
-            rsp,
-            gdt::user_code_selector(),
-            gdt::user_data_selector(),
-        )
+    pub(super) fn user(entry: VirtAddr, pml4: PhysFrame, rsp: VirtAddr, fs_base: VirtAddr) -> Self {
+        Self {
+            fs_base: fs_base.as_u64(),
+            ..Self::new(
+                entry,
+                pml4,
+                rsp,
+                gdt::user_code_selector(),
+                gdt::user_data_selector(),
+            )
+        }
     }
 
     #[naked]
@@ -105,8 +111,23 @@ impl Context {
     pushfq
     pop qword ptr [rdi+0xb0]
 
+    mov ecx, 0xc0000100
+    rdmsr
+    mov [rdi+0xb8], eax
+    mov [rdi+0xbc], edx
+
     fxsave [rdi+0xc0]
 
+    mov rax, [rsi+0x90]
+    mov fs, ax
+    mov rax, [rsi+0x98]
+    mov gs, ax
+
+    mov ecx, 0xc0000100
+    mov eax, [rsi+0xb8]
+    mov edx, [rsi+0xbc]
+    wrmsr
+
     mov rax, [rsi+0x00]
     mov rbx, [rsi+0x08]
     mov rcx, [rsi+0x10]
@@ -125,11 +146,6 @@ impl Context {
     mov r14, [rsi+0x70]
     mov r15, [rsi+0x78]
 
-    mov rax, [rsi+0x90]
-    mov fs, ax
-    mov rax, [rsi+0x98]
-    mov gs, ax
-
     mov rax, [rsi+0xa0]
     mov cr3, rax
 
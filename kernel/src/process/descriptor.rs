@@ -0,0 +1,158 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use {
+    super::{scheduler, Pid},
+    crate::{console, fs::file::OpenFile, pipe},
+    alloc::{collections::BTreeMap, sync::Arc},
+    core::{ops::DerefMut, str},
+    message::{Body, Header, Message},
+    spinning_top::Spinlock,
+    syscalls::{DirEntry, Ty, Whence},
+    terminal::print,
+};
+
+/// The file descriptors of a process.
+///
+/// The duplicated descriptors and the descriptors inherited by the children refer to the same
+/// object, so they share the offset of a file, for example. The object is closed when the last
+/// descriptor referring to it is closed.
+#[derive(Clone, Debug)]
+pub(crate) struct Table(BTreeMap<i32, Arc<Object>>);
+impl Table {
+    pub(crate) fn get(&self, fildes: i32) -> Option<Arc<Object>> {
+        self.0.get(&fildes).cloned()
+    }
+
+    /// Adds `object` and returns its descriptor, which is the smallest unused one.
+    pub(crate) fn insert(&mut self, object: Arc<Object>) -> i32 {
+        let fildes = (0..)
+            .find(|fildes| !self.0.contains_key(fildes))
+            .expect("No available file descriptor.");
+
+        self.0.insert(fildes, object);
+
+        fildes
+    }
+
+    /// Makes `fildes` refer to `object`, and returns the object which `fildes` referred to.
+    ///
+    /// The returned object must be dropped after unlocking the scheduler because closing an
+    /// object may send a message.
+    pub(crate) fn replace(&mut self, fildes: i32, object: Arc<Object>) -> Option<Arc<Object>> {
+        self.0.insert(fildes, object)
+    }
+
+    /// Removes `fildes`.
+    ///
+    /// The returned object must be dropped after unlocking the scheduler. See [`Table::replace`].
+    pub(crate) fn remove(&mut self, fildes: i32) -> Option<Arc<Object>> {
+        self.0.remove(&fildes)
+    }
+}
+impl Default for Table {
+    /// Returns a table whose standard input, output and error refer to the console.
+    fn default() -> Self {
+        let console = Arc::new(Object::Console);
+
+        Self(
+            (0..3)
+                .map(|fildes| (fildes, Arc::clone(&console)))
+                .collect(),
+        )
+    }
+}
+
+/// An object which a file descriptor refers to.
+#[derive(Debug)]
+pub(crate) enum Object {
+    /// Reads the lines typed on the console, and writes to the screen and the serial port.
+    Console,
+    File(Spinlock<OpenFile>),
+    PipeReader(pipe::Reader),
+    PipeWriter(pipe::Writer),
+    /// An object which the process `pid` serves. The process receives
+    /// `Body(Ty::Close as u64, handle, 0, 0, 0)` when the object is closed.
+    Server {
+        pid: Pid,
+        handle: u64,
+    },
+}
+impl Object {
+    /// Reads at most `len` bytes, passes them to `copy`, and returns the number of the read
+    /// bytes. Reading the console or a pipe waits until some bytes are available.
+    ///
+    /// The offset of a file advances only if `copy` succeeds. This method returns [`None`] if the
+    /// object is not readable or `copy` fails.
+    pub(crate) fn read(&self, len: usize, copy: impl FnOnce(&[u8]) -> Option<()>) -> Option<usize> {
+        let read = match self {
+            Self::Console => console::read(len),
+            Self::File(f) => {
+                // The lock is released while copying because writing to the user memory may
+                // fault.
+                let read = lock(f).peek(len)?;
+
+                copy(read)?;
+                lock(f).advance(read.len());
+
+                return Some(read.len());
+            }
+            Self::PipeReader(r) => r.read(len),
+            Self::PipeWriter(_) | Self::Server { .. } => return None,
+        };
+
+        copy(&read).map(|()| read.len())
+    }
+
+    /// Writes `buf` and returns the number of the written bytes.
+    ///
+    /// Returns [`None`] if the object is not writable.
+    pub(crate) fn write(&self, buf: &[u8]) -> Option<usize> {
+        match self {
+            Self::Console => {
+                print!("{}", str::from_utf8(buf).ok()?);
+
+                Some(buf.len())
+            }
+            Self::PipeWriter(w) => w.write(buf),
+            Self::File(_) | Self::PipeReader(_) | Self::Server { .. } => None,
+        }
+    }
+
+    pub(crate) fn seek(&self, offset: i64, whence: Whence) -> Option<u64> {
+        match self {
+            Self::File(f) => lock(f).seek(offset, whence),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn read_dir(&self) -> Option<DirEntry> {
+        match self {
+            Self::File(f) => lock(f).read_dir(),
+            _ => None,
+        }
+    }
+
+    /// Returns the PID of the server and the handle if this is a server-backed object.
+    pub(crate) fn server(&self) -> Option<(Pid, u64)> {
+        match self {
+            Self::Server { pid, handle } => Some((*pid, *handle)),
+            _ => None,
+        }
+    }
+}
+impl Drop for Object {
+    fn drop(&mut self) {
+        if let Self::Server { pid, handle } = self {
+            let m = Message::new(
+                Header::new(scheduler::current_pid()),
+                Body(Ty::Close as u64, *handle, 0, 0, 0),
+            );
+
+            scheduler::notify(*pid, m);
+        }
+    }
+}
+
+fn lock(f: &Spinlock<OpenFile>) -> impl DerefMut<Target = OpenFile> + '_ {
+    f.try_lock().expect("Failed to lock an open file.")
+}
@@ -1 +1 @@
-pub(crate) use super::scheduler::{receive_from, receive_from_any, send};
+pub(crate) use super::scheduler::{receive_from, receive_from_any, send, try_receive_from_any};
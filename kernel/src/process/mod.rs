@@ -1,10 +1,12 @@
 mod context;
+pub(crate) mod descriptor;
 pub(crate) mod ipc;
 mod pid;
 mod priority;
 mod receive_from;
 pub(crate) mod scheduler;
 mod status;
+mod wait_queue;
 
 #[cfg(feature = "qemu_test")]
 use crate::tests;
@@ -16,24 +18,30 @@ use {
         status::Status,
     },
     crate::{
+        fs,
+        interrupt::irq,
         mem::{
-            self,
-            allocator::{allocate_pages_for_user, kpbox::KpBox},
-            paging,
+            allocator::{allocate_stack_for_user, free_current_user_space, kpbox::KpBox},
+            elf, paging, shared, vma,
         },
         sysproc,
     },
     alloc::collections::VecDeque,
-    core::{cell::UnsafeCell, convert::TryInto},
+    core::{cell::UnsafeCell, convert::TryInto, mem},
+    log::error,
+    message::Message,
     os_units::{Bytes, NumOfPages},
     static_assertions::const_assert,
+    syscalls::{Stat, SYSTEM_PROCESS_PID},
     x86_64::{
         registers::control::Cr3,
-        structures::paging::{PageSize, PageTable, PageTableFlags, PhysFrame, Size4KiB},
+        structures::paging::{
+            mapper::MapToError, PageSize, PageTable, PageTableFlags, PhysFrame, Size4KiB,
+        },
         PhysAddr, VirtAddr,
     },
 };
-pub(crate) use {pid::Pid, scheduler::switch};
+pub(crate) use {pid::Pid, scheduler::switch, wait_queue::WaitQueue};
 
 // No truncation from u64 to usize on the x86_64 platform.
 #[allow(clippy::cast_possible_truncation)]
@@ -46,18 +54,89 @@ const_assert!(STACK_GUARD_SIZE.as_usize() + STACK_MAGIC.as_bytes().len() <= STAC
 pub(super) fn init() {
     scheduler::init();
 
-    scheduler::add_process_as_runnable(Process::binary("xhci.bin"));
-    scheduler::add_process_as_runnable(Process::from_function(sysproc::main, "sysproc"));
+    // The system calls implemented with messages are sent to `SYSTEM_PROCESS_PID`, so create the
+    // system process right after the idle process.
+    let sysproc = Process::from_function(sysproc::main, "sysproc");
+    assert_eq!(
+        sysproc.pid, SYSTEM_PROCESS_PID,
+        "Wrong PID for the system process."
+    );
+    scheduler::add_process_as_runnable(sysproc);
+
+    for name in ["xhci.bin", "sh.bin"] {
+        load_binary(name);
+    }
 
     #[cfg(feature = "qemu_test")]
     scheduler::add_process_as_runnable(Process::from_function(tests::main, "tests"));
 }
 
+/// Loads the executable `name` in the initrd as a child of the current process, which inherits the
+/// file descriptors of the current process.
+pub(crate) fn spawn(name: &'static str) -> Result<Pid, LoadError> {
+    let mut p = Process::binary(name)?;
+    let pid = p.pid;
+
+    p.descriptors = scheduler::with_current_descriptors(|d| d.clone());
+
+    scheduler::add_process_as_runnable(p);
+
+    Ok(pid)
+}
+
+/// Terminates the current process.
+///
+/// The descriptors are closed, the interrupts and the unmapped shared memory objects owned by the
+/// process are released, and the user memory is freed here. The rest of the process is freed when
+/// another process exits.
+pub(crate) fn exit() -> ! {
+    // Closing a descriptor may send a message, so close them before locking the scheduler.
+    drop(scheduler::with_current_descriptors(mem::take));
+
+    let pid = scheduler::current_pid();
+
+    irq::release(pid);
+
+    free_current_user_space();
+    shared::release(pid);
+
+    scheduler::exit();
+}
+
+/// Loads the executable `name` as a privileged process.
+fn load_binary(name: &'static str) {
+    match Process::binary(name) {
+        Ok(mut p) => {
+            p.privileged = true;
+
+            scheduler::add_process_as_runnable(p);
+        }
+        Err(e) => error!("Failed to load {}: {:?}", name, e),
+    }
+}
+
+/// The reasons why an executable cannot be loaded.
+#[derive(Debug)]
+pub(crate) enum LoadError {
+    Fs(fs::Error),
+    Elf(elf::Error),
+}
+impl From<fs::Error> for LoadError {
+    fn from(e: fs::Error) -> Self {
+        Self::Fs(e)
+    }
+}
+impl From<elf::Error> for LoadError {
+    fn from(e: elf::Error) -> Self {
+        Self::Elf(e)
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct Process {
     pid: Pid,
 
-    _pml4: KpBox<PageTable>,
+    pml4: KpBox<PageTable>,
 
     context: Context,
     kernel_stack: KpBox<UnsafeCell<[u8; STACK_SIZE]>>,
@@ -67,13 +146,23 @@ pub(crate) struct Process {
     send_to: Option<Pid>,
     receive_from: Option<ReceiveFrom>,
     pids_try_to_send_this_process: VecDeque<Pid>,
+    /// The messages from the kernel which are not received yet.
+    notifications: VecDeque<Message>,
+    /// Whether the process which this process was sending to or receiving from exited before the
+    /// message was passed.
+    peer_exited: bool,
+    descriptors: descriptor::Table,
+    /// Whether the process may use the privileged system calls. The kernel processes and the
+    /// processes loaded at boot are privileged. A spawned process is privileged if its executable
+    /// has the set-user-ID bit.
+    privileged: bool,
     name: &'static str,
 }
 impl Process {
     fn idle() -> Self {
         Self {
             pid: pid::generate(),
-            _pml4: Self::generate_pml4(),
+            pml4: Self::generate_pml4(),
             context: Context::default(),
             kernel_stack: Self::generate_kernel_stack(),
             priority: LEAST_PRIORITY,
@@ -82,6 +171,10 @@ impl Process {
             status: Status::Running,
             receive_from: None,
             pids_try_to_send_this_process: VecDeque::new(),
+            notifications: VecDeque::new(),
+            peer_exited: false,
+            descriptors: descriptor::Table::default(),
+            privileged: true,
             name: "idle",
         }
     }
@@ -103,7 +196,7 @@ impl Process {
 
         Process {
             pid: pid::generate(),
-            _pml4: pml4,
+            pml4,
 
             context,
             kernel_stack,
@@ -117,39 +210,49 @@ impl Process {
             receive_from: None,
 
             pids_try_to_send_this_process: VecDeque::new(),
+            notifications: VecDeque::new(),
+            peer_exited: false,
+            descriptors: descriptor::Table::default(),
+            privileged: true,
             name,
         }
     }
 
     #[allow(clippy::too_many_lines)]
-    fn binary(name: &'static str) -> Self {
+    fn binary(name: &'static str) -> Result<Self, LoadError> {
+        let handler = fs::find(name)?;
+        let raw = handler.content();
+        let privileged = handler.mode() & Stat::S_ISUID != 0;
+
         let pml4 = Self::generate_pml4();
 
         let pml4_frame = PhysFrame::from_start_address(pml4.phys_addr());
         let pml4_frame = pml4_frame.expect("PML4 is not page-aligned.");
 
-        let handler = crate::fs::get_handler(name);
-        let raw = handler.content();
-
         let kernel_stack = Self::generate_kernel_stack();
 
         unsafe {
             switch_pml4_do(pml4_frame, || {
-                let entry = mem::elf::map_to_current_address_space(raw).unwrap();
+                let image = elf::map_to_current_address_space(raw, elf::random_load_base())?;
 
                 let stack_size = NumOfPages::<Size4KiB>::new(5);
 
-                let stack_top = allocate_pages_for_user(NumOfPages::new(5)).unwrap();
+                let Some(stack_top) = allocate_stack_for_user(stack_size) else {
+                    image.free();
+
+                    return Err(elf::Error::MapTo(MapToError::FrameAllocationFailed).into());
+                };
 
                 let context = Context::user(
-                    entry,
+                    image.entry,
                     pml4_frame,
                     stack_top + stack_size.as_bytes().as_usize() - 8_u64,
+                    image.thread_pointer,
                 );
 
-                Self {
+                Ok(Self {
                     pid: pid::generate(),
-                    _pml4: pml4,
+                    pml4,
 
                     context,
                     kernel_stack,
@@ -163,8 +266,12 @@ impl Process {
                     receive_from: None,
 
                     pids_try_to_send_this_process: VecDeque::new(),
+                    notifications: VecDeque::new(),
+                    peer_exited: false,
+                    descriptors: descriptor::Table::default(),
+                    privileged,
                     name,
-                }
+                })
             })
         }
     }
@@ -191,6 +298,13 @@ impl Process {
         );
     }
 
+    /// Returns [`None`] if the address spaces or the page tables are locked.
+    fn resident_pages(&self) -> Option<NumOfPages<Size4KiB>> {
+        let pml4 = PhysFrame::from_start_address(self.pml4.phys_addr());
+
+        vma::resident_pages(pml4.expect("PML4 is not page-aligned."))
+    }
+
     fn kernel_stack_bottom_addr(&self) -> VirtAddr {
         self.kernel_stack.virt_addr() + self.kernel_stack.bytes().as_usize()
     }
@@ -202,8 +316,9 @@ impl Process {
             pml4[i].set_unused();
         }
 
-        let flags =
-            PageTableFlags::PRESENT | PageTableFlags::WRITABLE | PageTableFlags::USER_ACCESSIBLE;
+        // The recursive entry must not be user accessible. Otherwise SMAP and SMEP treat the page
+        // tables as user pages.
+        let flags = PageTableFlags::PRESENT | PageTableFlags::WRITABLE;
 
         let addr = pml4.phys_addr();
 
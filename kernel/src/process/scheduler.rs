@@ -1,44 +1,69 @@
 use {
     super::{
         context::Context,
+        descriptor,
         priority::{Priority, LEAST_PRIORITY},
         receive_from::ReceiveFrom,
         Pid,
     },
     crate::{
+        interrupt::timer,
         mem::{self, accessor::Single, paging},
         process::{status::Status, Process},
         tss,
     },
-    alloc::collections::{BTreeMap, VecDeque},
+    alloc::{
+        collections::{BTreeMap, VecDeque},
+        vec::Vec,
+    },
     array_init::array_init,
     conquer_once::spin::Lazy,
     message::Message,
+    os_units::NumOfPages,
     spinning_top::{Spinlock, SpinlockGuard},
-    x86_64::{instructions::interrupts::without_interrupts, PhysAddr, VirtAddr},
+    syscalls::SYSTEM_PROCESS_PID,
+    x86_64::{
+        instructions::interrupts::without_interrupts, structures::paging::Size4KiB, PhysAddr,
+        VirtAddr,
+    },
 };
 
 static SCHEDULER: Lazy<Spinlock<Scheduler>> = Lazy::new(|| Spinlock::new(Scheduler::new()));
 
+const IDLE_PID: Pid = 0;
+
 pub(crate) fn switch() {
     let mut manager = lock();
 
-    if let Some((current_context, next_context)) = manager.try_switch() {
+    let contexts = manager.try_switch();
+
+    manager.update_timer();
+
+    if let Some((current_context, next_context)) = contexts {
         drop(manager);
 
         Context::switch(current_context, next_context);
     }
 }
 
-pub(crate) fn send(msg: VirtAddr, to: Pid) {
+/// Sends the message at `msg` to `to`, blocking until `to` receives it.
+///
+/// Returns `false` if `to` is the current process, does not exist, or exits before receiving the
+/// message.
+pub(crate) fn send(msg: VirtAddr, to: Pid) -> bool {
     // The kernel process calls this function, and the interrupts may be enabled at that time. If
     // we forget to disable interrupts, a timer interrupt may happen when the kernel process holds
     // the lock of the process scheduler, and the subsequent process fails to lock the scheduler
     // because the previous process already locks it. Thus, we disable the interrupts.
     without_interrupts(|| {
-        lock().send(msg, to);
+        if !lock().send(msg, to) {
+            return false;
+        }
+
         switch();
-    });
+
+        !lock().take_peer_exited()
+    })
 }
 
 pub(crate) fn receive_from_any(msg_buf: VirtAddr) {
@@ -50,19 +75,124 @@ pub(crate) fn receive_from_any(msg_buf: VirtAddr) {
     });
 }
 
-pub(crate) fn receive_from(msg_buf: VirtAddr, from: Pid) {
+/// Receives a message if a process is waiting to send one. Returns `true` if a message is
+/// received.
+pub(crate) fn try_receive_from_any(msg_buf: VirtAddr) -> bool {
+    // Ditto as `send` for `without_interrupts`.
+    without_interrupts(|| lock().try_receive_from_any(msg_buf))
+}
+
+/// Receives a message from `from` into `msg_buf`, blocking until `from` sends one.
+///
+/// Returns `false` if `from` is the current process, does not exist, or exits before sending a
+/// message.
+pub(crate) fn receive_from(msg_buf: VirtAddr, from: Pid) -> bool {
     // Ditto as `send` for `without_interrupts`.
     without_interrupts(|| {
-        lock().receive_from(msg_buf, from);
+        if !lock().receive_from(msg_buf, from) {
+            return false;
+        }
 
         switch();
-    });
+
+        !lock().take_peer_exited()
+    })
+}
+
+/// Sends `m` to `to` without blocking the current process.
+///
+/// If `to` is not waiting for a message from any process, `m` is queued and received the next time
+/// `to` receives a message from any process. `m` is discarded if `to` has exited.
+pub(crate) fn notify(to: Pid, m: Message) {
+    // Ditto as `send` for `without_interrupts`.
+    without_interrupts(|| lock().notify(to, m));
 }
 
 pub(crate) fn current_process_name() -> &'static str {
     lock().current_process_name()
 }
 
+/// Blocks the current process until [`wake`] is called for it.
+///
+/// The caller must not hold any lock. See [`super::WaitQueue`] for the usage.
+pub(crate) fn block() {
+    // Ditto as `send` for `without_interrupts`.
+    without_interrupts(|| {
+        lock().running_as_mut().status = Status::Blocked;
+
+        switch();
+    });
+}
+
+/// Makes the blocked process `pid` runnable. This function does nothing if `pid` is not blocked.
+pub(crate) fn wake(pid: Pid) {
+    // Ditto as `send` for `without_interrupts`.
+    without_interrupts(|| lock().wake_blocked(pid));
+}
+
+/// Returns `true` if `pid` is the idle process or the system process, which run in the kernel.
+pub(crate) fn is_kernel_process(pid: Pid) -> bool {
+    pid == IDLE_PID || pid == SYSTEM_PROCESS_PID
+}
+
+/// Returns `true` if the current process may use the privileged system calls.
+pub(crate) fn current_is_privileged() -> bool {
+    lock().running_as_ref().privileged
+}
+
+pub(crate) fn current_pid() -> Pid {
+    lock().running
+}
+
+/// Calls `f` with the file descriptors of the current process.
+pub(crate) fn with_current_descriptors<T>(f: impl FnOnce(&mut descriptor::Table) -> T) -> T {
+    f(&mut lock().running_as_mut().descriptors)
+}
+
+/// Returns `true` if the process `pid` exists and has not exited.
+pub(crate) fn exists(pid: Pid) -> bool {
+    lock().is_alive(pid)
+}
+
+/// Marks the current process as exited and switches to another process.
+///
+/// The processes waiting to send a message to or receive one from the current process are woken,
+/// and their system calls fail. The process which exited before is freed here. The current one
+/// cannot be freed yet because its kernel stack is in use.
+pub(super) fn exit() -> ! {
+    // Ditto as `send` for `without_interrupts`.
+    without_interrupts(|| {
+        let mut scheduler = lock();
+
+        scheduler.free_exited_processes();
+        scheduler.wake_peers_of_running();
+        scheduler.running_as_mut().status = Status::Exited;
+
+        drop(scheduler);
+
+        switch();
+    });
+
+    unreachable!("The exited process is scheduled.");
+}
+
+/// Calls `f` with the PID, the name and the number of the resident pages of each process.
+///
+/// Returns [`None`] if the scheduler or the address spaces are locked.
+pub(crate) fn try_for_each_memory_usage(
+    mut f: impl FnMut(Pid, &'static str, NumOfPages<Size4KiB>),
+) -> Option<()> {
+    let scheduler = SCHEDULER.try_lock()?;
+
+    for p in scheduler.processes.values() {
+        if p.status != Status::Exited {
+            f(p.pid, p.name, p.resident_pages()?);
+        }
+    }
+
+    Some(())
+}
+
 pub(super) fn add_process_as_runnable(p: Process) {
     lock().add_process_as_runnable(p);
 }
@@ -90,7 +220,7 @@ impl Scheduler {
 
             runnable_pids: RunnablePids::new(),
 
-            running: 0,
+            running: IDLE_PID,
         }
     }
 
@@ -101,7 +231,7 @@ impl Scheduler {
     fn add_idle_process_as_running(&mut self) {
         let idle = Process::idle();
 
-        assert_eq!(idle.pid, 0, "Wrong PID for the idle process.");
+        assert_eq!(idle.pid, IDLE_PID, "Wrong PID for the idle process.");
         assert_eq!(
             idle.status,
             Status::Running,
@@ -140,22 +270,109 @@ impl Scheduler {
         self.runnable_pids.push(pid, priority);
     }
 
-    fn send(&mut self, msg: VirtAddr, to: Pid) {
-        Sender::new(self, msg, to).send();
+    fn wake_blocked(&mut self, pid: Pid) {
+        if self
+            .process_as_ref(pid)
+            .map_or(false, |p| p.status == Status::Blocked)
+        {
+            self.wake(pid);
+        }
+    }
+
+    fn send(&mut self, msg: VirtAddr, to: Pid) -> bool {
+        Sender::new(self, msg, to).map(Sender::send).is_some()
     }
 
     fn receive_from_any(&mut self, msg_buf: VirtAddr) {
         Receiver::new_from_any(self, msg_buf).receive();
     }
 
-    fn receive_from(&mut self, msg_buf: VirtAddr, from: Pid) {
-        Receiver::new_from(self, msg_buf, from).receive();
+    fn try_receive_from_any(&mut self, msg_buf: VirtAddr) -> bool {
+        Receiver::new_from_any(self, msg_buf).try_receive()
+    }
+
+    fn receive_from(&mut self, msg_buf: VirtAddr, from: Pid) -> bool {
+        Receiver::new_from(self, msg_buf, from)
+            .map(Receiver::receive)
+            .is_some()
+    }
+
+    fn notify(&mut self, to: Pid, m: Message) {
+        let Some(p) = self.process_as_mut(to) else {
+            return;
+        };
+
+        if p.status == Status::Exited {
+            return;
+        }
+
+        if p.status == Status::Receiving(ReceiveFrom::Any) {
+            let dst = p.msg_ptr.take();
+            let dst = dst.expect("Message destination address is not specified.");
+
+            p.receive_from = None;
+
+            // SAFETY: `dst` is the buffer where the receiver waits for a message.
+            unsafe { write_msg(dst, m) }
+
+            self.wake(to);
+        } else {
+            p.notifications.push_back(m);
+        }
+    }
+
+    /// Wakes the processes which wait to send a message to or receive one from the running
+    /// process, marking that their peer exited.
+    fn wake_peers_of_running(&mut self) {
+        let running = self.running;
+
+        let senders = core::mem::take(&mut self.running_as_mut().pids_try_to_send_this_process);
+        let receivers = self
+            .processes
+            .values()
+            .filter(|p| p.status == Status::Receiving(ReceiveFrom::Id(running)))
+            .map(|p| p.pid)
+            .collect::<Vec<_>>();
+
+        for pid in senders.into_iter().chain(receivers) {
+            let p = self.process_as_mut(pid);
+            let p = p.expect("No such process.");
+
+            p.msg_ptr = None;
+            p.send_to = None;
+            p.receive_from = None;
+            p.peer_exited = true;
+
+            self.wake(pid);
+        }
+    }
+
+    /// Returns whether the peer of the running process exited, clearing the flag.
+    fn take_peer_exited(&mut self) -> bool {
+        core::mem::take(&mut self.running_as_mut().peer_exited)
+    }
+
+    /// Frees the exited processes except the running one, whose kernel stack is in use.
+    fn free_exited_processes(&mut self) {
+        let running = self.running;
+
+        self.processes
+            .retain(|pid, p| *pid == running || p.status != Status::Exited);
     }
 
     fn try_switch(&mut self) -> Option<(*mut Context, *mut Context)> {
         Switcher(self).try_switch()
     }
 
+    /// Starts a new time slice, or stops the timer if only the idle process can run.
+    fn update_timer(&self) {
+        if self.running == IDLE_PID && self.runnable_pids.is_empty() {
+            timer::stop();
+        } else {
+            timer::start_time_slice();
+        }
+    }
+
     fn current_process_name(&self) -> &'static str {
         self.running_as_ref().name
     }
@@ -164,6 +381,11 @@ impl Scheduler {
         self.running_as_ref().kernel_stack_bottom_addr()
     }
 
+    fn is_alive(&self, pid: Pid) -> bool {
+        self.process_as_ref(pid)
+            .map_or(false, |p| p.status != Status::Exited)
+    }
+
     fn running_as_ref(&self) -> &Process {
         self.process_as_ref(self.running)
             .expect("Running process is not stored.")
@@ -189,12 +411,15 @@ struct Sender<'a> {
     to: Pid,
 }
 impl<'a> Sender<'a> {
-    fn new(manager: &'a mut Scheduler, msg: VirtAddr, to: Pid) -> Self {
-        assert_ne!(manager.running, to, "Tried to send a message to self.");
+    /// Returns [`None`] if `to` is the running process, does not exist, or has exited.
+    fn new(manager: &'a mut Scheduler, msg: VirtAddr, to: Pid) -> Option<Self> {
+        if manager.running == to || !manager.is_alive(to) {
+            return None;
+        }
 
         let msg = virt_to_phys(msg);
 
-        Self { manager, msg, to }
+        Some(Self { manager, msg, to })
     }
 
     fn send(mut self) {
@@ -302,22 +527,26 @@ impl<'a> Receiver<'a> {
         }
     }
 
-    fn new_from(manager: &'a mut Scheduler, msg_buf: VirtAddr, from: Pid) -> Self {
-        assert_ne!(
-            manager.running, from,
-            "Tried to receive a message from self."
-        );
+    /// Returns [`None`] if `from` is the running process, does not exist, or has exited.
+    fn new_from(manager: &'a mut Scheduler, msg_buf: VirtAddr, from: Pid) -> Option<Self> {
+        if manager.running == from || !manager.is_alive(from) {
+            return None;
+        }
 
         let msg_buf = virt_to_phys(msg_buf);
 
-        Self {
+        Some(Self {
             manager,
             msg_buf,
             from: ReceiveFrom::Id(from),
-        }
+        })
     }
 
     fn receive(mut self) {
+        if self.receive_notification() {
+            return;
+        }
+
         if self.is_sender_waiting() {
             self.copy_msg_and_wake();
         } else {
@@ -325,12 +554,42 @@ impl<'a> Receiver<'a> {
         }
     }
 
+    fn try_receive(mut self) -> bool {
+        if self.receive_notification() {
+            return true;
+        }
+
+        let waiting = self.is_sender_waiting();
+
+        if waiting {
+            self.copy_msg_and_wake();
+        }
+
+        waiting
+    }
+
+    /// Receives a notification from the kernel if any. Returns `true` if one is received.
+    fn receive_notification(&mut self) -> bool {
+        if self.from != ReceiveFrom::Any {
+            return false;
+        }
+
+        let m = self.manager.running_as_mut().notifications.pop_front();
+
+        if let Some(m) = m {
+            // SAFETY: `msg_buf` is the buffer of the receiver.
+            unsafe { write_msg(self.msg_buf, m) }
+        }
+
+        m.is_some()
+    }
+
     fn is_sender_waiting(&self) -> bool {
         if let ReceiveFrom::Id(id) = self.from {
             let p = self.manager.process_as_ref(id);
             let p = p.expect("The sender does not exist.");
 
-            p.send_to == Some(id)
+            p.send_to == Some(self.manager.running)
         } else {
             let p = self.manager.running_as_ref();
 
@@ -347,6 +606,10 @@ impl<'a> Receiver<'a> {
 
     fn src_pid(&mut self) -> Pid {
         if let ReceiveFrom::Id(id) = self.from {
+            let p = self.manager.running_as_mut();
+
+            p.pids_try_to_send_this_process.retain(|pid| *pid != id);
+
             id
         } else {
             let p = self.manager.running_as_mut();
@@ -501,6 +764,16 @@ unsafe fn copy_msg(src: PhysAddr, dst: PhysAddr, sender_slot_id: Pid) {
     dst.write_volatile(src.read_volatile());
 }
 
+/// # Safety
+///
+/// `dst` must be the correct address to save a message.
+unsafe fn write_msg(dst: PhysAddr, m: Message) {
+    // SAFETY: The caller must ensure that `dst` is the correct address to save a message.
+    let mut dst: Single<Message> = unsafe { mem::accessor::new(dst) };
+
+    dst.write_volatile(m);
+}
+
 fn virt_to_phys(v: VirtAddr) -> PhysAddr {
     paging::translate_addr(v).expect("Failed to convert a virtual address to physical one.")
 }
@@ -524,4 +797,8 @@ impl RunnablePids {
     fn pop(&mut self) -> Option<Pid> {
         self.0.iter_mut().find_map(VecDeque::pop_front)
     }
+
+    fn is_empty(&self) -> bool {
+        self.0.iter().all(VecDeque::is_empty)
+    }
 }
@@ -7,6 +7,13 @@ use {
 pub(super) enum Status {
     Running,
     Runnable,
-    Sending { to: Pid, message: PhysAddr },
+    Sending {
+        to: Pid,
+        message: PhysAddr,
+    },
     Receiving(ReceiveFrom),
+    /// The process is in a [`super::WaitQueue`].
+    Blocked,
+    /// The process has exited, and it is freed when another process exits.
+    Exited,
 }
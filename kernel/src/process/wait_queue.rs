@@ -0,0 +1,34 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use {
+    super::{scheduler, Pid},
+    alloc::collections::VecDeque,
+};
+
+/// The processes blocked until an event happens, such as the arrival of bytes in a pipe.
+///
+/// A process adds itself with [`WaitQueue::add_current`], releases the lock protecting the queue,
+/// and calls [`scheduler::block`]. The interrupts must stay disabled in between so that the
+/// process does not miss [`WaitQueue::wake_all`].
+#[derive(Debug, Default)]
+pub(crate) struct WaitQueue(VecDeque<Pid>);
+impl WaitQueue {
+    pub(crate) const fn new() -> Self {
+        Self(VecDeque::new())
+    }
+
+    pub(crate) fn add_current(&mut self) {
+        let pid = scheduler::current_pid();
+
+        if !self.0.contains(&pid) {
+            self.0.push_back(pid);
+        }
+    }
+
+    /// Makes all the waiting processes runnable. They check the event again when they run.
+    pub(crate) fn wake_all(&mut self) {
+        for pid in self.0.drain(..) {
+            scheduler::wake(pid);
+        }
+    }
+}
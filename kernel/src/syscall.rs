@@ -1,13 +1,30 @@
 use {
     crate::{
+        acpi::{self, power},
+        console,
+        fs::{self, file::OpenFile},
         gdt,
-        mem::{allocator, paging},
-        process::{self, Pid},
+        interrupt::{irq, timer},
+        mem::{
+            allocator::{self, phys},
+            paging, shared, swap, user, vma,
+        },
+        pipe,
+        process::{self, descriptor::Object, Pid},
+    },
+    alloc::{string::String, sync::Arc, vec::Vec},
+    core::{
+        arch::asm,
+        convert::{TryFrom, TryInto},
     },
-    core::{arch::asm, convert::TryInto, ffi::c_void, panic::PanicInfo, slice},
+    log::{error, warn, Level, LevelFilter},
     num_traits::FromPrimitive,
     os_units::{Bytes, NumOfPages},
-    terminal::print,
+    spinning_top::Spinlock,
+    syscalls::{
+        Clock, DirEntry, DmaConstraints, Interrupt, LogRecord, MemoryStats, MsiMessage, Permission,
+        ProcessMemoryStats, SharedMemoryHandle, Stat, Whence, FS_ERROR, MAX_STR_LEN,
+    },
     x86_64::{
         registers::{
             model_specific::{Efer, EferFlags, LStar, Msr, Star},
@@ -20,6 +37,11 @@ use {
 
 const IA32_FMASK: Msr = Msr::new(0xc000_0084);
 
+/// The maximum number of bytes which one `write` system call copies from the user memory.
+///
+/// The bytes are copied to the kernel heap, so an unbounded length could exhaust it.
+const MAX_WRITE_LEN: u64 = 4096;
+
 pub(super) fn init() {
     register_handler();
 
@@ -32,6 +54,7 @@ pub(super) fn init() {
     }
 
     disable_interrupts_on_syscall();
+    disable_user_access_on_syscall();
 }
 
 /// # Safety
@@ -68,6 +91,17 @@ fn disable_interrupts_on_syscall() {
     }
 }
 
+/// Clears RFLAGS.AC on a system call.
+///
+/// Otherwise a user process could set the flag and let the kernel access the user memory
+/// regardless of SMAP.
+fn disable_user_access_on_syscall() {
+    // SAFETY: Clearing the AC flag on a system call does not violate memory safety.
+    unsafe {
+        update_ia32_fmask(|mask| mask.insert(RFlags::ALIGNMENT_CHECK));
+    }
+}
+
 /// # Safety
 ///
 /// See: [`x86_64::registers::rflags::write`].
@@ -167,51 +201,206 @@ unsafe fn select_proper_syscall_unchecked(ty: syscalls::Ty, a1: u64, a2: u64, a3
             sys_allocate_pages(NumOfPages::new(a1.try_into().unwrap())).as_u64()
         }
         syscalls::Ty::DeallocatePages => {
-            sys_deallocate_pages(VirtAddr::new(a1), NumOfPages::new(a2.try_into().unwrap()))
-        }
-        syscalls::Ty::MapPages => {
-            sys_map_pages(PhysAddr::new(a1), Bytes::new(a2.try_into().unwrap())).as_u64()
-        }
-        syscalls::Ty::UnmapPages => {
-            sys_unmap_pages(VirtAddr::new(a1), Bytes::new(a2.try_into().unwrap()))
+            sys_deallocate_pages(a1, NumOfPages::new(a2.try_into().unwrap()))
         }
+        syscalls::Ty::MapPages => sys_map_pages(a1, Bytes::new(a2.try_into().unwrap())).as_u64(),
+        syscalls::Ty::UnmapPages => sys_unmap_pages(a1, Bytes::new(a2.try_into().unwrap())),
         syscalls::Ty::TranslateAddress => sys_translate_address(VirtAddr::new(a1)).as_u64(),
-        // SAFETY: The caller must ensure that `a2` is the correct pointer to the string.
-        syscalls::Ty::Write => unsafe {
-            sys_write(
-                a1.try_into().unwrap(),
-                a2 as *const _,
-                a3.try_into().unwrap(),
-            )
-            .try_into()
-            .unwrap()
-        },
-        syscalls::Ty::Send => sys_send(VirtAddr::new(a1), a2.try_into().unwrap()),
+        // SAFETY: The caller must ensure that `a2` is the correct pointer to the buffer of `a3`
+        // bytes.
+        syscalls::Ty::Write => unsafe { sys_write(a1, a2 as *const _, a3) },
+        syscalls::Ty::Send => sys_send(VirtAddr::new(a1), a2),
         syscalls::Ty::ReceiveFromAny => sys_receive_from_any(VirtAddr::new(a1)),
-        syscalls::Ty::ReceiveFrom => sys_receive_from(VirtAddr::new(a1), a2.try_into().unwrap()),
-        // SAFETY: The caller must ensure that `a1` is the correct pointer to the panic
-        // information.
-        syscalls::Ty::Panic => unsafe { sys_panic(a1 as *const PanicInfo<'_>) },
+        syscalls::Ty::ReceiveFrom => sys_receive_from(VirtAddr::new(a1), a2),
+        syscalls::Ty::TryReceiveFromAny => sys_try_receive_from_any(VirtAddr::new(a1)),
+        // SAFETY: The caller must ensure that `a1` is the correct pointer to the message of `a2`
+        // bytes.
+        syscalls::Ty::Panic => unsafe { sys_panic(a1 as *const _, a2) },
+        syscalls::Ty::CreateSharedMemory => {
+            sys_create_shared_memory(Bytes::new(a1.try_into().unwrap()))
+        }
+        syscalls::Ty::MapSharedMemory => sys_map_shared_memory(a1, a2).as_u64(),
+        syscalls::Ty::UnmapSharedMemory => sys_unmap_shared_memory(VirtAddr::new(a1)),
+        syscalls::Ty::GrantSharedMemory => sys_grant_shared_memory(a1, a2),
+        // SAFETY: The caller must ensure that `a2` is the correct pointer to the constraints.
+        syscalls::Ty::AllocateDmaPages => unsafe {
+            sys_allocate_dma_pages(Bytes::new(a1.try_into().unwrap()), a2 as *const _).as_u64()
+        },
+        // SAFETY: The caller must ensure that `a1` and `a2` are the correct pointers to the
+        // buffers.
+        syscalls::Ty::GetMemoryStats => unsafe {
+            sys_get_memory_stats(a1 as *mut _, a2 as *mut _, a3.try_into().unwrap())
+        },
+        syscalls::Ty::RegisterSwapDevice => sys_register_swap_device(a1, a2),
+        syscalls::Ty::IrqRegister => sys_irq_register(a1, a2),
+        syscalls::Ty::IrqAck => sys_irq_ack(a1, a2),
+        syscalls::Ty::IrqUnregister => sys_irq_unregister(a1, a2),
+        // SAFETY: The caller must ensure that `a2` is the correct pointer to the buffer.
+        syscalls::Ty::GetMsiMessage => unsafe { sys_get_msi_message(a1, a2 as *mut _) },
+        // SAFETY: The caller must ensure that `a2` is the correct pointer to the buffer of `a3`
+        // bytes.
+        syscalls::Ty::GetAcpiTable => unsafe {
+            sys_get_acpi_table(a1, a2 as *mut _, a3.try_into().unwrap())
+        },
+        syscalls::Ty::ClockGetTime => sys_clock_gettime(a1),
+        syscalls::Ty::ConsoleInput => sys_console_input(a1),
+        // SAFETY: The caller must ensure that `a2` is the correct pointer to the buffer of `a3`
+        // records.
+        syscalls::Ty::ReadKernelLog => unsafe {
+            sys_read_kernel_log(a1, a2 as *mut _, a3.try_into().unwrap())
+        },
+        // SAFETY: The caller must ensure that `a2` is the correct pointer to the strings.
+        syscalls::Ty::Log => unsafe { sys_log(a1, a2 as *const _) },
+        // SAFETY: The caller must ensure that `a2` is the correct pointer to the string of `a3`
+        // bytes.
+        syscalls::Ty::SetLogLevel => unsafe { sys_set_log_level(a1, a2 as *const _, a3) },
+        // SAFETY: The caller must ensure that `a1` is the correct pointer to the path of `a2`
+        // bytes.
+        syscalls::Ty::Open => unsafe { sys_open(a1 as *const _, a2) },
+        // SAFETY: The caller must ensure that `a2` is the correct pointer to the buffer of `a3`
+        // bytes.
+        syscalls::Ty::Read => unsafe { sys_read(a1, a2 as *mut _, a3) },
+        syscalls::Ty::Seek => sys_seek(a1, a2, a3),
+        // SAFETY: The caller must ensure that `a1` is the correct pointer to the path of `a2` bytes
+        // and `a3` is the correct pointer to the buffer.
+        syscalls::Ty::Stat => unsafe { sys_stat(a1 as *const _, a2, a3 as *mut _) },
+        // SAFETY: The caller must ensure that `a2` is the correct pointer to the buffer.
+        syscalls::Ty::ReadDir => unsafe { sys_read_dir(a1, a2 as *mut _) },
+        syscalls::Ty::Close => sys_close(a1),
+        syscalls::Ty::Dup => sys_dup(a1),
+        syscalls::Ty::Dup2 => sys_dup2(a1, a2),
+        // SAFETY: The caller must ensure that `a1` is the correct pointer to the buffer.
+        syscalls::Ty::Pipe => unsafe { sys_pipe(a1 as *mut _) },
+        syscalls::Ty::OpenServer => sys_open_server(a1, a2),
+        // SAFETY: The caller must ensure that `a2` is the correct pointer to the buffer.
+        syscalls::Ty::ServerOf => unsafe { sys_server_of(a1, a2 as *mut _) },
+        // SAFETY: The caller must ensure that `a1` is the correct pointer to the path of `a2`
+        // bytes.
+        syscalls::Ty::Spawn => unsafe { sys_spawn(a1 as *const _, a2) },
+        syscalls::Ty::Exit => process::exit(),
+        syscalls::Ty::Shutdown => sys_shutdown(),
+        syscalls::Ty::Reboot => sys_reboot(),
         _ => unreachable!("This sytem call should not be handled by the kernel itself."),
     }
 }
 
+fn sys_shutdown() -> u64 {
+    if process::scheduler::current_is_privileged() {
+        power::shutdown();
+    }
+
+    0
+}
+
+fn sys_reboot() -> u64 {
+    if process::scheduler::current_is_privileged() {
+        power::reboot();
+    }
+
+    0
+}
+
 fn sys_allocate_pages(num_of_pages: NumOfPages<Size4KiB>) -> VirtAddr {
     allocator::allocate_pages_for_user(num_of_pages).unwrap_or_else(VirtAddr::zero)
 }
 
-fn sys_deallocate_pages(virt: VirtAddr, pages: NumOfPages<Size4KiB>) -> u64 {
-    allocator::deallocate_pages(virt, pages);
-    0
+/// # Safety
+///
+/// `constraints` must be valid.
+unsafe fn sys_allocate_dma_pages(bytes: Bytes, constraints: *const DmaConstraints) -> VirtAddr {
+    // SAFETY: The caller ensures that `constraints` is valid.
+    let constraints = match unsafe { user::read(constraints) } {
+        Some(c) => c,
+        None => return VirtAddr::zero(),
+    };
+
+    if !constraints.is_satisfiable_for(bytes) {
+        return VirtAddr::zero();
+    }
+
+    allocator::allocate_dma_pages_for_user(bytes.as_num_of_pages(), &constraints)
+        .unwrap_or_else(VirtAddr::zero)
+}
+
+/// Returns the number of the processes, or 0 if the statistics cannot be collected.
+///
+/// # Safety
+///
+/// `stats` must be valid, and `processes` must be valid for `len` elements.
+unsafe fn sys_get_memory_stats(
+    stats: *mut MemoryStats,
+    processes: *mut ProcessMemoryStats,
+    len: usize,
+) -> u64 {
+    let s = phys::stats();
+    let s = MemoryStats::new(s.total, s.free, s.reserved, s.largest_free_run);
+
+    // SAFETY: The caller ensures that `stats` is valid.
+    if unsafe { user::write(stats, s) }.is_none() {
+        return 0;
+    }
+
+    let mut n = 0;
+
+    let r = process::scheduler::try_for_each_memory_usage(|pid, name, pages| {
+        if n < len {
+            // SAFETY: The caller ensures that `processes` is valid for `len` elements.
+            let _ = unsafe {
+                user::write(
+                    processes.wrapping_add(n),
+                    ProcessMemoryStats::new(pid, name, pages),
+                )
+            };
+        }
+
+        n += 1;
+    });
+
+    r.map_or(0, |()| n.try_into().unwrap())
 }
 
-fn sys_map_pages(start: PhysAddr, bytes: Bytes) -> VirtAddr {
-    crate::mem::map_pages_for_user(start, bytes)
+fn sys_deallocate_pages(virt: u64, pages: NumOfPages<Size4KiB>) -> u64 {
+    VirtAddr::try_new(virt)
+        .ok()
+        .and_then(|virt| allocator::deallocate_pages_for_user(virt, pages))
+        .map_or(0, |()| 1)
 }
 
-fn sys_unmap_pages(start: VirtAddr, bytes: Bytes) -> u64 {
-    crate::mem::unmap_pages(start, bytes);
-    0
+fn sys_map_pages(start: u64, bytes: Bytes) -> VirtAddr {
+    PhysAddr::try_new(start)
+        .ok()
+        .and_then(|start| crate::mem::map_pages_for_user(start, bytes))
+        .unwrap_or_else(VirtAddr::zero)
+}
+
+fn sys_unmap_pages(start: u64, bytes: Bytes) -> u64 {
+    VirtAddr::try_new(start)
+        .ok()
+        .and_then(|start| crate::mem::unmap_pages_for_user(start, bytes))
+        .map_or(0, |()| 1)
+}
+
+fn sys_create_shared_memory(bytes: Bytes) -> u64 {
+    shared::create(bytes).unwrap_or(0)
+}
+
+fn sys_map_shared_memory(handle: SharedMemoryHandle, permission: u64) -> VirtAddr {
+    let permission: Option<Permission> = FromPrimitive::from_u64(permission);
+
+    permission
+        .and_then(|p| shared::map(handle, p))
+        .unwrap_or_else(VirtAddr::zero)
+}
+
+fn sys_unmap_shared_memory(start: VirtAddr) -> u64 {
+    shared::unmap(start).map_or(0, |()| 1)
+}
+
+fn sys_grant_shared_memory(handle: SharedMemoryHandle, pid: u64) -> u64 {
+    Pid::try_from(pid)
+        .ok()
+        .and_then(|pid| shared::grant(handle, pid))
+        .map_or(0, |()| 1)
 }
 
 fn sys_translate_address(v: VirtAddr) -> PhysAddr {
@@ -220,45 +409,415 @@ fn sys_translate_address(v: VirtAddr) -> PhysAddr {
 
 /// # Safety
 ///
-/// `buf` must be valid.
-unsafe fn sys_write(fildes: i32, buf: *const c_void, nbyte: u32) -> i32 {
-    if fildes == 1 {
-        let buf: *const u8 = buf.cast();
+/// `buf` must be valid for `len` bytes.
+unsafe fn sys_write(fildes: u64, buf: *const u8, len: u64) -> u64 {
+    // Writing fewer bytes than requested is allowed, as with the pipes and the sockets of POSIX.
+    let len = len.min(MAX_WRITE_LEN);
 
+    let buf = len.try_into().ok().and_then(|len| {
         // SAFETY: The caller ensures that `buf` is valid.
-        let s = unsafe { slice::from_raw_parts(buf, nbyte.try_into().unwrap()) };
-        let s = core::str::from_utf8(s);
+        unsafe { user::read_bytes(buf, len) }
+    });
+
+    descriptor(fildes)
+        .zip(buf)
+        .and_then(|(object, buf)| object.write(&buf))
+        .map_or(FS_ERROR, |len| len.try_into().unwrap())
+}
+
+fn sys_send(m: VirtAddr, to: u64) -> u64 {
+    Pid::try_from(to)
+        .map_or(false, |to| process::ipc::send(m, to))
+        .into()
+}
+
+fn sys_receive_from_any(m: VirtAddr) -> u64 {
+    process::ipc::receive_from_any(m);
+    0
+}
+
+fn sys_receive_from(m: VirtAddr, from: u64) -> u64 {
+    Pid::try_from(from)
+        .map_or(false, |from| process::ipc::receive_from(m, from))
+        .into()
+}
+
+fn sys_try_receive_from_any(m: VirtAddr) -> u64 {
+    process::ipc::try_receive_from_any(m).into()
+}
+
+fn sys_register_swap_device(block_size: u64, address_limit: u64) -> u64 {
+    if !process::scheduler::current_is_privileged() {
+        return false.into();
+    }
+
+    swap::register(block_size, address_limit).into()
+}
 
-        if let Ok(s) = s {
-            print!("{}", s);
+fn sys_irq_register(kind: u64, number: u64) -> u64 {
+    if !process::scheduler::current_is_privileged() {
+        return false.into();
+    }
+
+    Interrupt::from_raw(kind, number)
+        .map_or(false, irq::register)
+        .into()
+}
+
+fn sys_irq_ack(kind: u64, number: u64) -> u64 {
+    Interrupt::from_raw(kind, number)
+        .map_or(false, irq::ack)
+        .into()
+}
+
+fn sys_irq_unregister(kind: u64, number: u64) -> u64 {
+    Interrupt::from_raw(kind, number)
+        .map_or(false, irq::unregister)
+        .into()
+}
+
+unsafe fn sys_get_msi_message(vector: u64, message: *mut MsiMessage) -> u64 {
+    let m = u8::try_from(vector).ok().and_then(irq::msi_message);
+
+    m.map_or(false, |m| {
+        // SAFETY: The caller must ensure that `message` is the correct pointer.
+        unsafe { user::write(message, m) }.is_some()
+    })
+    .into()
+}
+
+/// # Safety
+///
+/// `buf` must be valid for `len` records.
+unsafe fn sys_read_kernel_log(from: u64, buf: *mut LogRecord, len: usize) -> u64 {
+    let mut records = Vec::new();
+
+    // Copy the records first because writing to the user memory may fault.
+    terminal::log::for_each_record_since(from, |r| {
+        if records.len() < len {
+            records.push(*r);
+        }
+    });
+
+    let mut n = 0;
 
-            nbyte.try_into().unwrap()
-        } else {
-            0
+    for r in records {
+        // SAFETY: The caller ensures that `buf` is valid for `len` records.
+        if unsafe { user::write(buf.wrapping_add(n), r) }.is_none() {
+            break;
         }
-    } else {
-        unimplemented!("Not stdout");
+
+        n += 1;
     }
+
+    n.try_into().unwrap()
 }
 
-fn sys_send(m: VirtAddr, to: Pid) -> u64 {
-    process::ipc::send(m, to);
+/// # Safety
+///
+/// `strs` must point to the pointers and the lengths of the module and the message.
+unsafe fn sys_log(level: u64, strs: *const [u64; 4]) -> u64 {
+    let Some(level) = Level::iter().find(|l| *l as u64 == level) else {
+        return 0;
+    };
+
+    // SAFETY: The caller ensures that `strs` is valid.
+    let Some([module, module_len, message, message_len]) = (unsafe { user::read(strs) }) else {
+        return 0;
+    };
+
+    // SAFETY: The caller ensures that the strings are valid.
+    let strs = unsafe {
+        read_str(module as *const _, module_len).zip(read_str(message as *const _, message_len))
+    };
+
+    if let Some((module, message)) = strs {
+        terminal::log::log_for_process(
+            process::scheduler::current_pid(),
+            process::scheduler::current_process_name(),
+            level,
+            &module,
+            format_args!("{}", message),
+        );
+    }
+
     0
 }
 
-fn sys_receive_from_any(m: VirtAddr) -> u64 {
-    process::ipc::receive_from_any(m);
-    0
+/// # Safety
+///
+/// `target` must be valid for `len` bytes.
+unsafe fn sys_set_log_level(level: u64, target: *const u8, len: u64) -> u64 {
+    if !process::scheduler::current_is_privileged() {
+        return false.into();
+    }
+
+    let level = LevelFilter::iter().find(|l| *l as u64 == level);
+
+    // SAFETY: The caller ensures that `target` is valid.
+    let target = unsafe { read_str(target, len) };
+
+    level
+        .zip(target)
+        .map(|(level, target)| terminal::log::set_level(&target, level))
+        .is_some()
+        .into()
+}
+
+/// # Safety
+///
+/// `path` must be valid for `len` bytes.
+unsafe fn sys_open(path: *const u8, len: u64) -> u64 {
+    // SAFETY: The caller ensures that `path` is valid.
+    let path = unsafe { read_str(path, len) };
+
+    path.and_then(|path| OpenFile::open(&path).ok())
+        .map_or(FS_ERROR, |f| {
+            insert_descriptor(Object::File(Spinlock::new(f)))
+        })
+}
+
+/// # Safety
+///
+/// `buf` must be valid for `len` bytes.
+unsafe fn sys_read(fildes: u64, buf: *mut u8, len: u64) -> u64 {
+    // Reading the console or a pipe may wait for the other processes, so the scheduler must not be
+    // locked here.
+    let read = descriptor(fildes)
+        .zip(len.try_into().ok())
+        .and_then(|(object, len)| {
+            // SAFETY: The caller ensures that `buf` is valid.
+            object.read(len, |read| unsafe { user::write_bytes(buf, read) })
+        });
+
+    read.map_or(FS_ERROR, |len| len.try_into().unwrap())
+}
+
+fn sys_seek(fildes: u64, offset: u64, whence: u64) -> u64 {
+    let offset = i64::from_ne_bytes(offset.to_ne_bytes());
+    let whence: Option<Whence> = FromPrimitive::from_u64(whence);
+
+    descriptor(fildes)
+        .zip(whence)
+        .and_then(|(object, whence)| object.seek(offset, whence))
+        .unwrap_or(FS_ERROR)
+}
+
+/// # Safety
+///
+/// `path` must be valid for `len` bytes, and `buf` must be valid.
+unsafe fn sys_stat(path: *const u8, len: u64, buf: *mut Stat) -> u64 {
+    // SAFETY: The caller ensures that `path` is valid.
+    let path = unsafe { read_str(path, len) };
+    let stat = path
+        .and_then(|path| fs::lookup(&path).ok())
+        .map(|n| n.stat());
+
+    // SAFETY: The caller ensures that `buf` is valid.
+    stat.and_then(|stat| unsafe { user::write(buf, stat) })
+        .map_or(FS_ERROR, |()| 0)
+}
+
+/// # Safety
+///
+/// `buf` must be valid.
+unsafe fn sys_read_dir(fildes: u64, buf: *mut DirEntry) -> u64 {
+    let entry = descriptor(fildes).and_then(|object| object.read_dir());
+
+    // SAFETY: The caller ensures that `buf` is valid.
+    entry
+        .and_then(|entry| unsafe { user::write(buf, entry) })
+        .is_some()
+        .into()
+}
+
+fn sys_close(fildes: u64) -> u64 {
+    let closed = fildes.try_into().ok().and_then(|fildes| {
+        process::scheduler::with_current_descriptors(|descriptors| descriptors.remove(fildes))
+    });
+
+    // Closing an object may send a message, so drop it after unlocking the scheduler.
+    closed.map_or(FS_ERROR, |_| 0)
+}
+
+fn sys_dup(fildes: u64) -> u64 {
+    descriptor(fildes).map_or(FS_ERROR, |object| {
+        let fildes = process::scheduler::with_current_descriptors(|d| d.insert(object));
+
+        fildes.try_into().unwrap()
+    })
+}
+
+fn sys_dup2(old: u64, new: u64) -> u64 {
+    let new = new.try_into().ok();
+
+    descriptor(old).zip(new).map_or(FS_ERROR, |(object, new)| {
+        let replaced = process::scheduler::with_current_descriptors(|d| d.replace(new, object));
+
+        // Ditto as `sys_close`.
+        drop(replaced);
+
+        new.try_into().unwrap()
+    })
+}
+
+/// # Safety
+///
+/// `buf` must be valid.
+unsafe fn sys_pipe(buf: *mut [i32; 2]) -> u64 {
+    let (reader, writer) = pipe::new();
+
+    let fildes = process::scheduler::with_current_descriptors(|d| {
+        [
+            d.insert(Arc::new(Object::PipeReader(reader))),
+            d.insert(Arc::new(Object::PipeWriter(writer))),
+        ]
+    });
+
+    // SAFETY: The caller ensures that `buf` is valid.
+    if unsafe { user::write(buf, fildes) }.is_some() {
+        0
+    } else {
+        for fildes in fildes {
+            let closed = process::scheduler::with_current_descriptors(|d| d.remove(fildes));
+
+            drop(closed);
+        }
+
+        FS_ERROR
+    }
+}
+
+fn sys_open_server(pid: u64, handle: u64) -> u64 {
+    let pid = pid.try_into().ok().filter(|&pid| {
+        process::scheduler::exists(pid) && !process::scheduler::is_kernel_process(pid)
+    });
+
+    pid.map_or(FS_ERROR, |pid| {
+        insert_descriptor(Object::Server { pid, handle })
+    })
+}
+
+/// # Safety
+///
+/// `buf` must be valid.
+unsafe fn sys_server_of(fildes: u64, buf: *mut [u64; 2]) -> u64 {
+    let server = descriptor(fildes).and_then(|object| object.server());
+
+    // SAFETY: The caller ensures that `buf` is valid.
+    server
+        .and_then(|(pid, handle)| unsafe { user::write(buf, [pid.try_into().unwrap(), handle]) })
+        .map_or(FS_ERROR, |()| 0)
+}
+
+/// # Safety
+///
+/// `path` must be valid for `len` bytes.
+unsafe fn sys_spawn(path: *const u8, len: u64) -> u64 {
+    // SAFETY: The caller ensures that `path` is valid.
+    let path = unsafe { read_str(path, len) };
+    let name = path.and_then(|path| fs::find(&path).ok()).map(|f| f.name());
+
+    let pid = name.and_then(|name| {
+        process::spawn(name)
+            .map_err(|e| warn!("Failed to spawn {}: {:?}", name, e))
+            .ok()
+    });
+
+    pid.map_or(FS_ERROR, |pid| pid.try_into().unwrap())
+}
+
+/// Returns the object which `fildes` of the current process refers to.
+fn descriptor(fildes: u64) -> Option<Arc<Object>> {
+    let fildes = fildes.try_into().ok()?;
+
+    process::scheduler::with_current_descriptors(|d| d.get(fildes))
+}
+
+/// Adds `object` to the descriptors of the current process and returns its descriptor.
+fn insert_descriptor(object: Object) -> u64 {
+    let fildes = process::scheduler::with_current_descriptors(|d| d.insert(Arc::new(object)));
+
+    fildes.try_into().unwrap()
+}
+
+/// Returns [`None`] if the string is longer than [`MAX_STR_LEN`] bytes or not valid UTF-8.
+///
+/// # Safety
+///
+/// `s` must be valid for `len` bytes.
+unsafe fn read_str(s: *const u8, len: u64) -> Option<String> {
+    let len = usize::try_from(len)
+        .ok()
+        .filter(|&len| len <= MAX_STR_LEN)?;
+
+    // SAFETY: The caller ensures that `s` is valid.
+    let bytes = unsafe { user::read_bytes(s, len) }?;
+
+    String::from_utf8(bytes).ok()
 }
 
-fn sys_receive_from(m: VirtAddr, from: Pid) -> u64 {
-    process::ipc::receive_from(m, from);
+fn sys_console_input(c: u64) -> u64 {
+    // Only the keyboard drivers may type on the console.
+    if !process::scheduler::current_is_privileged() {
+        return 0;
+    }
+
+    if let Some(c) = u32::try_from(c).ok().and_then(char::from_u32) {
+        console::input(c);
+    }
+
     0
 }
 
-unsafe fn sys_panic(i: *const PanicInfo<'_>) -> ! {
+fn sys_clock_gettime(clock: u64) -> u64 {
+    let time = match FromPrimitive::from_u64(clock) {
+        Some(Clock::Realtime) => timer::realtime(),
+        Some(Clock::Monotonic) => timer::uptime(),
+        None => None,
+    };
+
+    time.map_or(0, |t| t.as_nanos().try_into().unwrap())
+}
+
+/// # Safety
+///
+/// `buf` must be valid for `len` bytes.
+unsafe fn sys_get_acpi_table(signature: u64, buf: *mut u8, len: usize) -> u64 {
+    if !process::scheduler::current_is_privileged() {
+        return 0;
+    }
+
+    let Ok(signature) = u32::try_from(signature) else {
+        return 0;
+    };
+
+    acpi::table::with_table(signature.to_le_bytes(), |table| {
+        let n = table.len().min(len);
+
+        // SAFETY: The caller ensures that `buf` is valid for `len` bytes.
+        unsafe { user::write_bytes(buf, &table[..n]) }.map(|()| table.len())
+    })
+    .flatten()
+    .map_or(0, |len| len.try_into().unwrap())
+}
+
+/// # Safety
+///
+/// `message` must be valid for `len` bytes.
+unsafe fn sys_panic(message: *const u8, len: u64) -> ! {
     let name = process::scheduler::current_process_name();
 
-    // SAFETY: The caller must ensure that `i` is the correct pointer to the panic information.
-    panic!("The process {} paniced: {}", name, unsafe { &*i });
+    vma::dump();
+
+    // SAFETY: The caller ensures that `message` is valid.
+    let message = unsafe { read_str(message, len) };
+
+    error!(
+        "The process {} paniced: {}",
+        name,
+        message.as_deref().unwrap_or("<invalid message>")
+    );
+
+    process::exit();
 }
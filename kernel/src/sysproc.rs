@@ -1,5 +1,5 @@
 use {
-    crate::process::ipc,
+    crate::{acpi, process::ipc},
     core::{
         convert::{TryFrom, TryInto},
         mem::MaybeUninit,
@@ -7,6 +7,7 @@ use {
     log::warn,
     message::Message,
     num_traits::FromPrimitive,
+    syscalls::UNSUPPORTED_REQUEST,
     x86_64::{
         instructions::port::{PortReadOnly, PortWriteOnly},
         structures::port::{PortRead, PortWrite},
@@ -37,6 +38,7 @@ fn handle_message(m: Message) {
         select_system_calls(m, t);
     } else {
         warn!("Unrecognized message: {:?}", m);
+        reply_with_result(m, UNSUPPORTED_REQUEST);
     }
 }
 
@@ -46,7 +48,14 @@ fn select_system_calls(m: Message, t: syscalls::Ty) {
         syscalls::Ty::Inl => unsafe { reply_inl(m) },
         syscalls::Ty::Outb => unsafe { reply_outb(m) },
         syscalls::Ty::Outl => unsafe { reply_outl(m) },
-        _ => panic!("Not supported: {:?}", t),
+        syscalls::Ty::AcpiDevice => reply_acpi_device(m),
+        syscalls::Ty::AcpiResource => reply_acpi_resource(m),
+        syscalls::Ty::AcpiPciRoute => reply_acpi_pci_route(m),
+        _ => {
+            // The sender waits for a reply, so reply even if the request is not supported.
+            warn!("Not supported: {:?}", t);
+            reply_with_result(m, UNSUPPORTED_REQUEST);
+        }
     }
 }
 
@@ -74,9 +83,49 @@ unsafe fn reply_outl(m: Message) {
     reply_without_contents(m);
 }
 
+fn reply_acpi_device(m: Message) {
+    let hid = acpi::device::hid(m.body.1.try_into().unwrap());
+
+    let b = hid.map_or_else(message::Body::default, |h| {
+        message::Body(1, h.into_raw(), 0, 0, 0)
+    });
+
+    reply_with_body(m, b);
+}
+
+fn reply_acpi_resource(m: Message) {
+    let r = acpi::device::resource(m.body.1.try_into().unwrap(), m.body.2.try_into().unwrap());
+
+    let b = r.map_or_else(message::Body::default, |r| {
+        let (kind, a, b) = r.into_raw();
+
+        message::Body(kind, a, b, 0, 0)
+    });
+
+    reply_with_body(m, b);
+}
+
+fn reply_acpi_pci_route(m: Message) {
+    let irq = u8::try_from(m.body.1)
+        .ok()
+        .zip(u8::try_from(m.body.2).ok())
+        .and_then(|(device, pin)| acpi::device::route_pci_interrupt(device, pin));
+
+    let b = irq.map_or_else(message::Body::default, |irq| {
+        let (gsi, flags) = irq.into_raw();
+
+        message::Body(1, gsi, flags, 0, 0)
+    });
+
+    reply_with_body(m, b);
+}
+
 fn reply_with_result(received: Message, result: u64) {
+    reply_with_body(received, message::Body(result, 0, 0, 0, 0));
+}
+
+fn reply_with_body(received: Message, b: message::Body) {
     let h = message::Header::default();
-    let b = message::Body(result, 0, 0, 0, 0);
 
     let reply = Message::new(h, b);
     let to = received.header.sender;
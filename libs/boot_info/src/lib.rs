@@ -5,6 +5,7 @@ pub mod vram;
 
 use {
     mem::MemoryDescriptor,
+    os_units::Bytes,
     x86_64::{PhysAddr, VirtAddr},
 };
 
@@ -15,6 +16,7 @@ pub struct Info {
     vram_info: vram::Info,
     mem_map: mem::Map,
     rsdp: PhysAddr,
+    initrd_bytes: Bytes,
 }
 
 impl Info {
@@ -25,12 +27,14 @@ impl Info {
         vram_info: vram::Info,
         mem_map: mem::Map,
         rsdp: PhysAddr,
+        initrd_bytes: Bytes,
     ) -> Self {
         Self {
             entry_addr,
             vram_info,
             mem_map,
             rsdp,
+            initrd_bytes,
         }
     }
 
@@ -49,6 +53,12 @@ impl Info {
         self.rsdp
     }
 
+    /// Returns the size of the initrd mapped at `predefined_mmap::INITRD_ADDR`.
+    #[must_use]
+    pub fn initrd_bytes(&self) -> Bytes {
+        self.initrd_bytes
+    }
+
     #[must_use]
     pub fn mem_map_mut(&mut self) -> &mut [MemoryDescriptor] {
         self.mem_map.as_mut_slice()
@@ -7,8 +7,8 @@ extern crate alloc;
 use {
     alloc::vec::Vec,
     boot_info::mem::MemoryDescriptor,
-    core::fmt,
-    os_units::NumOfPages,
+    core::{convert::TryFrom, fmt},
+    os_units::{Bytes, NumOfPages},
     x86_64::{
         structures::paging::{FrameAllocator, FrameDeallocator, PhysFrame, Size4KiB},
         PhysAddr,
@@ -34,6 +34,42 @@ impl FrameManager {
 
         self.0.push(frames);
     }
+
+    #[must_use]
+    pub fn stats(&self) -> Stats {
+        let count = |available| {
+            self.0
+                .iter()
+                .filter(|f| f.available == available)
+                .map(|f| f.num_of_pages)
+                .fold(NumOfPages::new(0), |acc, n| acc + n)
+        };
+
+        let free = count(true);
+        let used = count(false);
+        let total = free + used;
+
+        let span = match (self.0.first(), self.0.last()) {
+            (Some(first), Some(last)) => {
+                Bytes::new(usize::try_from(last.end() - first.start).unwrap()).as_num_of_pages()
+            }
+            _ => NumOfPages::new(0),
+        };
+
+        let largest_free_run = self
+            .0
+            .iter()
+            .filter(|f| f.available)
+            .max_by_key(|f| f.num_of_pages.as_usize())
+            .map_or_else(|| NumOfPages::new(0), |f| f.num_of_pages);
+
+        Stats {
+            total,
+            free,
+            reserved: span - total,
+            largest_free_run,
+        }
+    }
 }
 impl FrameManager {
     pub fn alloc(&mut self, num_of_pages: NumOfPages<Size4KiB>) -> Option<PhysAddr> {
@@ -46,6 +82,46 @@ impl FrameManager {
         None
     }
 
+    /// Allocates `num_of_pages` frames whose start address is aligned to `align`.
+    pub fn alloc_aligned(
+        &mut self,
+        num_of_pages: NumOfPages<Size4KiB>,
+        align: u64,
+    ) -> Option<PhysAddr> {
+        self.alloc_with_constraints(num_of_pages, &Constraints::aligned(align))
+    }
+
+    /// Allocates `num_of_pages` physically contiguous frames which satisfy `constraints`.
+    pub fn alloc_with_constraints(
+        &mut self,
+        num_of_pages: NumOfPages<Size4KiB>,
+        constraints: &Constraints,
+    ) -> Option<PhysAddr> {
+        for i in 0..self.0.len() {
+            if let Some(start) = self.0[i].start_for_allocating(num_of_pages, constraints) {
+                return Some(self.alloc_aligned_from_frames_at(i, start, num_of_pages));
+            }
+        }
+
+        None
+    }
+
+    fn alloc_aligned_from_frames_at(
+        &mut self,
+        i: usize,
+        start: PhysAddr,
+        n: NumOfPages<Size4KiB>,
+    ) -> PhysAddr {
+        if start == self.0[i].start {
+            return self.alloc_from_frames_at(i, n);
+        }
+
+        let head = Bytes::new(usize::try_from(start - self.0[i].start).unwrap());
+        self.split_frames(i, head.as_num_of_pages());
+
+        self.alloc_from_frames_at(i + 1, n)
+    }
+
     fn alloc_from_frames_at(&mut self, i: usize, n: NumOfPages<Size4KiB>) -> PhysAddr {
         if self.0[i].is_splittable(n) {
             self.split_frames(i, n);
@@ -83,6 +159,43 @@ impl FrameManager {
         }
     }
 
+    /// Frees `num_of_pages` frames from `addr`, which may be a part of allocated frames.
+    ///
+    /// This method does nothing if the range is not in a single run of allocated frames.
+    pub fn free_range(&mut self, addr: PhysAddr, num_of_pages: NumOfPages<Size4KiB>) {
+        let end = addr + num_of_pages.as_bytes().as_usize();
+
+        let Some(mut i) = self
+            .0
+            .iter()
+            .position(|f| !f.available && f.start <= addr && end <= f.end())
+        else {
+            return;
+        };
+
+        if self.0[i].start < addr {
+            let head = Bytes::new(usize::try_from(addr - self.0[i].start).unwrap());
+            self.split_used_frames(i, head.as_num_of_pages());
+
+            i += 1;
+        }
+
+        if end < self.0[i].end() {
+            self.split_used_frames(i, num_of_pages);
+        }
+
+        self.free_memory_for_frames_at(i);
+    }
+
+    fn split_used_frames(&mut self, i: usize, num_of_pages: NumOfPages<Size4KiB>) {
+        let new_frames_start = self.0[i].start + num_of_pages.as_bytes().as_usize();
+        let new_frames_num = self.0[i].num_of_pages - num_of_pages;
+        let new_frames = Frames::new_for_used(new_frames_start, new_frames_num);
+
+        self.0[i].num_of_pages = num_of_pages;
+        self.0.insert(i + 1, new_frames);
+    }
+
     fn free_memory_for_frames_at(&mut self, i: usize) {
         self.0[i].available = true;
         self.merge_before_and_after_frames(i);
@@ -128,6 +241,62 @@ impl FrameDeallocator<Size4KiB> for FrameManager {
     }
 }
 
+/// Physical constraints of frames to allocate.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Constraints {
+    /// The alignment of the start address.
+    pub align: u64,
+    /// Frames must not cross any multiples of this value.
+    pub boundary: Option<u64>,
+    /// The exclusive upper limit of the addresses of the frames.
+    pub limit: u64,
+}
+impl Constraints {
+    #[must_use]
+    pub fn aligned(align: u64) -> Self {
+        Self {
+            align,
+            boundary: None,
+            limit: u64::MAX,
+        }
+    }
+
+    /// Returns the lowest address from `addr` which satisfies the alignment and boundary
+    /// constraints for `bytes` bytes.
+    fn start_from(&self, addr: u64, bytes: u64) -> u64 {
+        let start = x86_64::align_up(addr, self.align);
+
+        match self.boundary {
+            Some(b) if start / b != (start + bytes.saturating_sub(1)) / b => {
+                x86_64::align_up(start, b)
+            }
+            _ => start,
+        }
+    }
+}
+
+/// Statistics of the physical frames.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Stats {
+    /// The number of the frames which the manager manages.
+    pub total: NumOfPages<Size4KiB>,
+    /// The number of the frames which are not allocated.
+    pub free: NumOfPages<Size4KiB>,
+    /// The number of the frames in the gaps between the managed frames.
+    ///
+    /// These frames are used by the firmware, the boot loader, the kernel image, the initrd or
+    /// the devices.
+    pub reserved: NumOfPages<Size4KiB>,
+    /// The number of the frames of the largest physically contiguous free run.
+    pub largest_free_run: NumOfPages<Size4KiB>,
+}
+impl Stats {
+    #[must_use]
+    pub fn used(&self) -> NumOfPages<Size4KiB> {
+        self.total - self.free
+    }
+}
+
 #[derive(PartialEq, Eq)]
 struct Frames {
     start: PhysAddr,
@@ -143,7 +312,6 @@ impl Frames {
         }
     }
 
-    #[cfg(test)]
     fn new_for_used(start: PhysAddr, num_of_pages: NumOfPages<Size4KiB>) -> Self {
         Self {
             start,
@@ -160,6 +328,20 @@ impl Frames {
         self.num_of_pages >= request_num_of_pages && self.available
     }
 
+    fn start_for_allocating(
+        &self,
+        request_num_of_pages: NumOfPages<Size4KiB>,
+        constraints: &Constraints,
+    ) -> Option<PhysAddr> {
+        let bytes = request_num_of_pages.as_bytes().as_usize() as u64;
+
+        let start = constraints.start_from(self.start.as_u64(), bytes);
+        let end = start.checked_add(bytes)?;
+
+        (self.available && end <= self.end().as_u64() && end <= constraints.limit)
+            .then(|| PhysAddr::new(start))
+    }
+
     fn is_mergeable(&self, other: &Self) -> bool {
         self.available && other.available && self.is_consecutive(other)
     }
@@ -188,7 +370,7 @@ impl fmt::Debug for Frames {
 #[cfg(test)]
 mod tests {
     use {
-        super::{FrameManager, Frames},
+        super::{Constraints, FrameManager, Frames, Stats},
         os_units::NumOfPages,
         x86_64::PhysAddr,
     };
@@ -262,6 +444,80 @@ mod tests {
         assert_eq!(f, manager!(U 0 => 0x3000));
     }
 
+    #[test]
+    fn allocate_aligned() {
+        let mut f = manager!(A 0x1000 => 0x50_0000);
+        let a = f.alloc_aligned(NumOfPages::new(512), 0x20_0000);
+
+        assert_eq!(a, Some(PhysAddr::new(0x20_0000)));
+        assert_eq!(
+            f,
+            manager!(
+                A 0x1000 => 0x20_0000,
+                U 0x20_0000 => 0x40_0000,
+                A 0x40_0000 => 0x50_0000,
+            )
+        );
+    }
+
+    #[test]
+    fn allocate_aligned_from_aligned_frames() {
+        let mut f = manager!(A 0x20_0000 => 0x40_0000);
+        let a = f.alloc_aligned(NumOfPages::new(512), 0x20_0000);
+
+        assert_eq!(a, Some(PhysAddr::new(0x20_0000)));
+        assert_eq!(f, manager!(U 0x20_0000 => 0x40_0000));
+    }
+
+    #[test]
+    fn fail_to_allocate_aligned() {
+        let mut f = manager!(
+            A 0x1000 => 0x30_0000,
+            U 0x30_0000 => 0x40_0000,
+        );
+
+        let a = f.alloc_aligned(NumOfPages::new(512), 0x20_0000);
+        assert!(a.is_none());
+    }
+
+    #[test]
+    fn allocate_without_crossing_boundary() {
+        let mut f = manager!(A 0xe000 => 0x2_0000);
+        let c = Constraints {
+            align: 0x1000,
+            boundary: Some(0x1_0000),
+            limit: u64::MAX,
+        };
+
+        let a = f.alloc_with_constraints(NumOfPages::new(3), &c);
+
+        assert_eq!(a, Some(PhysAddr::new(0x1_0000)));
+        assert_eq!(
+            f,
+            manager!(
+                A 0xe000 => 0x1_0000,
+                U 0x1_0000 => 0x1_3000,
+                A 0x1_3000 => 0x2_0000,
+            )
+        );
+    }
+
+    #[test]
+    fn fail_to_allocate_below_limit() {
+        let mut f = manager!(
+            A 0 => 0x2000,
+            A 0x1_0000 => 0x2_0000,
+        );
+        let c = Constraints {
+            align: 0x1000,
+            boundary: None,
+            limit: 0x1_2000,
+        };
+
+        let a = f.alloc_with_constraints(NumOfPages::new(3), &c);
+        assert!(a.is_none());
+    }
+
     #[test]
     fn free_single_frames() {
         let mut f = manager!(U 0 => 0x3000);
@@ -319,6 +575,99 @@ mod tests {
         assert_eq!(f, manager!(A 0 => 0x10000))
     }
 
+    #[test]
+    fn free_middle_of_frames() {
+        let mut f = manager!(
+            A 0 => 0x1000,
+            U 0x1000 => 0x5000,
+        );
+
+        f.free_range(PhysAddr::new(0x2000), NumOfPages::new(2));
+
+        assert_eq!(
+            f,
+            manager!(
+                A 0 => 0x1000,
+                U 0x1000 => 0x2000,
+                A 0x2000 => 0x4000,
+                U 0x4000 => 0x5000,
+            )
+        );
+    }
+
+    #[test]
+    fn free_head_of_frames_and_merge() {
+        let mut f = manager!(
+            A 0 => 0x1000,
+            U 0x1000 => 0x5000,
+        );
+
+        f.free_range(PhysAddr::new(0x1000), NumOfPages::new(1));
+
+        assert_eq!(
+            f,
+            manager!(
+                A 0 => 0x2000,
+                U 0x2000 => 0x5000,
+            )
+        );
+    }
+
+    #[test]
+    fn free_range_outside_of_used_frames() {
+        let mut f = manager!(
+            U 0 => 0x2000,
+            A 0x2000 => 0x5000,
+        );
+
+        f.free_range(PhysAddr::new(0x1000), NumOfPages::new(2));
+
+        assert_eq!(
+            f,
+            manager!(
+                U 0 => 0x2000,
+                A 0x2000 => 0x5000,
+            )
+        );
+    }
+
+    #[test]
+    fn count_frames() {
+        let f = manager!(
+            A 0 => 0x1000,
+            A 0x2000 => 0xc000,
+            U 0xc000 => 0x10000,
+            U 0x10000 => 0x13000,
+            A 0x13000 => 0x15000,
+        );
+
+        assert_eq!(
+            f.stats(),
+            Stats {
+                total: NumOfPages::new(0x14),
+                free: NumOfPages::new(0xd),
+                reserved: NumOfPages::new(1),
+                largest_free_run: NumOfPages::new(0xa),
+            }
+        );
+        assert_eq!(f.stats().used(), NumOfPages::new(7));
+    }
+
+    #[test]
+    fn count_no_frames() {
+        let f = manager!();
+
+        assert_eq!(
+            f.stats(),
+            Stats {
+                total: NumOfPages::new(0),
+                free: NumOfPages::new(0),
+                reserved: NumOfPages::new(0),
+                largest_free_run: NumOfPages::new(0),
+            }
+        );
+    }
+
     #[test]
     fn mergable_two_frmaes() {
         let f1 = frames!(A 0x2000 => 0xc000);
@@ -1,7 +1,8 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 use {
-    os_units::NumOfPages,
+    os_units::{Bytes, NumOfPages},
+    syscalls::{DmaConstraints, DmaError},
     x86_64::{structures::paging::Size4KiB, VirtAddr},
 };
 
@@ -22,6 +23,19 @@ pub(super) fn allocate_pages(n: NumOfPages<Size4KiB>) -> VirtAddr {
     VirtAddr::from_ptr(p)
 }
 
+#[cfg(not(test))]
+pub(super) fn allocate_dma_pages(
+    bytes: Bytes,
+    constraints: &DmaConstraints,
+) -> Result<VirtAddr, DmaError> {
+    syscalls::allocate_dma_pages(bytes, constraints)
+}
+
+#[cfg(test)]
+pub(super) fn allocate_dma_pages(bytes: Bytes, _: &DmaConstraints) -> Result<VirtAddr, DmaError> {
+    Ok(allocate_pages(bytes.as_num_of_pages()))
+}
+
 #[cfg(not(test))]
 pub(super) fn deallocate_pages(v: VirtAddr, n: NumOfPages<Size4KiB>) {
     syscalls::deallocate_pages(v, n);
@@ -0,0 +1,197 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use {
+    super::alloc,
+    core::{
+        fmt,
+        marker::PhantomData,
+        mem,
+        ops::{Deref, DerefMut},
+        ptr, slice,
+    },
+    os_units::Bytes,
+    syscalls::{DmaConstraints, DmaError},
+    x86_64::{structures::paging::Size4KiB, PhysAddr, VirtAddr},
+};
+
+/// A box placed on physically contiguous memory which satisfies the given [`DmaConstraints`].
+pub struct DmaBox<T: ?Sized> {
+    virt: VirtAddr,
+    bytes: Bytes,
+    _marker: PhantomData<T>,
+}
+impl<T> DmaBox<T> {
+    /// # Errors
+    ///
+    /// This method returns an error if the memory which satisfies `constraints` cannot be
+    /// allocated.
+    pub fn new(x: T, constraints: &DmaConstraints) -> Result<Self, DmaError> {
+        super::assert_alignment(&x);
+
+        let mut b = Self::from_bytes(Bytes::new(mem::size_of::<T>()), constraints)?;
+
+        // SAFETY: This operation is safe because the memory `b.virt` points is allocated, and is
+        // page-aligned.
+        unsafe {
+            ptr::write(b.virt.as_mut_ptr(), x);
+        }
+
+        Ok(b)
+    }
+}
+impl<T> Deref for DmaBox<T> {
+    type Target = T;
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: This operation is safe because the memory region `virt` points is allocated and
+        // is not used by the others.
+        unsafe { &*self.virt.as_ptr() }
+    }
+}
+impl<T> DerefMut for DmaBox<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // SAFETY: This operation is safe because the memory region `virt` points is allocated and
+        // is not used by the others.
+        unsafe { &mut *self.virt.as_mut_ptr() }
+    }
+}
+impl<T> fmt::Debug for DmaBox<T>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T> DmaBox<[T]>
+where
+    T: Clone,
+{
+    /// # Errors
+    ///
+    /// This method returns an error if the memory which satisfies `constraints` cannot be
+    /// allocated.
+    pub fn new_slice(
+        x: T,
+        num_of_elements: usize,
+        constraints: &DmaConstraints,
+    ) -> Result<Self, DmaError> {
+        super::assert_alignment(&x);
+
+        let bytes = Bytes::new(mem::size_of::<T>() * num_of_elements);
+        let b = Self::from_bytes(bytes, constraints)?;
+
+        for i in 0..num_of_elements {
+            let p: *mut T = b.virt.as_mut_ptr();
+
+            // SAFETY: This operation is safe. The memory ptr points is allocated and is aligned
+            // because the first elements is page-aligned.
+            unsafe { ptr::write(p.add(i), x.clone()) }
+        }
+
+        Ok(b)
+    }
+
+    fn num_of_elements(&self) -> usize {
+        self.bytes.as_usize() / mem::size_of::<T>()
+    }
+}
+impl<T> Deref for DmaBox<[T]>
+where
+    T: Clone,
+{
+    type Target = [T];
+    fn deref(&self) -> &Self::Target {
+        unsafe { slice::from_raw_parts(self.virt.as_ptr(), self.num_of_elements()) }
+    }
+}
+impl<T> DerefMut for DmaBox<[T]>
+where
+    T: Clone,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { slice::from_raw_parts_mut(self.virt.as_mut_ptr(), self.num_of_elements()) }
+    }
+}
+impl<T> fmt::Debug for DmaBox<[T]>
+where
+    T: Clone,
+    [T]: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: ?Sized> DmaBox<T> {
+    #[must_use]
+    pub fn virt_addr(&self) -> VirtAddr {
+        self.virt
+    }
+
+    /// The memory is physically contiguous, so the returned address is the start of the whole
+    /// buffer.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the `DmaBox` is not mapped.
+    #[must_use]
+    pub fn phys_addr(&self) -> PhysAddr {
+        let a = syscalls::translate_address(self.virt);
+
+        if a.is_null() {
+            unreachable!("Address: {:?} is not mapped.", self.virt);
+        }
+
+        a
+    }
+
+    #[must_use]
+    pub fn bytes(&self) -> Bytes {
+        self.bytes
+    }
+
+    fn from_bytes(bytes: Bytes, constraints: &DmaConstraints) -> Result<Self, DmaError> {
+        if !constraints.is_satisfiable_for(bytes) {
+            return Err(DmaError::InvalidConstraints);
+        }
+
+        let virt = alloc::allocate_dma_pages(bytes, constraints)?;
+
+        Ok(Self {
+            virt,
+            bytes,
+            _marker: PhantomData,
+        })
+    }
+}
+impl<T: ?Sized> Drop for DmaBox<T> {
+    fn drop(&mut self) {
+        let num_of_pages = self.bytes.as_num_of_pages::<Size4KiB>();
+        alloc::deallocate_pages(self.virt, num_of_pages);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::DmaBox,
+        syscalls::{DmaConstraints, DmaError},
+    };
+
+    #[test]
+    fn new_slice() {
+        let c = DmaConstraints::new().align(64).boundary(0x1_0000);
+        let b = DmaBox::new_slice(3_u32, 5, &c);
+
+        assert_eq!(*b.unwrap(), [3; 5]);
+    }
+
+    #[test]
+    fn fail_to_cross_boundary() {
+        let c = DmaConstraints::new().boundary(0x1000);
+        let b = DmaBox::new_slice(0_u8, 0x2000, &c);
+
+        assert_eq!(b.err(), Some(DmaError::InvalidConstraints));
+    }
+}
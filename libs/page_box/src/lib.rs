@@ -19,6 +19,47 @@ use {
 };
 
 mod alloc;
+mod dma;
+mod pool;
+
+pub use {
+    dma::DmaBox,
+    pool::{DmaPool, PoolBox, PoolClassStats, PoolStats},
+    syscalls::{DmaConstraints, DmaError},
+};
+
+/// A buffer which a device can access with its physical address.
+pub trait DmaBuffer {
+    fn phys_addr(&self) -> PhysAddr;
+    fn bytes(&self) -> Bytes;
+}
+impl<T: ?Sized> DmaBuffer for PageBox<T> {
+    fn phys_addr(&self) -> PhysAddr {
+        PageBox::phys_addr(self)
+    }
+
+    fn bytes(&self) -> Bytes {
+        PageBox::bytes(self)
+    }
+}
+impl<T: ?Sized> DmaBuffer for DmaBox<T> {
+    fn phys_addr(&self) -> PhysAddr {
+        DmaBox::phys_addr(self)
+    }
+
+    fn bytes(&self) -> Bytes {
+        DmaBox::bytes(self)
+    }
+}
+impl<T: ?Sized> DmaBuffer for PoolBox<'_, T> {
+    fn phys_addr(&self) -> PhysAddr {
+        PoolBox::phys_addr(self)
+    }
+
+    fn bytes(&self) -> Bytes {
+        PoolBox::bytes(self)
+    }
+}
 
 pub struct PageBox<T: ?Sized> {
     virt: VirtAddr,
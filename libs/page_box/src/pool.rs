@@ -0,0 +1,521 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use {
+    super::alloc,
+    core::{
+        array, fmt,
+        marker::PhantomData,
+        mem,
+        ops::{Deref, DerefMut},
+        ptr::{self, NonNull},
+        slice,
+    },
+    os_units::Bytes,
+    spinning_top::Spinlock,
+    syscalls::{DmaConstraints, DmaError},
+    x86_64::{PhysAddr, VirtAddr},
+};
+
+const PAGE_SIZE: usize = 4096;
+const MIN_CHUNK_SIZE: usize = 64;
+
+/// The number of the size classes. The sizes are 64, 128, ..., 4096 bytes.
+const NUM_OF_CLASSES: usize = 7;
+
+/// A pool which carves physically contiguous pages into chunks for small DMA buffers.
+///
+/// Each size class of the pool serves chunks of a power of two bytes, from 64 bytes to 4 KiB. A
+/// chunk is aligned to its size, so it never crosses a page boundary. Pages are allocated with the
+/// constraints passed to [`DmaPool::new`], and they are kept in the pool once allocated.
+pub struct DmaPool {
+    constraints: DmaConstraints,
+    classes: Spinlock<[Class; NUM_OF_CLASSES]>,
+}
+impl DmaPool {
+    #[must_use]
+    pub fn new(constraints: DmaConstraints) -> Self {
+        Self {
+            constraints,
+            classes: Spinlock::new(array::from_fn(Class::new)),
+        }
+    }
+
+    /// # Errors
+    ///
+    /// This method returns [`DmaError::InvalidConstraints`] if `x` does not fit in a page or `T` is
+    /// a zero-sized type, and [`DmaError::OutOfMemory`] if a new page cannot be allocated.
+    pub fn alloc<T>(&self, x: T) -> Result<PoolBox<'_, T>, DmaError> {
+        let bytes = Bytes::new(mem::size_of::<T>());
+        let (virt, class) = self.alloc_chunk::<T>(bytes)?;
+
+        // SAFETY: This operation is safe because the chunk `virt` points is allocated, and is
+        // aligned to at least the alignment of `T`.
+        unsafe {
+            ptr::write(virt.as_mut_ptr(), x);
+        }
+
+        Ok(PoolBox {
+            pool: self,
+            virt,
+            bytes,
+            class,
+            drop_value: drop_value::<T>,
+            _marker: PhantomData,
+        })
+    }
+
+    /// # Errors
+    ///
+    /// This method returns [`DmaError::InvalidConstraints`] if the slice does not fit in a page or
+    /// `T` is a zero-sized type, and [`DmaError::OutOfMemory`] if a new page cannot be allocated.
+    pub fn alloc_slice<T: Clone>(
+        &self,
+        x: T,
+        num_of_elements: usize,
+    ) -> Result<PoolBox<'_, [T]>, DmaError> {
+        let bytes = mem::size_of::<T>()
+            .checked_mul(num_of_elements)
+            .ok_or(DmaError::InvalidConstraints)?;
+        let bytes = Bytes::new(bytes);
+        let (virt, class) = self.alloc_chunk::<T>(bytes)?;
+
+        for i in 0..num_of_elements {
+            let p: *mut T = virt.as_mut_ptr();
+
+            // SAFETY: This operation is safe because the chunk is large enough to hold all
+            // elements, and is aligned to at least the alignment of `T`.
+            unsafe { ptr::write(p.add(i), x.clone()) }
+        }
+
+        Ok(PoolBox {
+            pool: self,
+            virt,
+            bytes,
+            class,
+            drop_value: drop_slice::<T>,
+            _marker: PhantomData,
+        })
+    }
+
+    #[must_use]
+    pub fn stats(&self) -> PoolStats {
+        let classes = self.classes.lock();
+
+        PoolStats {
+            classes: array::from_fn(|i| classes[i].stats()),
+        }
+    }
+
+    /// Allocates a chunk for `bytes` bytes of `T`s and returns its address and its size class.
+    ///
+    /// Zero-sized types are rejected because a chunk for them is meaningless for DMA, and the
+    /// number of the elements of a slice of them cannot be derived from the size.
+    fn alloc_chunk<T>(&self, bytes: Bytes) -> Result<(VirtAddr, usize), DmaError> {
+        if mem::size_of::<T>() == 0 {
+            return Err(DmaError::InvalidConstraints);
+        }
+
+        let class = class_index(bytes, mem::align_of::<T>()).ok_or(DmaError::InvalidConstraints)?;
+        let virt = self.classes.lock()[class].alloc(&self.constraints)?;
+
+        Ok((virt, class))
+    }
+
+    fn free(&self, virt: VirtAddr, class: usize) {
+        self.classes.lock()[class].free(virt);
+    }
+}
+impl fmt::Debug for DmaPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DmaPool")
+            .field("constraints", &self.constraints)
+            .field("stats", &self.stats())
+            .finish()
+    }
+}
+
+/// A box placed on a chunk of a [`DmaPool`].
+pub struct PoolBox<'a, T: ?Sized> {
+    pool: &'a DmaPool,
+    virt: VirtAddr,
+    bytes: Bytes,
+    class: usize,
+    /// Drops the value placed on the chunk.
+    drop_value: unsafe fn(VirtAddr, Bytes),
+    _marker: PhantomData<T>,
+}
+impl<T> Deref for PoolBox<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: This operation is safe because the chunk `virt` points is allocated and is not
+        // used by the others.
+        unsafe { &*self.virt.as_ptr() }
+    }
+}
+impl<T> DerefMut for PoolBox<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // SAFETY: This operation is safe because the chunk `virt` points is allocated and is not
+        // used by the others.
+        unsafe { &mut *self.virt.as_mut_ptr() }
+    }
+}
+impl<T> fmt::Debug for PoolBox<'_, T>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T> PoolBox<'_, [T]>
+where
+    T: Clone,
+{
+    fn num_of_elements(&self) -> usize {
+        self.bytes.as_usize() / mem::size_of::<T>()
+    }
+}
+impl<T> Deref for PoolBox<'_, [T]>
+where
+    T: Clone,
+{
+    type Target = [T];
+    fn deref(&self) -> &Self::Target {
+        unsafe { slice::from_raw_parts(self.virt.as_ptr(), self.num_of_elements()) }
+    }
+}
+impl<T> DerefMut for PoolBox<'_, [T]>
+where
+    T: Clone,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { slice::from_raw_parts_mut(self.virt.as_mut_ptr(), self.num_of_elements()) }
+    }
+}
+impl<T> fmt::Debug for PoolBox<'_, [T]>
+where
+    T: Clone,
+    [T]: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: ?Sized> PoolBox<'_, T> {
+    #[must_use]
+    pub fn virt_addr(&self) -> VirtAddr {
+        self.virt
+    }
+
+    /// # Panics
+    ///
+    /// This method panics if the `PoolBox` is not mapped.
+    #[must_use]
+    pub fn phys_addr(&self) -> PhysAddr {
+        let a = syscalls::translate_address(self.virt);
+
+        if a.is_null() {
+            unreachable!("Address: {:?} is not mapped.", self.virt);
+        }
+
+        a
+    }
+
+    #[must_use]
+    pub fn bytes(&self) -> Bytes {
+        self.bytes
+    }
+}
+impl<T: ?Sized> Drop for PoolBox<'_, T> {
+    fn drop(&mut self) {
+        // SAFETY: This operation is safe because the chunk holds the initialized value which
+        // `drop_value` expects, and the value is not used after this.
+        unsafe {
+            (self.drop_value)(self.virt, self.bytes);
+        }
+
+        self.pool.free(self.virt, self.class);
+    }
+}
+
+/// # Safety
+///
+/// `virt` must point to an initialized `T`.
+unsafe fn drop_value<T>(virt: VirtAddr, _: Bytes) {
+    // SAFETY: The caller must ensure the safety requirements.
+    unsafe { ptr::drop_in_place(virt.as_mut_ptr::<T>()) }
+}
+
+/// # Safety
+///
+/// `virt` must point to `bytes` bytes of initialized `T`s, and `T` must not be a zero-sized type.
+unsafe fn drop_slice<T>(virt: VirtAddr, bytes: Bytes) {
+    let p = ptr::slice_from_raw_parts_mut(
+        virt.as_mut_ptr::<T>(),
+        bytes.as_usize() / mem::size_of::<T>(),
+    );
+
+    // SAFETY: The caller must ensure the safety requirements.
+    unsafe { ptr::drop_in_place(p) }
+}
+
+/// Usage of a [`DmaPool`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PoolStats {
+    pub classes: [PoolClassStats; NUM_OF_CLASSES],
+}
+impl PoolStats {
+    #[must_use]
+    pub fn num_of_pages(&self) -> usize {
+        self.classes.iter().map(|c| c.num_of_pages).sum()
+    }
+
+    #[must_use]
+    pub fn bytes_in_use(&self) -> Bytes {
+        Bytes::new(
+            self.classes
+                .iter()
+                .map(|c| c.bytes_in_use().as_usize())
+                .sum(),
+        )
+    }
+}
+impl fmt::Display for PoolStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} pages, {} bytes in use",
+            self.num_of_pages(),
+            self.bytes_in_use().as_usize()
+        )?;
+
+        for c in self.classes.iter().filter(|c| c.num_of_pages > 0) {
+            write!(
+                f,
+                ", {} B: {}/{}",
+                c.chunk_size,
+                c.num_of_chunks_in_use,
+                c.num_of_chunks()
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Usage of a size class of a [`DmaPool`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PoolClassStats {
+    pub chunk_size: usize,
+    pub num_of_pages: usize,
+    pub num_of_chunks_in_use: usize,
+}
+impl PoolClassStats {
+    #[must_use]
+    pub fn num_of_chunks(&self) -> usize {
+        self.num_of_pages * (PAGE_SIZE / self.chunk_size)
+    }
+
+    #[must_use]
+    pub fn num_of_free_chunks(&self) -> usize {
+        self.num_of_chunks() - self.num_of_chunks_in_use
+    }
+
+    #[must_use]
+    pub fn bytes_in_use(&self) -> Bytes {
+        Bytes::new(self.chunk_size * self.num_of_chunks_in_use)
+    }
+}
+
+struct Class {
+    chunk_size: usize,
+    free: Option<NonNull<FreeChunk>>,
+    num_of_pages: usize,
+    num_of_chunks_in_use: usize,
+}
+// SAFETY: The free chunks are owned by the pool, and they are accessed only while the pool is
+// locked.
+unsafe impl Send for Class {}
+impl Class {
+    fn new(index: usize) -> Self {
+        Self {
+            chunk_size: MIN_CHUNK_SIZE << index,
+            free: None,
+            num_of_pages: 0,
+            num_of_chunks_in_use: 0,
+        }
+    }
+
+    fn alloc(&mut self, constraints: &DmaConstraints) -> Result<VirtAddr, DmaError> {
+        if self.free.is_none() {
+            self.add_page(constraints)?;
+        }
+
+        let chunk = self.free.expect("No free chunk.");
+
+        // SAFETY: This operation is safe because `chunk` is a free chunk which `push` wrote.
+        self.free = unsafe { chunk.as_ref().next };
+        self.num_of_chunks_in_use += 1;
+
+        Ok(VirtAddr::from_ptr(chunk.as_ptr()))
+    }
+
+    fn free(&mut self, virt: VirtAddr) {
+        self.push(virt);
+        self.num_of_chunks_in_use -= 1;
+    }
+
+    fn add_page(&mut self, constraints: &DmaConstraints) -> Result<(), DmaError> {
+        let bytes = Bytes::new(PAGE_SIZE);
+
+        if !constraints.is_satisfiable_for(bytes) {
+            return Err(DmaError::InvalidConstraints);
+        }
+
+        let page = alloc::allocate_dma_pages(bytes, constraints)?;
+
+        for i in (0..PAGE_SIZE / self.chunk_size).rev() {
+            self.push(page + i * self.chunk_size);
+        }
+
+        self.num_of_pages += 1;
+
+        Ok(())
+    }
+
+    fn push(&mut self, virt: VirtAddr) {
+        let chunk: *mut FreeChunk = virt.as_mut_ptr();
+
+        // SAFETY: This operation is safe because the chunk is owned by the pool, is not used by
+        // the others, and is large enough to hold `FreeChunk`.
+        unsafe {
+            ptr::write(chunk, FreeChunk { next: self.free });
+        }
+
+        self.free = NonNull::new(chunk);
+    }
+
+    fn stats(&self) -> PoolClassStats {
+        PoolClassStats {
+            chunk_size: self.chunk_size,
+            num_of_pages: self.num_of_pages,
+            num_of_chunks_in_use: self.num_of_chunks_in_use,
+        }
+    }
+}
+
+struct FreeChunk {
+    next: Option<NonNull<FreeChunk>>,
+}
+
+fn class_index(bytes: Bytes, align: usize) -> Option<usize> {
+    let size = bytes
+        .as_usize()
+        .max(align)
+        .max(MIN_CHUNK_SIZE)
+        .next_power_of_two();
+
+    (size <= PAGE_SIZE).then(|| {
+        (size.trailing_zeros() - MIN_CHUNK_SIZE.trailing_zeros())
+            .try_into()
+            .unwrap()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::DmaPool,
+        core::cell::Cell,
+        os_units::Bytes,
+        syscalls::{DmaConstraints, DmaError},
+    };
+
+    struct CountDrop<'a>(&'a Cell<usize>);
+    impl Clone for CountDrop<'_> {
+        fn clone(&self) -> Self {
+            Self(self.0)
+        }
+    }
+    impl Drop for CountDrop<'_> {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    #[test]
+    fn chunks_are_aligned_to_their_size() {
+        let p = DmaPool::new(DmaConstraints::new());
+
+        let a = p.alloc([0_u8; 8]).unwrap();
+        let b = p.alloc_slice(0_u32, 100).unwrap();
+
+        assert_eq!(a.virt_addr().as_u64() % 64, 0);
+        assert_eq!(b.virt_addr().as_u64() % 512, 0);
+        assert_eq!(*b, [0; 100]);
+    }
+
+    #[test]
+    fn reuse_freed_chunk() {
+        let p = DmaPool::new(DmaConstraints::new());
+
+        let a = p.alloc(3_u64).unwrap();
+        let addr = a.virt_addr();
+        drop(a);
+
+        let b = p.alloc(4_u64).unwrap();
+
+        assert_eq!(b.virt_addr(), addr);
+        assert_eq!(*b, 4);
+    }
+
+    #[test]
+    fn stats() {
+        let p = DmaPool::new(DmaConstraints::new());
+
+        let _a = p.alloc([0_u8; 8]).unwrap();
+        let _b = p.alloc([0_u8; 8]).unwrap();
+        let _c = p.alloc([0_u8; 2048]).unwrap();
+
+        let s = p.stats();
+
+        assert_eq!(s.num_of_pages(), 2);
+        assert_eq!(s.bytes_in_use(), Bytes::new(64 * 2 + 2048));
+        assert_eq!(s.classes[0].num_of_free_chunks(), 62);
+    }
+
+    #[test]
+    fn drop_the_values() {
+        let p = DmaPool::new(DmaConstraints::new());
+        let dropped = Cell::new(0);
+
+        drop(p.alloc(CountDrop(&dropped)).unwrap());
+        assert_eq!(dropped.get(), 1);
+
+        drop(p.alloc_slice(CountDrop(&dropped), 3).unwrap());
+        assert_eq!(dropped.get(), 1 + 1 + 3);
+    }
+
+    #[test]
+    fn fail_to_allocate_zero_sized_type() {
+        let p = DmaPool::new(DmaConstraints::new());
+
+        assert_eq!(p.alloc(()).err(), Some(DmaError::InvalidConstraints));
+        assert_eq!(
+            p.alloc_slice((), 3).err(),
+            Some(DmaError::InvalidConstraints)
+        );
+    }
+
+    #[test]
+    fn fail_to_allocate_larger_than_page() {
+        let p = DmaPool::new(DmaConstraints::new());
+
+        assert_eq!(
+            p.alloc_slice(0_u8, 4097).err(),
+            Some(DmaError::InvalidConstraints)
+        );
+    }
+}
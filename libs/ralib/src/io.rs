@@ -2,7 +2,10 @@
 
 use {
     alloc::string::ToString,
-    core::{convert::TryInto, fmt},
+    core::{
+        convert::{TryFrom, TryInto},
+        fmt,
+    },
 };
 
 #[macro_export]
@@ -23,26 +26,45 @@ macro_rules! println{
 }
 
 pub(crate) fn init() {
-    let r = log::set_logger(&LOGGER).map(|()| log::set_max_level(log::LevelFilter::Info));
+    // The kernel filters the records.
+    let r = log::set_logger(&LOGGER).map(|()| log::set_max_level(log::LevelFilter::Trace));
     r.expect("Failed to initialize logger.");
 }
 
 #[doc(hidden)]
 pub fn _print(args: fmt::Arguments<'_>) {
     let s = args.to_string();
+    let mut bytes = s.as_bytes();
 
-    let _ = unsafe { syscalls::write(1, s.as_ptr().cast(), s.len().try_into().unwrap()) };
+    // The kernel may write fewer bytes than requested.
+    while !bytes.is_empty() {
+        // SAFETY: `bytes` is valid for its length.
+        let n =
+            unsafe { syscalls::write(1, bytes.as_ptr().cast(), bytes.len().try_into().unwrap()) };
+
+        let Ok(n @ 1..) = usize::try_from(n) else {
+            break;
+        };
+
+        bytes = &bytes[n..];
+    }
 }
 
 static LOGGER: Logger = Logger;
 struct Logger;
 impl log::Log for Logger {
-    fn enabled(&self, metadata: &log::Metadata<'_>) -> bool {
-        metadata.level() <= log::Level::Info
+    /// Always returns `true` because the kernel filters the records. The levels cannot be cached
+    /// here since another process may change them with [`syscalls::set_log_level`] at any time.
+    fn enabled(&self, _: &log::Metadata<'_>) -> bool {
+        true
     }
 
     fn log(&self, record: &log::Record<'_>) {
-        println!("{} - {}", record.level(), record.args());
+        syscalls::log(
+            record.level(),
+            record.module_path().unwrap_or_else(|| record.target()),
+            &record.args().to_string(),
+        );
     }
 
     fn flush(&self) {}
@@ -9,11 +9,51 @@ pub mod mem;
 
 extern crate alloc;
 
+use core::fmt::{self, Write};
+
 pub fn init() {
     io::init();
 }
 
 #[panic_handler]
 fn panic(i: &core::panic::PanicInfo<'_>) -> ! {
-    syscalls::panic(i);
+    // The message is formatted here because the kernel must not follow the pointers in the panic
+    // information. The heap may not be initialized yet, so the buffer is on the stack.
+    let mut message = PanicMessage::default();
+    let _ = write!(message, "{}", i);
+
+    syscalls::panic(message.as_str());
+}
+
+/// A buffer which truncates the message exceeding its capacity.
+struct PanicMessage {
+    buf: [u8; 256],
+    len: usize,
+}
+impl PanicMessage {
+    fn as_str(&self) -> &str {
+        core::str::from_utf8(&self.buf[..self.len]).unwrap_or("")
+    }
+}
+impl Default for PanicMessage {
+    fn default() -> Self {
+        Self {
+            buf: [0; 256],
+            len: 0,
+        }
+    }
+}
+impl fmt::Write for PanicMessage {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut n = s.len().min(self.buf.len() - self.len);
+
+        while !s.is_char_boundary(n) {
+            n -= 1;
+        }
+
+        self.buf[self.len..self.len + n].copy_from_slice(&s.as_bytes()[..n]);
+        self.len += n;
+
+        Ok(())
+    }
 }
@@ -6,13 +6,21 @@
 #![feature(naked_functions)]
 
 use {
-    core::{arch::asm, convert::TryInto, ffi::c_void, panic::PanicInfo},
+    core::{arch::asm, convert::TryInto, ffi::c_void, fmt, ops::RangeInclusive, time::Duration},
+    log::{Level, LevelFilter},
     message::Message,
     num_derive::FromPrimitive,
     os_units::{Bytes, NumOfPages},
-    x86_64::{structures::paging::Size4KiB, PhysAddr, VirtAddr},
+    x86_64::{
+        structures::paging::{PageSize, Size4KiB},
+        PhysAddr, VirtAddr,
+    },
 };
 
+/// The PID of the system process, which handles the system calls implemented with messages, such
+/// as the port I/O.
+pub const SYSTEM_PROCESS_PID: i32 = 1;
+
 /// # Safety
 ///
 /// This function is unsafe because reading a value from I/O port may have side effects which
@@ -23,9 +31,9 @@ pub unsafe fn inb(port: u16) -> u8 {
     let header = message::Header::new(0);
     let m = Message::new(header, body);
 
-    send(m, 2);
+    send_to_system_process(m);
 
-    let reply = receive_from(2);
+    let reply = receive_from_system_process();
 
     reply.body.0.try_into().unwrap()
 }
@@ -39,9 +47,9 @@ pub unsafe fn inl(port: u16) -> u32 {
     let header = message::Header::new(0);
     let m = Message::new(header, body);
 
-    send(m, 2);
+    send_to_system_process(m);
 
-    let reply = receive_from(2);
+    let reply = receive_from_system_process();
 
     reply.body.0.try_into().unwrap()
 }
@@ -55,9 +63,9 @@ pub unsafe fn outb(port: u16, value: u8) {
     let header = message::Header::new(0);
     let m = Message::new(header, body);
 
-    send(m, 2);
+    send_to_system_process(m);
 
-    receive_ack(2);
+    receive_from_system_process();
 }
 
 /// # Safety
@@ -69,9 +77,9 @@ pub unsafe fn outl(port: u16, value: u32) {
     let header = message::Header::new(0);
     let m = Message::new(header, body);
 
-    send(m, 2);
+    send_to_system_process(m);
 
-    receive_ack(2);
+    receive_from_system_process();
 }
 
 #[must_use]
@@ -88,7 +96,43 @@ pub fn allocate_pages(pages: NumOfPages<Size4KiB>) -> VirtAddr {
     ))
 }
 
-pub fn deallocate_pages(virt: VirtAddr, pages: NumOfPages<Size4KiB>) {
+/// Allocates physically contiguous pages which satisfy `constraints` for DMA.
+///
+/// # Errors
+///
+/// This function returns an error if `constraints` cannot be satisfied for `bytes` bytes, or the
+/// system has no physical memory which satisfies them.
+pub fn allocate_dma_pages(
+    bytes: Bytes,
+    constraints: &DmaConstraints,
+) -> Result<VirtAddr, DmaError> {
+    if !constraints.is_satisfiable_for(bytes) {
+        return Err(DmaError::InvalidConstraints);
+    }
+
+    let constraints: *const DmaConstraints = constraints;
+
+    let v = general_syscall(
+        Ty::AllocateDmaPages,
+        bytes
+            .as_usize()
+            .try_into()
+            .unwrap_or_else(|_| unreachable!("On x86_64 architecture, `u64` == `usize`.")),
+        constraints as u64,
+        0,
+    );
+
+    if v == 0 {
+        Err(DmaError::OutOfMemory)
+    } else {
+        Ok(VirtAddr::new(v))
+    }
+}
+
+/// Frees the pages allocated by [`allocate_pages`] or [`allocate_dma_pages`].
+///
+/// Returns `false` if `virt..virt+pages` is not exactly a range allocated by them.
+pub fn deallocate_pages(virt: VirtAddr, pages: NumOfPages<Size4KiB>) -> bool {
     // SAFETY: This operation is safe as the all arguments are propertly passed.
     general_syscall(
         Ty::DeallocatePages,
@@ -98,7 +142,7 @@ pub fn deallocate_pages(virt: VirtAddr, pages: NumOfPages<Size4KiB>) {
             .try_into()
             .unwrap_or_else(|_| unreachable!("On x86_64 architecture, `u64` == `usize`.")),
         0,
-    );
+    ) != 0
 }
 
 #[must_use]
@@ -115,7 +159,10 @@ pub fn map_pages(start: PhysAddr, bytes: Bytes) -> VirtAddr {
     ))
 }
 
-pub fn unmap_pages(start: VirtAddr, bytes: Bytes) {
+/// Unmaps the pages mapped by [`map_pages`].
+///
+/// Returns `false` if `start..start+bytes` is not exactly a range mapped by [`map_pages`].
+pub fn unmap_pages(start: VirtAddr, bytes: Bytes) -> bool {
     general_syscall(
         Ty::UnmapPages,
         start.as_u64(),
@@ -124,7 +171,7 @@ pub fn unmap_pages(start: VirtAddr, bytes: Bytes) {
             .try_into()
             .unwrap_or_else(|_| unreachable!("On x86_64 architecture, `usize` == `u64`.")),
         0,
-    );
+    ) != 0
 }
 
 #[must_use]
@@ -133,9 +180,9 @@ pub fn getpid() -> i32 {
     let header = message::Header::default();
     let m = Message::new(header, body);
 
-    send(m, 1);
+    send_to_system_process(m);
 
-    let reply = receive_from(1);
+    let reply = receive_from_system_process();
 
     reply.body.0.try_into().unwrap()
 }
@@ -147,7 +194,90 @@ pub fn translate_address(a: VirtAddr) -> PhysAddr {
     PhysAddr::new(general_syscall(Ty::TranslateAddress, a.as_u64(), 0, 0))
 }
 
-pub fn send(m: Message, to: i32) {
+/// Creates a shared memory object of `bytes` bytes.
+///
+/// Only the calling process can map the object until it calls [`grant_shared_memory`]. The returned
+/// handle can be passed to the granted processes via IPC. The object is freed when the last mapping
+/// to it is unmapped, or when the calling process exits if the object is not mapped.
+#[must_use]
+pub fn create_shared_memory(bytes: Bytes) -> Option<SharedMemoryHandle> {
+    let h = general_syscall(
+        Ty::CreateSharedMemory,
+        bytes
+            .as_usize()
+            .try_into()
+            .unwrap_or_else(|_| unreachable!("On x86_64 architecture, `u64` == `usize`.")),
+        0,
+        0,
+    );
+
+    (h != 0).then_some(h)
+}
+
+/// This method will return a null address if `handle` is invalid, the calling process is not
+/// allowed to map it, or the system is out of memory.
+#[must_use]
+pub fn map_shared_memory(handle: SharedMemoryHandle, permission: Permission) -> VirtAddr {
+    VirtAddr::new(general_syscall(
+        Ty::MapSharedMemory,
+        handle,
+        permission as u64,
+        0,
+    ))
+}
+
+/// Allows the process `pid` to map the shared memory object `handle`.
+///
+/// Returns `false` if `handle` is invalid or the calling process did not create the object.
+#[must_use]
+pub fn grant_shared_memory(handle: SharedMemoryHandle, pid: i32) -> bool {
+    general_syscall(Ty::GrantSharedMemory, handle, pid.try_into().unwrap(), 0) != 0
+}
+
+/// Unmaps the shared memory mapped at `start`.
+///
+/// Returns `false` if no shared memory is mapped at `start`.
+pub fn unmap_shared_memory(start: VirtAddr) -> bool {
+    general_syscall(Ty::UnmapSharedMemory, start.as_u64(), 0, 0) != 0
+}
+
+/// Returns the statistics of the physical memory, and fills `processes` with the memory usage of
+/// each process.
+///
+/// The second value is the number of the processes, which may exceed the length of `processes`.
+/// This function returns [`None`] if the kernel fails to collect the statistics.
+#[must_use]
+pub fn memory_stats(processes: &mut [ProcessMemoryStats]) -> Option<(MemoryStats, usize)> {
+    let mut stats = MemoryStats::default();
+
+    let stats_ptr: *mut MemoryStats = &mut stats;
+    let processes_ptr: *mut ProcessMemoryStats = processes.as_mut_ptr();
+
+    let n = general_syscall(
+        Ty::GetMemoryStats,
+        stats_ptr as u64,
+        processes_ptr as u64,
+        processes
+            .len()
+            .try_into()
+            .unwrap_or_else(|_| unreachable!("On x86_64 architecture, `u64` == `usize`.")),
+    );
+
+    (n != 0).then(|| {
+        (
+            stats,
+            n.try_into()
+                .unwrap_or_else(|_| unreachable!("On x86_64 architecture, `u64` == `usize`.")),
+        )
+    })
+}
+
+/// Sends `m` to `to`, blocking until `to` receives it.
+///
+/// Returns `false` if `to` is the calling process, does not exist, or exits before receiving the
+/// message.
+#[must_use]
+pub fn send(m: Message, to: i32) -> bool {
     let ty = Ty::Send;
     let a1 = &m;
     let a1: *const Message = a1;
@@ -155,7 +285,7 @@ pub fn send(m: Message, to: i32) {
     let a2: u64 = to.try_into().unwrap();
     let a3 = 0;
 
-    message_syscall(ty, a1, a2, a3);
+    general_syscall(ty, a1, a2, a3) != 0
 }
 
 #[must_use]
@@ -174,18 +304,425 @@ pub fn receive_from_any() -> Message {
     m
 }
 
+/// Receives a message if a process is waiting to send one to this process.
+///
+/// Unlike [`receive_from_any`], this function does not block.
+#[must_use]
+pub fn try_receive_from_any() -> Option<Message> {
+    let mut m = Message::default();
+
+    let m_ptr: *mut Message = &mut m;
+
+    let received = general_syscall(Ty::TryReceiveFromAny, m_ptr as u64, 0, 0);
+
+    (received != 0).then_some(m)
+}
+
+/// Registers the calling process as the device where the kernel swaps out the user pages.
+///
+/// The kernel sends messages whose first field is a [`SwapOperation`] to the process. The second,
+/// third and fourth fields are the first block, the number of blocks and the physical address of
+/// the page to transfer. The process must reply with a message whose first field is 0 on success.
+///
+/// The page is always placed below `address_limit`.
+///
+/// This function returns `false` if swapping is disabled, another process is already registered,
+/// or the calling process is not privileged.
+#[must_use]
+pub fn register_swap_device(block_size: u32, address_limit: u64) -> bool {
+    general_syscall(Ty::RegisterSwapDevice, block_size.into(), address_limit, 0) != 0
+}
+
+/// Routes `interrupt` to the calling process.
+///
+/// When the interrupt happens, the kernel masks it and sends a message to the process. The sender
+/// of the message is [`INTERRUPT_SENDER`], and [`Interrupt::from_message`] converts it back. The
+/// process must call [`irq_ack`] after handling the interrupt to receive the next one. The
+/// interrupt is unregistered when the process exits.
+///
+/// This function returns `false` if the interrupt is invalid or already registered, or the calling
+/// process is not privileged.
+#[must_use]
+pub fn irq_register(interrupt: Interrupt) -> bool {
+    let (kind, number) = interrupt.into_raw();
+
+    general_syscall(Ty::IrqRegister, kind, number, 0) != 0
+}
+
+/// Unmasks `interrupt` which the calling process handled.
+///
+/// This function returns `false` if the interrupt is not registered by the calling process.
+#[must_use]
+pub fn irq_ack(interrupt: Interrupt) -> bool {
+    let (kind, number) = interrupt.into_raw();
+
+    general_syscall(Ty::IrqAck, kind, number, 0) != 0
+}
+
+/// Stops routing `interrupt` to the calling process.
+///
+/// This function returns `false` if the interrupt is not registered by the calling process.
+#[must_use]
+pub fn irq_unregister(interrupt: Interrupt) -> bool {
+    let (kind, number) = interrupt.into_raw();
+
+    general_syscall(Ty::IrqUnregister, kind, number, 0) != 0
+}
+
+/// Returns the address and the data which a device writes to signal the MSI `vector`.
+///
+/// This function returns [`None`] if the calling process does not own [`Interrupt::Msi`] of
+/// `vector`.
+#[must_use]
+pub fn msi_message(vector: u8) -> Option<MsiMessage> {
+    let mut m = MsiMessage::default();
+
+    let m_ptr: *mut MsiMessage = &mut m;
+
+    let owned = general_syscall(Ty::GetMsiMessage, vector.into(), m_ptr as u64, 0);
+
+    (owned != 0).then_some(m)
+}
+
+/// Returns the `_HID` of the `index`-th device in the ACPI namespace.
+///
+/// The devices having `_HID` are numbered from 0 without gaps, so iterating `index` from 0 until
+/// this function returns [`None`] enumerates all of them.
+#[must_use]
+pub fn acpi_device(index: usize) -> Option<AcpiHid> {
+    let reply = query_system_process(Ty::AcpiDevice, index.try_into().unwrap(), 0);
+
+    (reply.0 != 0).then_some(AcpiHid::from_raw(reply.1))
+}
+
+/// Returns the `index`-th resource of the `device`-th device in the ACPI namespace.
+///
+/// The numbering of the devices is the same as [`acpi_device`].
+#[must_use]
+pub fn acpi_resource(device: usize, index: usize) -> Option<AcpiResource> {
+    let reply = query_system_process(
+        Ty::AcpiResource,
+        device.try_into().unwrap(),
+        index.try_into().unwrap(),
+    );
+
+    AcpiResource::from_raw(reply.0, reply.1, reply.2)
+}
+
+/// Returns the interrupt which the interrupt `pin` of the PCI `device` on the bus 0 is connected
+/// to, according to the `_PRT` object of the PCI root bridge.
+///
+/// `pin` is the value of the Interrupt Pin register, so 1 means INTA#.
+#[must_use]
+pub fn acpi_pci_route(device: u8, pin: u8) -> Option<AcpiIrq> {
+    let reply = query_system_process(Ty::AcpiPciRoute, device.into(), pin.into());
+
+    if reply.0 == 0 {
+        None
+    } else {
+        AcpiIrq::from_raw(reply.1, reply.2)
+    }
+}
+
+/// Copies the ACPI table `signature`, such as `*b"APIC"` for the MADT, to `buf`, including its
+/// header.
+///
+/// Returns the length of the table, which may be larger than `buf`. In that case only the first
+/// `buf.len()` bytes are copied. This function returns [`None`] if there is no such table or the
+/// calling process is not privileged.
+#[must_use]
+pub fn acpi_table(signature: [u8; 4], buf: &mut [u8]) -> Option<usize> {
+    let len = general_syscall(
+        Ty::GetAcpiTable,
+        u32::from_le_bytes(signature).into(),
+        buf.as_mut_ptr() as u64,
+        buf.len().try_into().unwrap(),
+    );
+
+    (len != 0).then(|| len.try_into().unwrap())
+}
+
+/// Returns the time of `clock`, or [`None`] if the clock is not available.
+#[must_use]
+pub fn clock_gettime(clock: Clock) -> Option<Duration> {
+    let nanos = general_syscall(Ty::ClockGetTime, clock as u64, 0, 0);
+
+    (nanos != 0).then(|| Duration::from_nanos(nanos))
+}
+
+/// Passes a character typed on a keyboard to the console.
+///
+/// The character is ignored if the calling process is not privileged.
+pub fn console_input(c: char) {
+    general_syscall(Ty::ConsoleInput, c.into(), 0, 0);
+}
+
+/// Logs `message` of `module` with the PID and the name of the calling process.
+///
+/// The kernel drops the record if `level` is filtered out for the process or the module. See
+/// [`set_log_level`].
+pub fn log(level: Level, module: &str, message: &str) {
+    let message = truncate(message, MAX_STR_LEN);
+
+    let strs: [u64; 4] = [
+        module.as_ptr() as u64,
+        module.len().try_into().unwrap(),
+        message.as_ptr() as u64,
+        message.len().try_into().unwrap(),
+    ];
+    let strs_ptr: *const [u64; 4] = &strs;
+
+    general_syscall(Ty::Log, level as u64, strs_ptr as u64, 0);
+}
+
+/// Sets the maximum level of the records logged by the processes named `target` or by the modules
+/// under `target`, such as `xhci.bin` and `kernel::acpi`.
+///
+/// If `target` is empty, the level applies to the records which match no target. The level of the
+/// longest matching module wins over the level of the process.
+///
+/// Returns `false` if the arguments are invalid or the calling process is not privileged.
+#[must_use]
+pub fn set_log_level(target: &str, level: LevelFilter) -> bool {
+    general_syscall(
+        Ty::SetLogLevel,
+        level as u64,
+        target.as_ptr() as u64,
+        target.len().try_into().unwrap(),
+    ) != 0
+}
+
+/// Copies the records of the kernel log whose sequence numbers are `from` or larger to `buf`,
+/// oldest first.
+///
+/// Returns the number of the copied records. The kernel keeps only the latest records, so the
+/// first copied one may be newer than `from`.
+#[must_use]
+pub fn read_kernel_log(from: u64, buf: &mut [LogRecord]) -> usize {
+    general_syscall(
+        Ty::ReadKernelLog,
+        from,
+        buf.as_mut_ptr() as u64,
+        buf.len().try_into().unwrap(),
+    )
+    .try_into()
+    .unwrap()
+}
+
+/// Opens the file or the directory at `path` in the initrd, such as `fonts/ascii.psf`.
+///
+/// Returns the file descriptor, or [`None`] if there is no such file.
+#[must_use]
+pub fn open(path: &str) -> Option<i32> {
+    let fildes = general_syscall(
+        Ty::Open,
+        path.as_ptr() as u64,
+        path.len().try_into().unwrap(),
+        0,
+    );
+
+    fs_result(fildes).map(|fildes| fildes.try_into().unwrap())
+}
+
+/// Reads `fildes` to `buf`.
+///
+/// Reading a file starts from the current offset and advances it. Reading the standard input or a
+/// pipe waits until some bytes are available.
+///
+/// Returns the number of the read bytes, which is 0 at the end of a file or a pipe whose write end
+/// is closed. This function returns [`None`] if `fildes` is not opened or not readable.
+#[must_use]
+pub fn read(fildes: i32, buf: &mut [u8]) -> Option<usize> {
+    let len = general_syscall(
+        Ty::Read,
+        fildes.try_into().ok()?,
+        buf.as_mut_ptr() as u64,
+        buf.len().try_into().unwrap(),
+    );
+
+    fs_result(len).map(|len| len.try_into().unwrap())
+}
+
+/// Moves the offset of the opened file to `offset` bytes from `whence`.
+///
+/// The offset of a directory is the index of the entry returned by the next [`read_dir`].
+///
+/// Returns the new offset, or [`None`] if `fildes` is not an opened file or if the new offset is
+/// negative.
+#[must_use]
+// The kernel converts `offset` back to `i64`.
+#[allow(clippy::cast_sign_loss)]
+pub fn seek(fildes: i32, offset: i64, whence: Whence) -> Option<u64> {
+    fs_result(general_syscall(
+        Ty::Seek,
+        fildes.try_into().ok()?,
+        offset as u64,
+        whence as u64,
+    ))
+}
+
+/// Returns the status of the file or the directory at `path` in the initrd.
+#[must_use]
+pub fn stat(path: &str) -> Option<Stat> {
+    let mut stat = Stat::default();
+    let stat_ptr: *mut Stat = &mut stat;
+
+    fs_result(general_syscall(
+        Ty::Stat,
+        path.as_ptr() as u64,
+        path.len().try_into().unwrap(),
+        stat_ptr as u64,
+    ))
+    .map(|_| stat)
+}
+
+/// Returns the next entry of the opened directory, or [`None`] at the end of the directory.
+///
+/// The entries are sorted by their names.
+#[must_use]
+pub fn read_dir(fildes: i32) -> Option<DirEntry> {
+    let mut entry = DirEntry::default();
+    let entry_ptr: *mut DirEntry = &mut entry;
+
+    let read = general_syscall(Ty::ReadDir, fildes.try_into().ok()?, entry_ptr as u64, 0);
+
+    (read != 0).then_some(entry)
+}
+
+/// Closes `fildes`.
+///
+/// The object which `fildes` refers to is closed when all descriptors referring to it, including
+/// the duplicated and the inherited ones, are closed.
+///
+/// Returns `false` if `fildes` is not opened.
+pub fn close(fildes: i32) -> bool {
+    fildes.try_into().map_or(false, |fildes| {
+        fs_result(general_syscall(Ty::Close, fildes, 0, 0)).is_some()
+    })
+}
+
+/// Returns a new descriptor which refers to the same object as `fildes`. The new one is the
+/// smallest unused descriptor.
+#[must_use]
+pub fn dup(fildes: i32) -> Option<i32> {
+    fs_result(general_syscall(Ty::Dup, fildes.try_into().ok()?, 0, 0))
+        .map(|fildes| fildes.try_into().unwrap())
+}
+
+/// Makes `new` refer to the same object as `old`, closing `new` first if it is opened.
+///
+/// Returns `false` if `old` is not opened or `new` is negative.
+pub fn dup2(old: i32, new: i32) -> bool {
+    old.try_into()
+        .ok()
+        .zip(new.try_into().ok())
+        .map_or(false, |(old, new)| {
+            fs_result(general_syscall(Ty::Dup2, old, new, 0)).is_some()
+        })
+}
+
+/// Creates a pipe and returns the descriptors of its read end and write end.
+#[must_use]
+pub fn pipe() -> Option<(i32, i32)> {
+    let mut fildes = [0_i32; 2];
+    let fildes_ptr: *mut [i32; 2] = &mut fildes;
+
+    fs_result(general_syscall(Ty::Pipe, fildes_ptr as u64, 0, 0)).map(|_| (fildes[0], fildes[1]))
+}
+
+/// Returns a descriptor which refers to the object `handle` served by the process `pid`.
+///
+/// The descriptor is duplicated and inherited as the others, but it is neither readable nor
+/// writable. Use [`server_of`] and the protocol of the server instead. When the object is
+/// closed, the server receives `Body(Ty::Close as u64, handle, 0, 0, 0)` from the process which
+/// closed it last.
+///
+/// Returns [`None`] if there is no process `pid` or it runs in the kernel, such as the system
+/// process.
+#[must_use]
+pub fn open_server(pid: i32, handle: u64) -> Option<i32> {
+    fs_result(general_syscall(
+        Ty::OpenServer,
+        pid.try_into().ok()?,
+        handle,
+        0,
+    ))
+    .map(|fildes| fildes.try_into().unwrap())
+}
+
+/// Returns the PID of the server and the handle of the object which `fildes` refers to, or
+/// [`None`] if it is not a server-backed object.
+#[must_use]
+pub fn server_of(fildes: i32) -> Option<(i32, u64)> {
+    let mut server = [0_u64; 2];
+    let server_ptr: *mut [u64; 2] = &mut server;
+
+    fs_result(general_syscall(
+        Ty::ServerOf,
+        fildes.try_into().ok()?,
+        server_ptr as u64,
+        0,
+    ))
+    .map(|_| (server[0].try_into().unwrap(), server[1]))
+}
+
+/// Starts the executable at `path` in the initrd, and returns its PID.
+///
+/// The new process inherits the file descriptors of the calling process.
+#[must_use]
+pub fn spawn(path: &str) -> Option<i32> {
+    fs_result(general_syscall(
+        Ty::Spawn,
+        path.as_ptr() as u64,
+        path.len().try_into().unwrap(),
+        0,
+    ))
+    .map(|pid| pid.try_into().unwrap())
+}
+
+/// Terminates the calling process.
+///
+/// The file descriptors are closed, and the memory of the process is freed.
+pub fn exit() -> ! {
+    general_syscall(Ty::Exit, 0, 0, 0);
+    unreachable!("The `exit` system call should not return.");
+}
+
+/// Powers off the machine.
+///
+/// This function returns only if the calling process is not privileged.
+pub fn shutdown() {
+    general_syscall(Ty::Shutdown, 0, 0, 0);
+}
+
+/// Reboots the machine.
+///
+/// This function returns only if the calling process is not privileged.
+pub fn reboot() {
+    general_syscall(Ty::Reboot, 0, 0, 0);
+}
+
+/// Receives a message from `from`, blocking until `from` sends one.
+///
+/// Returns [`None`] if `from` is the calling process, does not exist, or exits before sending a
+/// message.
 #[must_use]
-pub fn receive_from(from: i32) -> Message {
+pub fn receive_from(from: i32) -> Option<Message> {
     let mut m = Message::default();
 
     let m_ptr: *mut Message = &mut m;
     let m_ptr: u64 = m_ptr as _;
 
-    message_syscall(Ty::ReceiveFrom, m_ptr, from.try_into().unwrap(), 0);
+    let received = general_syscall(Ty::ReceiveFrom, m_ptr, from.try_into().unwrap(), 0);
 
-    m
+    (received != 0).then_some(m)
 }
 
+/// Writes `nbyte` bytes from `buf` to `fildes`, such as the standard output or a pipe.
+///
+/// Returns the number of the written bytes, or -1 if `fildes` is not opened or not writable. The
+/// number may be less than `nbyte`, so the caller must write the rest again.
+///
 /// # Safety
 ///
 /// `buf` must be valid.
@@ -199,17 +736,760 @@ pub unsafe fn write(fildes: i32, buf: *const c_void, nbyte: u32) -> i32 {
         nbyte.into(),
     )
     .try_into()
-    .unwrap()
+    .unwrap_or(-1)
 }
 
-pub fn panic(info: &PanicInfo<'_>) -> ! {
-    let info: *const PanicInfo<'_> = info;
-    general_syscall(Ty::Panic, info as _, 0, 0);
+/// Reports the panic message `message` and terminates the calling process.
+pub fn panic(message: &str) -> ! {
+    general_syscall(
+        Ty::Panic,
+        message.as_ptr() as u64,
+        message.len().try_into().unwrap(),
+        0,
+    );
     unreachable!("The `panic` system call should not return.");
 }
 
-fn receive_ack(from: i32) {
-    let _ = receive_from(from);
+fn query_system_process(ty: Ty, a1: u64, a2: u64) -> message::Body {
+    let body = message::Body(ty as u64, a1, a2, 0, 0);
+    let header = message::Header::new(0);
+    let m = Message::new(header, body);
+
+    send_to_system_process(m);
+
+    receive_from_system_process().body
+}
+
+/// Returns the longest prefix of `s` which is at most `len` bytes and ends at a character boundary.
+fn truncate(s: &str, len: usize) -> &str {
+    let mut len = len.min(s.len());
+
+    while !s.is_char_boundary(len) {
+        len -= 1;
+    }
+
+    &s[..len]
+}
+
+fn send_to_system_process(m: Message) {
+    assert!(
+        send(m, SYSTEM_PROCESS_PID),
+        "The system process does not exist."
+    );
+}
+
+fn receive_from_system_process() -> Message {
+    receive_from(SYSTEM_PROCESS_PID).expect("The system process does not exist.")
+}
+
+pub type SharedMemoryHandle = u64;
+
+/// Physical constraints of a DMA buffer.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct DmaConstraints {
+    align: u64,
+    boundary: u64,
+    address_bits: u64,
+}
+impl DmaConstraints {
+    /// Creates constraints which only require the page alignment.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            align: Size4KiB::SIZE,
+            boundary: 0,
+            address_bits: 64,
+        }
+    }
+
+    /// The start address of the buffer will be aligned to `align` bytes.
+    ///
+    /// Alignments smaller than a page are rounded up to a page.
+    #[must_use]
+    pub const fn align(self, align: u64) -> Self {
+        Self {
+            align: if align > Size4KiB::SIZE {
+                align
+            } else {
+                Size4KiB::SIZE
+            },
+            ..self
+        }
+    }
+
+    /// The buffer will not cross any multiples of `boundary` bytes.
+    #[must_use]
+    pub const fn boundary(self, boundary: u64) -> Self {
+        Self { boundary, ..self }
+    }
+
+    /// The whole buffer will be placed below 4 GiB.
+    #[must_use]
+    pub const fn below_4gib(self) -> Self {
+        Self {
+            address_bits: 32,
+            ..self
+        }
+    }
+
+    #[must_use]
+    pub fn alignment(&self) -> u64 {
+        self.align
+    }
+
+    /// Returns the boundary not to cross, or `None` if there is no such boundary.
+    #[must_use]
+    pub fn boundary_to_avoid(&self) -> Option<u64> {
+        (self.boundary != 0).then_some(self.boundary)
+    }
+
+    /// Returns the exclusive upper limit of the physical addresses of the buffer.
+    #[must_use]
+    pub fn address_limit(&self) -> u64 {
+        if self.address_bits >= 64 {
+            u64::MAX
+        } else {
+            1 << self.address_bits
+        }
+    }
+
+    /// Returns `true` if a buffer of `bytes` bytes can satisfy the constraints.
+    #[must_use]
+    pub fn is_satisfiable_for(&self, bytes: Bytes) -> bool {
+        let bytes = bytes.as_usize() as u64;
+
+        let valid_boundary = self.boundary_to_avoid().map_or(true, |b| {
+            b.is_power_of_two() && b >= Size4KiB::SIZE && b >= bytes
+        });
+
+        bytes > 0
+            && self.align.is_power_of_two()
+            && valid_boundary
+            && (32..=64).contains(&self.address_bits)
+    }
+}
+impl Default for DmaConstraints {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum DmaError {
+    InvalidConstraints,
+    OutOfMemory,
+}
+
+/// Statistics of the physical memory.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct MemoryStats {
+    total: u64,
+    free: u64,
+    reserved: u64,
+    largest_free_run: u64,
+}
+impl MemoryStats {
+    #[must_use]
+    pub fn new(
+        total: NumOfPages<Size4KiB>,
+        free: NumOfPages<Size4KiB>,
+        reserved: NumOfPages<Size4KiB>,
+        largest_free_run: NumOfPages<Size4KiB>,
+    ) -> Self {
+        Self {
+            total: pages_to_u64(total),
+            free: pages_to_u64(free),
+            reserved: pages_to_u64(reserved),
+            largest_free_run: pages_to_u64(largest_free_run),
+        }
+    }
+
+    /// Returns the number of the frames which the kernel can allocate.
+    #[must_use]
+    pub fn total(&self) -> NumOfPages<Size4KiB> {
+        u64_to_pages(self.total)
+    }
+
+    #[must_use]
+    pub fn free(&self) -> NumOfPages<Size4KiB> {
+        u64_to_pages(self.free)
+    }
+
+    #[must_use]
+    pub fn used(&self) -> NumOfPages<Size4KiB> {
+        u64_to_pages(self.total.saturating_sub(self.free))
+    }
+
+    /// Returns the number of the frames used by the firmware, the boot loader, the kernel image,
+    /// the initrd or the devices.
+    #[must_use]
+    pub fn reserved(&self) -> NumOfPages<Size4KiB> {
+        u64_to_pages(self.reserved)
+    }
+
+    /// Returns the number of the frames of the largest physically contiguous free run.
+    #[must_use]
+    pub fn largest_free_run(&self) -> NumOfPages<Size4KiB> {
+        u64_to_pages(self.largest_free_run)
+    }
+}
+
+/// The memory usage of a process.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ProcessMemoryStats {
+    pid: i32,
+    name: [u8; 16],
+    resident_pages: u64,
+}
+impl ProcessMemoryStats {
+    /// `name` is truncated to 16 bytes.
+    #[must_use]
+    pub fn new(pid: i32, name: &str, resident_pages: NumOfPages<Size4KiB>) -> Self {
+        let mut buf = [0; 16];
+        let len = name.len().min(buf.len());
+
+        buf[..len].copy_from_slice(&name.as_bytes()[..len]);
+
+        Self {
+            pid,
+            name: buf,
+            resident_pages: pages_to_u64(resident_pages),
+        }
+    }
+
+    #[must_use]
+    pub fn pid(&self) -> i32 {
+        self.pid
+    }
+
+    /// Returns the name of the process, or an empty string if the name is not valid UTF-8.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        let len = self
+            .name
+            .iter()
+            .position(|c| *c == 0)
+            .unwrap_or(self.name.len());
+
+        core::str::from_utf8(&self.name[..len]).unwrap_or("")
+    }
+
+    /// Returns the number of the pages mapped to the physical memory in the user space of the
+    /// process. Memory-mapped I/O is not counted.
+    #[must_use]
+    pub fn resident_pages(&self) -> NumOfPages<Size4KiB> {
+        u64_to_pages(self.resident_pages)
+    }
+}
+
+/// A record of the kernel log.
+///
+/// The record is displayed like `[12:34:56.789] INFO xhci.bin[1] xhci::port: message`, where the
+/// time is the time of day in UTC. The process is omitted for the records of the kernel.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct LogRecord {
+    sequence: u64,
+    /// The time since the Unix epoch in nanoseconds, or 0 if unknown.
+    timestamp: u64,
+    /// The PID of the process which logged the record, or `KERNEL_PID` for the kernel.
+    pid: i32,
+    process: [u8; 16],
+    level: u8,
+    module: [u8; Self::MODULE_LEN],
+    message: [u8; Self::MESSAGE_LEN],
+}
+impl LogRecord {
+    pub const MODULE_LEN: usize = 32;
+    pub const MESSAGE_LEN: usize = 128;
+
+    const KERNEL_PID: i32 = -1;
+
+    /// `process` is the pair of the PID and the name of the process which logged the record, or
+    /// [`None`] for the kernel.
+    ///
+    /// The name of the process, `module` and `message` are truncated to 16,
+    /// [`Self::MODULE_LEN`] and [`Self::MESSAGE_LEN`] bytes.
+    #[must_use]
+    pub fn new(
+        sequence: u64,
+        timestamp: Option<Duration>,
+        process: Option<(i32, &str)>,
+        level: Level,
+        module: &str,
+        message: &str,
+    ) -> Self {
+        let (pid, name) = process.unwrap_or((Self::KERNEL_PID, ""));
+
+        Self {
+            sequence,
+            timestamp: timestamp.map_or(0, |t| t.as_nanos().try_into().unwrap_or(u64::MAX)),
+            pid,
+            process: truncate(name),
+            level: level as u8,
+            module: truncate(module),
+            message: truncate(message),
+        }
+    }
+
+    /// Returns the number of the records logged before this one.
+    #[must_use]
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// Returns the time since the Unix epoch.
+    #[must_use]
+    pub fn timestamp(&self) -> Option<Duration> {
+        (self.timestamp != 0).then(|| Duration::from_nanos(self.timestamp))
+    }
+
+    /// Returns the PID and the name of the process which logged the record, or [`None`] if the
+    /// kernel did.
+    #[must_use]
+    pub fn process(&self) -> Option<(i32, &str)> {
+        (self.pid != Self::KERNEL_PID).then(|| (self.pid, str_from_nul_padded(&self.process)))
+    }
+
+    #[must_use]
+    pub fn level(&self) -> Level {
+        match self.level {
+            1 => Level::Error,
+            2 => Level::Warn,
+            3 => Level::Info,
+            4 => Level::Debug,
+            _ => Level::Trace,
+        }
+    }
+
+    #[must_use]
+    pub fn module(&self) -> &str {
+        str_from_nul_padded(&self.module)
+    }
+
+    #[must_use]
+    pub fn message(&self) -> &str {
+        str_from_nul_padded(&self.message)
+    }
+}
+impl Default for LogRecord {
+    fn default() -> Self {
+        Self::new(0, None, None, Level::Trace, "", "")
+    }
+}
+impl fmt::Display for LogRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(t) = self.timestamp() {
+            let seconds_of_day = t.as_secs() % (24 * 60 * 60);
+
+            write!(
+                f,
+                "[{:02}:{:02}:{:02}.{:03}] ",
+                seconds_of_day / 3600,
+                seconds_of_day / 60 % 60,
+                seconds_of_day % 60,
+                t.subsec_millis()
+            )?;
+        }
+
+        write!(f, "{} ", self.level())?;
+
+        if let Some((pid, name)) = self.process() {
+            write!(f, "{}[{}] ", name, pid)?;
+        }
+
+        write!(f, "{}: {}", self.module(), self.message())
+    }
+}
+
+/// The status of a file or a directory in the initrd.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Stat {
+    size: u64,
+    mode: u32,
+    uid: u32,
+    /// The modification time in seconds since the Unix epoch.
+    mtime: u64,
+}
+impl Stat {
+    /// The mask of the file type in the mode.
+    pub const S_IFMT: u32 = 0o170_000;
+    pub const S_IFDIR: u32 = 0o040_000;
+    pub const S_IFREG: u32 = 0o100_000;
+    /// The set-user-ID bit. A process spawned from an executable with this bit is privileged.
+    pub const S_ISUID: u32 = 0o004_000;
+
+    #[must_use]
+    pub fn new(size: u64, mode: u32, uid: u32, mtime: u64) -> Self {
+        Self {
+            size,
+            mode,
+            uid,
+            mtime,
+        }
+    }
+
+    /// Returns the size of the file in bytes, or 0 for a directory.
+    #[must_use]
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Returns the file type and the permission bits.
+    #[must_use]
+    pub fn mode(&self) -> u32 {
+        self.mode
+    }
+
+    #[must_use]
+    pub fn uid(&self) -> u32 {
+        self.uid
+    }
+
+    #[must_use]
+    pub fn mtime(&self) -> Duration {
+        Duration::from_secs(self.mtime)
+    }
+
+    #[must_use]
+    pub fn is_dir(&self) -> bool {
+        self.mode & Self::S_IFMT == Self::S_IFDIR
+    }
+}
+
+/// An entry of a directory in the initrd.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct DirEntry {
+    name: [u8; Self::NAME_LEN],
+    stat: Stat,
+}
+impl DirEntry {
+    pub const NAME_LEN: usize = 256;
+
+    /// `name` is truncated to [`Self::NAME_LEN`] bytes.
+    #[must_use]
+    pub fn new(name: &str, stat: Stat) -> Self {
+        Self {
+            name: truncate(name),
+            stat,
+        }
+    }
+
+    /// Returns the name of the entry, which does not contain the path of the directory.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        str_from_nul_padded(&self.name)
+    }
+
+    #[must_use]
+    pub fn stat(&self) -> Stat {
+        self.stat
+    }
+}
+impl Default for DirEntry {
+    fn default() -> Self {
+        Self::new("", Stat::default())
+    }
+}
+
+/// Copies `s` to a NUL-padded array, truncating it at a character boundary.
+fn truncate<const N: usize>(s: &str) -> [u8; N] {
+    let mut len = s.len().min(N);
+
+    while !s.is_char_boundary(len) {
+        len -= 1;
+    }
+
+    let mut buf = [0; N];
+    buf[..len].copy_from_slice(&s.as_bytes()[..len]);
+
+    buf
+}
+
+/// Converts the value returned by a file system or a file descriptor call to [`None`] if it is
+/// [`FS_ERROR`].
+fn fs_result(r: u64) -> Option<u64> {
+    (r != FS_ERROR).then_some(r)
+}
+
+fn str_from_nul_padded(bytes: &[u8]) -> &str {
+    let len = bytes.iter().position(|c| *c == 0).unwrap_or(bytes.len());
+
+    core::str::from_utf8(&bytes[..len]).unwrap_or("")
+}
+
+fn pages_to_u64(n: NumOfPages<Size4KiB>) -> u64 {
+    n.as_usize()
+        .try_into()
+        .unwrap_or_else(|_| unreachable!("On x86_64 architecture, `u64` == `usize`."))
+}
+
+fn u64_to_pages(n: u64) -> NumOfPages<Size4KiB> {
+    NumOfPages::new(
+        n.try_into()
+            .unwrap_or_else(|_| unreachable!("On x86_64 architecture, `usize` == `u64`.")),
+    )
+}
+
+/// An operation which the kernel requests to the swap device.
+#[derive(Copy, Clone, FromPrimitive, Debug, PartialEq, Eq)]
+#[repr(u64)]
+pub enum SwapOperation {
+    /// Reads the blocks into the page.
+    Read,
+    /// Writes the page to the blocks.
+    Write,
+}
+
+/// The PID which the kernel uses as the sender of the interrupt notifications.
+pub const INTERRUPT_SENDER: i32 = -1;
+
+/// The value which the file system and the file descriptor calls return on failure.
+pub const FS_ERROR: u64 = u64::MAX;
+
+/// The first field of the reply which the system process sends for a request it does not support.
+pub const UNSUPPORTED_REQUEST: u64 = u64::MAX;
+
+/// The maximum length in bytes of a string passed to the kernel, such as a path or a log message.
+pub const MAX_STR_LEN: usize = 4096;
+
+/// The vectors which the devices can use for Message Signaled Interrupts.
+pub const MSI_VECTORS: RangeInclusive<u8> = 0x50..=0xef;
+
+/// A hardware interrupt which a user-space driver handles.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Interrupt {
+    /// An I/O APIC input. The numbers below 16 are the ISA IRQs, and the others are the global
+    /// system interrupts, which are level-triggered and active low like PCI interrupts.
+    Irq(u8),
+    /// A Message Signaled Interrupt with the vector in [`MSI_VECTORS`].
+    Msi(u8),
+}
+impl Interrupt {
+    const IRQ: u64 = 0;
+    const MSI: u64 = 1;
+
+    #[must_use]
+    pub fn from_raw(kind: u64, number: u64) -> Option<Self> {
+        let number = number.try_into().ok()?;
+
+        match kind {
+            Self::IRQ => Some(Self::Irq(number)),
+            Self::MSI => Some(Self::Msi(number)),
+            _ => None,
+        }
+    }
+
+    /// Returns the interrupt which `m` notifies, or [`None`] if `m` is not an interrupt
+    /// notification.
+    #[must_use]
+    pub fn from_message(m: &Message) -> Option<Self> {
+        if m.header.sender == INTERRUPT_SENDER {
+            Self::from_raw(m.body.0, m.body.1)
+        } else {
+            None
+        }
+    }
+
+    #[must_use]
+    pub fn into_raw(self) -> (u64, u64) {
+        match self {
+            Self::Irq(n) => (Self::IRQ, n.into()),
+            Self::Msi(v) => (Self::MSI, v.into()),
+        }
+    }
+}
+
+/// A pair of the address and the data of a Message Signaled Interrupt.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct MsiMessage {
+    address: u64,
+    data: u32,
+}
+impl MsiMessage {
+    #[must_use]
+    pub fn new(address: u64, data: u32) -> Self {
+        Self { address, data }
+    }
+
+    #[must_use]
+    pub fn address(&self) -> u64 {
+        self.address
+    }
+
+    #[must_use]
+    pub fn data(&self) -> u32 {
+        self.data
+    }
+}
+
+/// The hardware ID (`_HID`) of a device in the ACPI namespace, such as `PNP0501`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct AcpiHid([u8; 8]);
+impl AcpiHid {
+    /// Returns [`None`] if `id` is not ASCII or longer than 8 bytes.
+    #[must_use]
+    pub fn new(id: &str) -> Option<Self> {
+        if !id.is_ascii() || id.len() > 8 {
+            return None;
+        }
+
+        let mut bytes = [0; 8];
+        bytes[..id.len()].copy_from_slice(id.as_bytes());
+
+        Some(Self(bytes))
+    }
+
+    #[must_use]
+    pub fn from_raw(raw: u64) -> Self {
+        Self(raw.to_le_bytes())
+    }
+
+    #[must_use]
+    pub fn into_raw(self) -> u64 {
+        u64::from_le_bytes(self.0)
+    }
+
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        let len = self.0.iter().position(|&b| b == 0).unwrap_or(self.0.len());
+
+        core::str::from_utf8(&self.0[..len]).unwrap_or("")
+    }
+}
+
+/// An interrupt described in the ACPI namespace.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct AcpiIrq {
+    gsi: u32,
+    level_triggered: bool,
+    active_low: bool,
+}
+impl AcpiIrq {
+    const LEVEL_TRIGGERED: u64 = 1;
+    const ACTIVE_LOW: u64 = 2;
+
+    #[must_use]
+    pub fn new(gsi: u32, level_triggered: bool, active_low: bool) -> Self {
+        Self {
+            gsi,
+            level_triggered,
+            active_low,
+        }
+    }
+
+    #[must_use]
+    pub fn from_raw(gsi: u64, flags: u64) -> Option<Self> {
+        Some(Self::new(
+            gsi.try_into().ok()?,
+            flags & Self::LEVEL_TRIGGERED != 0,
+            flags & Self::ACTIVE_LOW != 0,
+        ))
+    }
+
+    #[must_use]
+    pub fn into_raw(self) -> (u64, u64) {
+        let mut flags = 0;
+
+        if self.level_triggered {
+            flags |= Self::LEVEL_TRIGGERED;
+        }
+
+        if self.active_low {
+            flags |= Self::ACTIVE_LOW;
+        }
+
+        (self.gsi.into(), flags)
+    }
+
+    /// Returns the global system interrupt number.
+    #[must_use]
+    pub fn gsi(&self) -> u32 {
+        self.gsi
+    }
+
+    #[must_use]
+    pub fn level_triggered(&self) -> bool {
+        self.level_triggered
+    }
+
+    #[must_use]
+    pub fn active_low(&self) -> bool {
+        self.active_low
+    }
+}
+
+/// A resource which a device in the ACPI namespace uses, read from its `_CRS` object.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum AcpiResource {
+    Irq(AcpiIrq),
+    Io { base: u16, len: u16 },
+    Memory { base: u64, len: u64 },
+}
+impl AcpiResource {
+    const IRQ: u64 = 1;
+    const IO: u64 = 2;
+    const MEMORY: u64 = 3;
+
+    #[must_use]
+    pub fn from_raw(kind: u64, a: u64, b: u64) -> Option<Self> {
+        match kind {
+            Self::IRQ => AcpiIrq::from_raw(a, b).map(Self::Irq),
+            Self::IO => Some(Self::Io {
+                base: a.try_into().ok()?,
+                len: b.try_into().ok()?,
+            }),
+            Self::MEMORY => Some(Self::Memory { base: a, len: b }),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub fn into_raw(self) -> (u64, u64, u64) {
+        match self {
+            Self::Irq(irq) => {
+                let (gsi, flags) = irq.into_raw();
+
+                (Self::IRQ, gsi, flags)
+            }
+            Self::Io { base, len } => (Self::IO, base.into(), len.into()),
+            Self::Memory { base, len } => (Self::MEMORY, base, len),
+        }
+    }
+}
+
+#[derive(Copy, Clone, FromPrimitive, Debug, PartialEq, Eq)]
+#[repr(u64)]
+pub enum Clock {
+    /// The wall-clock time since the Unix epoch.
+    Realtime,
+    /// The time since the boot, which never goes back.
+    Monotonic,
+}
+
+/// The origin of [`seek`].
+#[derive(Copy, Clone, FromPrimitive, Debug, PartialEq, Eq)]
+#[repr(u64)]
+pub enum Whence {
+    /// The start of the file.
+    Set,
+    /// The current offset.
+    Current,
+    /// The end of the file.
+    End,
+}
+
+#[derive(Copy, Clone, FromPrimitive, Debug, PartialEq, Eq)]
+#[repr(u64)]
+pub enum Permission {
+    ReadOnly,
+    ReadWrite,
 }
 
 #[derive(Copy, Clone, FromPrimitive, Debug)]
@@ -230,6 +1510,42 @@ pub enum Ty {
     ReceiveFromAny,
     ReceiveFrom,
     Panic,
+    CreateSharedMemory,
+    MapSharedMemory,
+    UnmapSharedMemory,
+    GrantSharedMemory,
+    AllocateDmaPages,
+    GetMemoryStats,
+    Exit,
+    TryReceiveFromAny,
+    RegisterSwapDevice,
+    IrqRegister,
+    IrqAck,
+    GetMsiMessage,
+    IrqUnregister,
+    Shutdown,
+    Reboot,
+    AcpiDevice,
+    AcpiResource,
+    AcpiPciRoute,
+    GetAcpiTable,
+    ClockGetTime,
+    ConsoleInput,
+    ReadKernelLog,
+    Log,
+    SetLogLevel,
+    Open,
+    Read,
+    Seek,
+    Stat,
+    ReadDir,
+    Close,
+    Dup,
+    Dup2,
+    Pipe,
+    OpenServer,
+    ServerOf,
+    Spawn,
 }
 
 #[naked]
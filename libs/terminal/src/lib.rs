@@ -2,7 +2,10 @@
 
 #![no_std]
 
+extern crate alloc;
+
 mod font;
 pub mod log;
+pub mod serial;
 pub mod vram;
 mod writer;
@@ -1,17 +1,36 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 use {
-    super::writer::Writer,
-    core::{fmt, fmt::Write},
+    super::{serial::Serial, writer::Writer},
+    alloc::{string::String, vec::Vec},
+    conquer_once::spin::OnceCell,
+    core::{
+        convert::TryInto,
+        fmt::{self, Write},
+        time::Duration,
+    },
     log::{Level, LevelFilter, Metadata, Record, SetLoggerError},
     rgb::RGB8,
     spinning_top::Spinlock,
+    syscalls::LogRecord,
+    x86_64::instructions::interrupts,
 };
 
+/// The number of the records kept in the ring buffer.
+const RING_CAPACITY: usize = 256;
+
 static LOGGER: Logger = Logger;
 
+/// The clock which returns the time since the Unix epoch, used to prefix log lines.
+static CLOCK: OnceCell<fn() -> Option<Duration>> = OnceCell::uninit();
+
 static LOG_WRITER: Spinlock<Writer> = Spinlock::new(Writer::new(RGB8::new(0xff, 0xff, 0xff)));
 
+/// The latest records, which are kept even after they are scrolled off the screen.
+static RING: Spinlock<Ring> = Spinlock::new(Ring::new());
+
+static FILTERS: Spinlock<Filters> = Spinlock::new(Filters::new());
+
 #[macro_export]
 macro_rules! print {
     ($($arg:tt)*) => {
@@ -31,19 +50,29 @@ macro_rules! println{
 
 #[doc(hidden)]
 pub fn _print(args: fmt::Arguments<'_>) {
-    write!(*LOG_WRITER.lock(), "{args}").unwrap();
+    // Disable interrupts so that an interrupt handler printing something does not wait for the
+    // locks held by the interrupted code.
+    interrupts::without_interrupts(|| {
+        write!(*LOG_WRITER.lock(), "{args}").unwrap();
+        write!(Serial, "{args}").unwrap();
+    });
 }
 
 struct Logger;
 impl log::Log for Logger {
     fn enabled(&self, metadata: &Metadata<'_>) -> bool {
-        metadata.level() <= Level::Info
+        interrupts::without_interrupts(|| {
+            metadata.level() <= FILTERS.lock().level_for(None, metadata.target())
+        })
     }
 
     fn log(&self, record: &Record<'_>) {
-        if self.enabled(record.metadata()) {
-            println!("{} - {}", record.level(), record.args());
-        }
+        emit(
+            None,
+            record.level(),
+            record.module_path().unwrap_or_else(|| record.target()),
+            *record.args(),
+        );
     }
 
     fn flush(&self) {}
@@ -53,5 +82,237 @@ impl log::Log for Logger {
 ///
 /// This function may return an error from `log::set_logger` function.
 pub fn init() -> Result<(), SetLoggerError> {
-    log::set_logger(&LOGGER).map(|()| log::set_max_level(LevelFilter::Info))
+    // The records are filtered by `FILTERS`.
+    log::set_logger(&LOGGER).map(|()| log::set_max_level(LevelFilter::Trace))
+}
+
+/// Logs a record of a user process.
+pub fn log_for_process(pid: i32, name: &str, level: Level, module: &str, args: fmt::Arguments<'_>) {
+    emit(Some((pid, name)), level, module, args);
+}
+
+/// Sets the maximum level of the records logged by the processes named `target` or by the modules
+/// under `target`.
+///
+/// If `target` is empty, the level applies to the records which match no target.
+pub fn set_level(target: &str, level: LevelFilter) {
+    interrupts::without_interrupts(|| FILTERS.lock().set(target, level));
+}
+
+/// Prefixes the log lines with the time of day in UTC read from `clock`, which returns the time
+/// since the Unix epoch.
+///
+/// Only the first call takes effect.
+pub fn set_clock(clock: fn() -> Option<Duration>) {
+    let _ = CLOCK.try_init_once(|| clock);
+}
+
+/// Calls `f` with each record whose sequence number is `from` or larger, oldest first.
+///
+/// This function must be called with interrupts disabled.
+pub fn for_each_record_since(from: u64, f: impl FnMut(&LogRecord)) {
+    RING.lock().since(from).for_each(f);
+}
+
+/// Sends the latest `n` records to the serial port.
+///
+/// This function is for the panic handler, so it does nothing if the ring buffer is locked.
+pub fn dump_last_records(n: usize) {
+    let Some(ring) = RING.try_lock() else {
+        return;
+    };
+
+    let from = ring
+        .next_sequence
+        .saturating_sub(n.try_into().unwrap_or(u64::MAX));
+
+    let _ = writeln!(Serial, "--- The last {} log records ---", n);
+
+    for r in ring.since(from) {
+        let _ = writeln!(Serial, "{}", r);
+    }
+
+    let _ = writeln!(Serial, "--- End of the log records ---");
+}
+
+/// Writes the record to the ring buffer, the screen and the serial port if the level is not
+/// filtered out.
+fn emit(process: Option<(i32, &str)>, level: Level, module: &str, args: fmt::Arguments<'_>) {
+    // Disable interrupts for the same reason as `_print`.
+    let enabled = interrupts::without_interrupts(|| {
+        level
+            <= FILTERS
+                .lock()
+                .level_for(process.map(|(_, name)| name), module)
+    });
+
+    if !enabled {
+        return;
+    }
+
+    let timestamp = CLOCK.get().and_then(|clock| clock());
+
+    let mut message = Truncated::default();
+    let _ = write!(message, "{}", args);
+
+    interrupts::without_interrupts(|| {
+        RING.lock()
+            .push(timestamp, process, level, module, message.as_str());
+    });
+
+    let time = TimeOfDay(timestamp);
+
+    match process {
+        Some((pid, name)) => println!("{}{} - {}[{}] {}", time, level, name, pid, args),
+        None => println!("{}{} - {}", time, level, args),
+    }
+}
+
+/// Displays the time of day in UTC like `[12:34:56.789] `, or nothing if the time is unknown.
+struct TimeOfDay(Option<Duration>);
+impl fmt::Display for TimeOfDay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(t) = self.0 {
+            let seconds_of_day = t.as_secs() % (24 * 60 * 60);
+
+            write!(
+                f,
+                "[{:02}:{:02}:{:02}.{:03}] ",
+                seconds_of_day / 3600,
+                seconds_of_day / 60 % 60,
+                seconds_of_day % 60,
+                t.subsec_millis()
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The maximum levels for the processes and the modules.
+struct Filters {
+    default: LevelFilter,
+    rules: Vec<(String, LevelFilter)>,
+}
+impl Filters {
+    const fn new() -> Self {
+        Self {
+            default: LevelFilter::Info,
+            rules: Vec::new(),
+        }
+    }
+
+    fn set(&mut self, target: &str, level: LevelFilter) {
+        if target.is_empty() {
+            self.default = level;
+        } else if let Some(r) = self.rules.iter_mut().find(|(t, _)| *t == target) {
+            r.1 = level;
+        } else {
+            self.rules.push((target.into(), level));
+        }
+    }
+
+    /// Returns the level of the longest rule matching `module`, or the level of `process` if there
+    /// is no such rule.
+    fn level_for(&self, process: Option<&str>, module: &str) -> LevelFilter {
+        let for_module = self
+            .rules
+            .iter()
+            .filter(|(t, _)| {
+                module
+                    .strip_prefix(t.as_str())
+                    .map_or(false, |rest| rest.is_empty() || rest.starts_with("::"))
+            })
+            .max_by_key(|(t, _)| t.len());
+
+        let for_process = || process.and_then(|p| self.rules.iter().find(|(t, _)| *t == p));
+
+        for_module
+            .or_else(for_process)
+            .map_or(self.default, |(_, l)| *l)
+    }
+}
+
+struct Ring {
+    records: [Option<LogRecord>; RING_CAPACITY],
+    next_sequence: u64,
+}
+impl Ring {
+    const fn new() -> Self {
+        Self {
+            records: [None; RING_CAPACITY],
+            next_sequence: 0,
+        }
+    }
+
+    fn push(
+        &mut self,
+        timestamp: Option<Duration>,
+        process: Option<(i32, &str)>,
+        level: Level,
+        module: &str,
+        message: &str,
+    ) {
+        let i = Self::index(self.next_sequence);
+
+        self.records[i] = Some(LogRecord::new(
+            self.next_sequence,
+            timestamp,
+            process,
+            level,
+            module,
+            message,
+        ));
+
+        self.next_sequence += 1;
+    }
+
+    /// Returns the records whose sequence numbers are `from` or larger, oldest first.
+    fn since(&self, from: u64) -> impl Iterator<Item = &LogRecord> {
+        let oldest = self
+            .next_sequence
+            .saturating_sub(RING_CAPACITY.try_into().unwrap());
+
+        (from.max(oldest)..self.next_sequence)
+            .filter_map(move |s| self.records[Self::index(s)].as_ref())
+    }
+
+    fn index(sequence: u64) -> usize {
+        let capacity: u64 = RING_CAPACITY.try_into().unwrap();
+
+        (sequence % capacity).try_into().unwrap()
+    }
+}
+
+/// A buffer which keeps only the beginning of the written string that fits in a record.
+struct Truncated {
+    buf: [u8; LogRecord::MESSAGE_LEN],
+    len: usize,
+}
+impl Truncated {
+    fn as_str(&self) -> &str {
+        core::str::from_utf8(&self.buf[..self.len]).unwrap_or("")
+    }
+}
+impl Default for Truncated {
+    fn default() -> Self {
+        Self {
+            buf: [0; LogRecord::MESSAGE_LEN],
+            len: 0,
+        }
+    }
+}
+impl Write for Truncated {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut n = s.len().min(self.buf.len() - self.len);
+
+        while !s.is_char_boundary(n) {
+            n -= 1;
+        }
+
+        self.buf[self.len..self.len + n].copy_from_slice(&s.as_bytes()[..n]);
+        self.len += n;
+
+        Ok(())
+    }
 }
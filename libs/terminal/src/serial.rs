@@ -0,0 +1,48 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! The COM1 serial port.
+//!
+//! Everything printed to the terminal is also sent to the port, which is initialized on the first
+//! output.
+
+use {conquer_once::spin::Lazy, core::fmt, spinning_top::Spinlock, uart_16550::SerialPort};
+
+const COM1: u16 = 0x3f8;
+
+/// The IRQ which COM1 raises when it receives a byte.
+pub const IRQ: u8 = 4;
+
+static PORT: Lazy<Spinlock<SerialPort>> = Lazy::new(|| {
+    // SAFETY: COM1 is at the standard address.
+    let mut port = unsafe { SerialPort::new(COM1) };
+
+    // This also enables the interrupt on receiving a byte.
+    port.init();
+
+    Spinlock::new(port)
+});
+
+/// Returns a received byte, or [`None`] if there is none.
+///
+/// This function must be called with interrupts disabled, e.g. in the interrupt handler.
+#[must_use]
+pub fn try_receive() -> Option<u8> {
+    PORT.lock().try_receive().ok()
+}
+
+pub(crate) struct Serial;
+impl fmt::Write for Serial {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut port = PORT.lock();
+
+        for b in s.bytes() {
+            if b == b'\n' {
+                port.send_raw(b'\r');
+            }
+
+            port.send_raw(b);
+        }
+
+        Ok(())
+    }
+}
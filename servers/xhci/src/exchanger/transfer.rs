@@ -2,12 +2,12 @@
 
 use {
     super::receiver::{self, ReceiveFuture},
-    crate::structures::{descriptor, registers, ring::transfer},
+    crate::structures::{descriptor, dma, registers, ring::transfer},
     alloc::{sync::Arc, vec::Vec},
     core::convert::TryInto,
     futures_util::task::AtomicWaker,
     log::debug,
-    page_box::PageBox,
+    page_box::{DmaBuffer, PoolBox},
     spinning_top::Spinlock,
     x86_64::PhysAddr,
     xhci::ring::trb::{
@@ -31,7 +31,7 @@ impl Sender {
     }
 
     pub(crate) async fn get_max_packet_size_from_device_descriptor(&mut self) -> u16 {
-        let b = PageBox::from(descriptor::Device::default());
+        let b = dma::alloc(descriptor::Device::default());
 
         let setup = *transfer_trb::SetupStage::default()
             .set_transfer_type(TransferType::In)
@@ -97,8 +97,8 @@ impl Sender {
         self.issue_trbs(&[setup.into(), status.into()]).await;
     }
 
-    pub(crate) async fn get_configuration_descriptor(&mut self) -> PageBox<[u8]> {
-        let b = PageBox::new_slice(0, 4096);
+    pub(crate) async fn get_configuration_descriptor(&mut self) -> PoolBox<'static, [u8]> {
+        let b = dma::alloc_slice(0, 4096);
 
         let (setup, data, status) = Self::trbs_for_getting_descriptors(
             &b,
@@ -110,7 +110,7 @@ impl Sender {
         b
     }
 
-    pub(crate) async fn issue_normal_trb<T: ?Sized>(&mut self, b: &PageBox<T>) {
+    pub(crate) async fn issue_normal_trb(&mut self, b: &(impl DmaBuffer + ?Sized)) {
         let t = *Normal::default()
             .set_data_buffer_pointer(b.phys_addr().as_u64())
             .set_trb_transfer_length(b.bytes().as_usize().try_into().unwrap())
@@ -119,8 +119,8 @@ impl Sender {
         self.issue_trbs(&[t.into()]).await;
     }
 
-    fn trbs_for_getting_descriptors<T: ?Sized>(
-        b: &PageBox<T>,
+    fn trbs_for_getting_descriptors(
+        b: &impl DmaBuffer,
         t: DescTyIdx,
     ) -> (
         transfer_trb::Allowed,
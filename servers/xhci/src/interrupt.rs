@@ -0,0 +1,100 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Interrupts from the xHC.
+//!
+//! If the xHC supports MSI or MSI-X, the Event Ring is read when the kernel notifies an interrupt
+//! instead of being polled.
+
+use {
+    crate::{pci, structures::registers},
+    conquer_once::spin::OnceCell,
+    core::task::Waker,
+    futures_util::task::AtomicWaker,
+    log::{info, warn},
+    syscalls::{Interrupt, MSI_VECTORS},
+};
+
+static INTERRUPT: OnceCell<Interrupt> = OnceCell::uninit();
+
+static EVENT_WAKER: AtomicWaker = AtomicWaker::new();
+
+/// Enables the interrupts of the xHC. Returns `false` if the interrupts are not available.
+pub(crate) fn init() -> bool {
+    let Some(space) = pci::iter_devices().find(pci::config::Space::is_xhci) else {
+        return false;
+    };
+
+    let Some(vector) = MSI_VECTORS
+        .clone()
+        .find(|v| syscalls::irq_register(Interrupt::Msi(*v)))
+    else {
+        warn!("No interrupt vector is available.");
+        return false;
+    };
+
+    let m = syscalls::msi_message(vector).expect("Failed to get the MSI message.");
+
+    if !space.enable_msi(m) {
+        info!("The xHC supports neither MSI nor MSI-X.");
+
+        assert!(
+            syscalls::irq_unregister(Interrupt::Msi(vector)),
+            "Failed to unregister the interrupt."
+        );
+
+        return false;
+    }
+
+    registers::handle(|r| {
+        r.interrupter_register_set
+            .interrupter_mut(0)
+            .iman
+            .update_volatile(|i| {
+                i.set_interrupt_enable();
+            });
+        r.operational.usbcmd.update_volatile(|u| {
+            u.set_interrupter_enable();
+        });
+    });
+
+    INTERRUPT.init_once(|| Interrupt::Msi(vector));
+
+    info!("The xHC interrupts with the vector {:#x}.", vector);
+
+    true
+}
+
+pub(crate) fn enabled() -> bool {
+    INTERRUPT.is_initialized()
+}
+
+/// Wakes the task waiting for events if `interrupt` is the one of the xHC.
+///
+/// Returns `false` if `interrupt` is not the one of the xHC.
+pub(crate) fn handle(interrupt: Interrupt) -> bool {
+    if INTERRUPT.get() != Some(&interrupt) {
+        return false;
+    }
+
+    registers::handle(|r| {
+        r.interrupter_register_set
+            .interrupter_mut(0)
+            .iman
+            .update_volatile(|i| {
+                i.clear_interrupt_pending();
+            });
+    });
+
+    assert!(
+        syscalls::irq_ack(interrupt),
+        "Failed to acknowledge the interrupt."
+    );
+
+    EVENT_WAKER.wake();
+
+    true
+}
+
+pub(crate) fn register_event_waker(w: &Waker) {
+    EVENT_WAKER.register(w);
+}
@@ -0,0 +1,61 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Messages sent to this process.
+//!
+//! The interrupt notifications are handled here, and the other messages are queued as requests.
+
+use {
+    crate::interrupt,
+    alloc::collections::VecDeque,
+    core::{
+        pin::Pin,
+        task::{Context, Poll},
+    },
+    futures_util::{stream::Stream, task::AtomicWaker},
+    log::warn,
+    message::Message,
+    spinning_top::Spinlock,
+    syscalls::Interrupt,
+};
+
+static REQUESTS: Spinlock<VecDeque<Message>> = Spinlock::new(VecDeque::new());
+
+static REQUEST_WAKER: AtomicWaker = AtomicWaker::new();
+
+/// Handles the all messages which have already arrived.
+pub(crate) fn dispatch_arrived() {
+    while let Some(m) = syscalls::try_receive_from_any() {
+        dispatch(m);
+    }
+}
+
+/// Waits for a message and handles it.
+pub(crate) fn wait_and_dispatch() {
+    dispatch(syscalls::receive_from_any());
+}
+
+fn dispatch(m: Message) {
+    if let Some(i) = Interrupt::from_message(&m) {
+        if !interrupt::handle(i) {
+            warn!("Unexpected interrupt: {:?}", i);
+        }
+    } else {
+        REQUESTS.lock().push_back(m);
+        REQUEST_WAKER.wake();
+    }
+}
+
+/// The stream of the messages other than the interrupt notifications.
+pub(crate) struct Requests;
+impl Stream for Requests {
+    type Item = Message;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        REQUEST_WAKER.register(cx.waker());
+
+        REQUESTS
+            .lock()
+            .pop_front()
+            .map_or(Poll::Pending, |m| Poll::Ready(Some(m)))
+    }
+}
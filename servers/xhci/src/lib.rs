@@ -25,6 +25,8 @@ pub(crate) type Futurelock<T> = GenericMutex<RawSpinlock, T>;
 pub(crate) type FuturelockGuard<'a, T> = GenericMutexGuard<'a, RawSpinlock, T>;
 
 mod exchanger;
+mod interrupt;
+mod ipc;
 mod multitask;
 mod pci;
 mod port;
@@ -81,7 +83,11 @@ fn init_and_spawn_tasks() {
 fn spawn_tasks(e: event::Ring) {
     port::spawn_all_connected_port_tasks();
 
-    multitask::add(Task::new_poll(event::task(e)));
+    if interrupt::init() {
+        multitask::add(Task::new(event::task(e)));
+    } else {
+        multitask::add(Task::new_poll(event::task(e)));
+    }
 }
 
 fn iter_xhc() -> impl Iterator<Item = PhysAddr> {
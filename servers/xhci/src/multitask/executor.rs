@@ -10,6 +10,7 @@
 
 use {
     super::task,
+    crate::ipc,
     alloc::collections::BTreeMap,
     core::task::{Context, Poll, Waker},
     task::Task,
@@ -28,14 +29,29 @@ impl Executor {
 
     pub(crate) fn run(&mut self) -> ! {
         loop {
-            self.run_woken_tasks();
+            ipc::dispatch_arrived();
+
+            if !self.run_woken_tasks() {
+                // No task can proceed until a message or an interrupt arrives.
+                ipc::wait_and_dispatch();
+            }
         }
     }
 
-    fn run_woken_tasks(&mut self) {
-        while let Some(id) = Self::pop_woken_task_id() {
-            self.run_task(id);
+    /// Runs the tasks woken so far, and returns `false` if there is none.
+    ///
+    /// The polling tasks are woken again right after they run, so the tasks woken while running
+    /// the others are left for the next call.
+    fn run_woken_tasks(&mut self) -> bool {
+        let n = task::COLLECTION.lock().num_of_woken_tasks();
+
+        for _ in 0..n {
+            if let Some(id) = Self::pop_woken_task_id() {
+                self.run_task(id);
+            }
         }
+
+        n > 0
     }
 
     fn pop_woken_task_id() -> Option<task::Id> {
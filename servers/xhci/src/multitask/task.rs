@@ -65,6 +65,10 @@ impl Collection {
         self.woken_task_ids.pop()
     }
 
+    pub(crate) fn num_of_woken_tasks(&self) -> usize {
+        self.woken_task_ids.len()
+    }
+
     pub(crate) fn remove_task(&mut self, id: Id) -> Option<Task> {
         self.tasks.remove(&id)
     }
@@ -0,0 +1,87 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use {
+    super::{RegisterIndex, Registers},
+    bit_field::BitField,
+    core::convert::TryInto,
+};
+
+/// An iterator over the capability list of a function.
+#[derive(Debug)]
+pub(crate) struct Capabilities<'a> {
+    registers: &'a Registers,
+    next: u8,
+    remaining: usize,
+}
+impl<'a> Capabilities<'a> {
+    pub(super) fn new(registers: &'a Registers) -> Self {
+        const CAPABILITIES_LIST: usize = 20;
+
+        let next = if registers
+            .get(RegisterIndex::new(1))
+            .get_bit(CAPABILITIES_LIST)
+        {
+            registers.get(RegisterIndex::new(0x0d)).get_bits(0..=7)
+        } else {
+            0
+        };
+
+        Self {
+            registers,
+            next: next.try_into().unwrap(),
+            // Each capability takes at least 4 bytes after the 64-byte header. This bounds the
+            // iteration even if the list is broken and loops.
+            remaining: (256 - 64) / 4,
+        }
+    }
+}
+impl<'a> Iterator for Capabilities<'a> {
+    type Item = Capability<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // The bottom two bits are reserved.
+        let offset = self.next & !0b11;
+
+        if offset == 0 || self.remaining == 0 {
+            return None;
+        }
+
+        self.remaining -= 1;
+
+        let index = RegisterIndex::new((offset / 4).into());
+        let header = self.registers.get(index);
+
+        self.next = header.get_bits(8..=15).try_into().unwrap();
+
+        Some(Capability {
+            registers: self.registers,
+            index,
+            id: header.get_bits(0..=7).try_into().unwrap(),
+        })
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Capability<'a> {
+    registers: &'a Registers,
+    index: RegisterIndex,
+    id: u8,
+}
+impl<'a> Capability<'a> {
+    pub(crate) fn id(&self) -> u8 {
+        self.id
+    }
+
+    /// Returns the `i`th double word of this capability.
+    pub(super) fn get(&self, i: usize) -> u32 {
+        self.registers.get(self.index + i)
+    }
+
+    pub(super) fn set(&self, i: usize, value: u32) {
+        self.registers.set(self.index + i, value);
+    }
+
+    pub(super) fn registers(&self) -> &'a Registers {
+        self.registers
+    }
+}
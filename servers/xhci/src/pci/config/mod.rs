@@ -1,13 +1,20 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 pub(crate) mod bar;
+pub(crate) mod capability;
 mod common;
+pub(crate) mod msi;
+pub(crate) mod msix;
 pub(crate) mod type_spec;
 
 use {
     self::common::Common,
     bar::Bar,
+    capability::Capabilities,
     core::{convert::TryFrom, ops::Add},
+    msi::Msi,
+    msix::MsiX,
+    syscalls::MsiMessage,
     type_spec::TypeSpec,
     x86_64::PhysAddr,
 };
@@ -32,6 +39,54 @@ impl Space {
         self.type_spec().base_address(index)
     }
 
+    pub(crate) fn capabilities(&self) -> Capabilities<'_> {
+        Capabilities::new(&self.registers)
+    }
+
+    pub(crate) fn msi(&self) -> Option<Msi<'_>> {
+        self.capabilities().find_map(Msi::new)
+    }
+
+    pub(crate) fn msix(&self) -> Option<MsiX<'_>> {
+        self.capabilities().find_map(MsiX::new)
+    }
+
+    /// Makes the function signal its interrupts with `m` instead of INTx.
+    ///
+    /// MSI-X is preferred to MSI, and only the first vector is used. Returns `false` if the
+    /// function supports neither of them.
+    pub(crate) fn enable_msi(&self, m: MsiMessage) -> bool {
+        if let Some(msix) = self.msix() {
+            if let Some(msi) = self.msi() {
+                msi.disable();
+            }
+
+            let mut table = msix.table();
+            table.set(0, m);
+            table.unmask(0);
+
+            msix.enable();
+        } else if let Some(msi) = self.msi() {
+            msi.enable(m);
+        } else {
+            return false;
+        }
+
+        self.disable_intx();
+
+        true
+    }
+
+    fn disable_intx(&self) {
+        const INTERRUPT_DISABLE: u32 = 1 << 10;
+
+        let index = RegisterIndex::new(1);
+
+        // Writing 0 to the status bits does not affect them.
+        let command = self.registers.get(index) & 0xffff;
+        self.registers.set(index, command | INTERRUPT_DISABLE);
+    }
+
     fn type_spec(&self) -> TypeSpec<'_> {
         TypeSpec::new(&self.registers, &self.common())
     }
@@ -66,6 +121,11 @@ impl Registers {
         let accessor = ConfigAddress::new(self.bus, self.device, Function::zero(), index);
         unsafe { accessor.read() }
     }
+
+    fn set(&self, index: RegisterIndex, value: u32) {
+        let accessor = ConfigAddress::new(self.bus, self.device, Function::zero(), index);
+        unsafe { accessor.write(value) }
+    }
 }
 
 struct ConfigAddress {
@@ -106,6 +166,14 @@ impl ConfigAddress {
             syscalls::inl(Self::PORT_CONFIG_DATA)
         }
     }
+
+    /// SAFETY: `self` must contain the valid config address.
+    unsafe fn write(&self, value: u32) {
+        unsafe {
+            syscalls::outl(Self::PORT_CONFIG_ADDR, self.as_u32());
+            syscalls::outl(Self::PORT_CONFIG_DATA, value);
+        }
+    }
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -0,0 +1,53 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use {
+    super::capability::Capability,
+    bit_field::BitField,
+    core::{convert::TryInto, ops::RangeInclusive},
+    syscalls::MsiMessage,
+};
+
+#[derive(Debug)]
+pub(crate) struct Msi<'a> {
+    capability: Capability<'a>,
+}
+impl<'a> Msi<'a> {
+    const ID: u8 = 0x05;
+
+    const ENABLE: usize = 16;
+    const MULTIPLE_MESSAGE_ENABLE: RangeInclusive<usize> = 20..=22;
+    const ADDRESS_64BIT: usize = 23;
+
+    pub(super) fn new(capability: Capability<'a>) -> Option<Self> {
+        (capability.id() == Self::ID).then_some(Self { capability })
+    }
+
+    /// Makes the function signal its interrupt with `m`. Only a single vector is used.
+    pub(crate) fn enable(&self, m: MsiMessage) {
+        let mut control = self.capability.get(0);
+
+        self.capability
+            .set(1, m.address().get_bits(0..32).try_into().unwrap());
+
+        if control.get_bit(Self::ADDRESS_64BIT) {
+            self.capability
+                .set(2, m.address().get_bits(32..64).try_into().unwrap());
+            self.capability.set(3, m.data());
+        } else {
+            self.capability.set(2, m.data());
+        }
+
+        control.set_bits(Self::MULTIPLE_MESSAGE_ENABLE, 0);
+        control.set_bit(Self::ENABLE, true);
+
+        self.capability.set(0, control);
+    }
+
+    pub(crate) fn disable(&self) {
+        let mut control = self.capability.get(0);
+
+        control.set_bit(Self::ENABLE, false);
+
+        self.capability.set(0, control);
+    }
+}
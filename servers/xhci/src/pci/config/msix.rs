@@ -0,0 +1,96 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use {
+    super::{bar, capability::Capability, common::Common, type_spec::TypeSpec},
+    accessor::array,
+    bit_field::BitField,
+    core::convert::TryInto,
+    ralib::mem::accessor::Mapper,
+    syscalls::MsiMessage,
+    x86_64::PhysAddr,
+};
+
+#[derive(Debug)]
+pub(crate) struct MsiX<'a> {
+    capability: Capability<'a>,
+}
+impl<'a> MsiX<'a> {
+    const ID: u8 = 0x11;
+
+    const FUNCTION_MASK: usize = 30;
+    const ENABLE: usize = 31;
+
+    pub(super) fn new(capability: Capability<'a>) -> Option<Self> {
+        (capability.id() == Self::ID).then_some(Self { capability })
+    }
+
+    fn table_size(&self) -> usize {
+        (self.capability.get(0).get_bits(16..=26) + 1)
+            .try_into()
+            .unwrap()
+    }
+
+    pub(crate) fn table(&self) -> Table {
+        let base = self.structure_address(1);
+
+        // SAFETY: The address is read from the MSI-X capability.
+        let entries = unsafe {
+            array::ReadWrite::new(base.as_u64().try_into().unwrap(), self.table_size(), Mapper)
+        };
+
+        Table { entries }
+    }
+
+    pub(crate) fn enable(&self) {
+        let mut control = self.capability.get(0);
+
+        control.set_bit(Self::FUNCTION_MASK, false);
+        control.set_bit(Self::ENABLE, true);
+
+        self.capability.set(0, control);
+    }
+
+    /// Returns the address of the structure located by the `i`th double word of the capability.
+    fn structure_address(&self, i: usize) -> PhysAddr {
+        let r = self.capability.get(i);
+
+        let bir = bar::Index::new(r.get_bits(0..=2));
+        let offset = r & !0b111;
+
+        let registers = self.capability.registers();
+        let bar = TypeSpec::new(registers, &Common::new(registers)).base_address(bir);
+
+        bar + u64::from(offset)
+    }
+}
+
+pub(crate) struct Table {
+    entries: array::ReadWrite<Entry, Mapper>,
+}
+impl Table {
+    pub(crate) fn set(&mut self, i: usize, m: MsiMessage) {
+        self.entries.update_volatile_at(i, |e| {
+            e.address_low = m.address().get_bits(0..32).try_into().unwrap();
+            e.address_high = m.address().get_bits(32..64).try_into().unwrap();
+            e.data = m.data();
+        });
+    }
+
+    pub(crate) fn unmask(&mut self, i: usize) {
+        self.entries.update_volatile_at(i, |e| {
+            e.vector_control.set_bit(Entry::MASKED, false);
+        });
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+struct Entry {
+    address_low: u32,
+    address_high: u32,
+    data: u32,
+    vector_control: u32,
+}
+impl Entry {
+    const MASKED: usize = 0;
+}
@@ -3,19 +3,19 @@
 use {
     crate::{
         port::init::fully_operational::FullyOperational,
-        structures::descriptor::{Configuration, Descriptor},
+        structures::{
+            descriptor::{Configuration, Descriptor},
+            dma,
+        },
     },
-    alloc::{string::String, vec::Vec},
+    alloc::vec::Vec,
     log::info,
-    page_box::PageBox,
-    spinning_top::Spinlock,
+    page_box::PoolBox,
     xhci::context::EndpointType,
 };
 
 const LOWER_ALPHABETS: &str = "abcdefghijklmnopqrstuvwxyz";
 
-static STR: Spinlock<String> = Spinlock::new(String::new());
-
 pub(in crate::port) async fn task(eps: FullyOperational) {
     let mut k = Keyboard::new(eps);
     k.configure().await;
@@ -32,13 +32,13 @@ pub(in crate::port) async fn task(eps: FullyOperational) {
 
 pub(crate) struct Keyboard {
     ep: FullyOperational,
-    buf: PageBox<[u8; 8]>,
+    buf: PoolBox<'static, [u8; 8]>,
 }
 impl Keyboard {
     pub(in crate::port) fn new(ep: FullyOperational) -> Self {
         Self {
             ep,
-            buf: [0; 8].into(),
+            buf: dma::alloc([0; 8]),
         }
     }
 
@@ -77,14 +77,13 @@ impl Keyboard {
             .collect::<Vec<&Configuration>>()[0]
     }
 
+    /// Passes the pressed keys to the console in the kernel.
     fn store_key(&self) {
         for c in self.buf.iter().skip(2) {
             if *c >= 4 && *c <= 0x1d {
-                STR.lock()
-                    .push(LOWER_ALPHABETS.chars().nth((c - 4).into()).unwrap());
+                syscalls::console_input(LOWER_ALPHABETS.chars().nth((c - 4).into()).unwrap());
             } else if *c == 0x28 {
-                info!("{}", STR.lock());
-                *STR.lock() = String::new();
+                syscalls::console_input('\n');
             }
         }
     }
@@ -1,20 +1,32 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 mod scsi;
+mod swap;
 
 use {
     crate::{
+        ipc::Requests,
         port::init::fully_operational::FullyOperational,
-        structures::descriptor::{Configuration, Descriptor},
+        structures::{
+            descriptor::{Configuration, Descriptor},
+            dma,
+        },
     },
     alloc::vec::Vec,
-    log::info,
-    page_box::PageBox,
+    core::convert::TryFrom,
+    futures_util::StreamExt,
+    log::{info, warn},
+    message::{Body, Header, Message},
+    num_traits::FromPrimitive,
+    page_box::{DmaBuffer, PageBox, PoolBox},
     scsi::{
         command_data_block,
         response::{Inquiry, Read10, ReadCapacity10},
         CommandBlockWrapper, CommandBlockWrapperHeaderBuilder, CommandStatusWrapper,
     },
+    swap::Frame,
+    syscalls::SwapOperation,
+    x86_64::PhysAddr,
     xhci::context::EndpointType,
 };
 
@@ -34,6 +46,8 @@ pub(in crate::port) async fn task(eps: FullyOperational) {
     m.read10().await;
 
     m.write10().await;
+
+    m.serve_swap(b).await;
 }
 
 struct MassStorage {
@@ -75,7 +89,7 @@ impl MassStorage {
             .build()
             .expect("Failed to build an inquiry command block wrapper.");
         let data = command_data_block::Inquiry::new(LEN);
-        let wrapper = PageBox::from(CommandBlockWrapper::new(header, data.into()));
+        let wrapper = dma::alloc(CommandBlockWrapper::new(header, data.into()));
 
         let (response, status): (PageBox<Inquiry>, _) = self.send_scsi_command(&wrapper).await;
 
@@ -92,7 +106,7 @@ impl MassStorage {
             .build()
             .expect("Failed to build a read capacity command block wrapper");
         let data = command_data_block::ReadCapacity::default();
-        let wrapper = PageBox::from(CommandBlockWrapper::new(header, data.into()));
+        let wrapper = dma::alloc(CommandBlockWrapper::new(header, data.into()));
 
         let (response, status): (PageBox<ReadCapacity10>, _) =
             self.send_scsi_command(&wrapper).await;
@@ -110,7 +124,7 @@ impl MassStorage {
             .build()
             .expect("Failed to build a read 10 command block wrapper.");
         let data = command_data_block::Read10::new(0, 64);
-        let wrapper = PageBox::from(CommandBlockWrapper::new(header, data.into()));
+        let wrapper = dma::alloc(CommandBlockWrapper::new(header, data.into()));
 
         let (response, status): (PageBox<Read10>, _) = self.send_scsi_command(&wrapper).await;
 
@@ -127,18 +141,101 @@ impl MassStorage {
             .build()
             .expect("Failed to build a write 10 command block wrapper.");
         let data = command_data_block::Write10::new(0, 64);
-        let wrapper = PageBox::from(CommandBlockWrapper::new(header, data.into()));
+        let wrapper = dma::alloc(CommandBlockWrapper::new(header, data.into()));
 
-        let content = PageBox::from(0x334_usize);
+        let content = dma::alloc(0x334_usize);
 
         let status = self.send_scsi_command_for_out(&wrapper, &content).await;
         status.check_corruption();
     }
 
+    /// Registers this device as the swap device and serves the requests from the kernel.
+    async fn serve_swap(&mut self, capacity: ReadCapacity10) {
+        let address_limit = if dma::supports_64bit_addressing() {
+            u64::MAX
+        } else {
+            1 << 32
+        };
+
+        if !syscalls::register_swap_device(capacity.block_size(), address_limit) {
+            return;
+        }
+
+        info!("USB Mass Storage is registered as the swap device.");
+
+        let mut requests = Requests;
+
+        while let Some(m) = requests.next().await {
+            let succeeded = self.handle_swap_request(m.body).await;
+
+            let reply = Message::new(Header::default(), Body(u64::from(!succeeded), 0, 0, 0, 0));
+            syscalls::send(reply, m.header.sender);
+        }
+    }
+
+    async fn handle_swap_request(&mut self, body: Body) -> bool {
+        let (Some(operation), Ok(lba), Ok(num_of_blocks)) = (
+            SwapOperation::from_u64(body.0),
+            u32::try_from(body.1),
+            u16::try_from(body.2),
+        ) else {
+            warn!("Invalid swap request: {:?}", body);
+            return false;
+        };
+
+        let frame = Frame::new(PhysAddr::new(body.3));
+
+        let status = match operation {
+            SwapOperation::Read => self.read_blocks(lba, num_of_blocks, &frame).await,
+            SwapOperation::Write => self.write_blocks(lba, num_of_blocks, &frame).await,
+        };
+
+        status.check_corruption();
+        status.succeeded()
+    }
+
+    async fn read_blocks(
+        &mut self,
+        lba: u32,
+        num_of_blocks: u16,
+        buf: &(impl DmaBuffer + ?Sized),
+    ) -> PoolBox<'static, CommandStatusWrapper> {
+        let header = CommandBlockWrapperHeaderBuilder::default()
+            .transfer_length(buf.bytes().as_usize().try_into().unwrap())
+            .flags(scsi::Flags::In)
+            .lun(0)
+            .command_len(0x0a)
+            .build()
+            .expect("Failed to build a read 10 command block wrapper.");
+        let data = command_data_block::Read10::new(lba, num_of_blocks);
+        let wrapper = dma::alloc(CommandBlockWrapper::new(header, data.into()));
+
+        self.send_scsi_command_for_in(&wrapper, buf).await
+    }
+
+    async fn write_blocks(
+        &mut self,
+        lba: u32,
+        num_of_blocks: u16,
+        buf: &(impl DmaBuffer + ?Sized),
+    ) -> PoolBox<'static, CommandStatusWrapper> {
+        let header = CommandBlockWrapperHeaderBuilder::default()
+            .transfer_length(buf.bytes().as_usize().try_into().unwrap())
+            .flags(scsi::Flags::Out)
+            .lun(0)
+            .command_len(0x0a)
+            .build()
+            .expect("Failed to build a write 10 command block wrapper.");
+        let data = command_data_block::Write10::new(lba, num_of_blocks);
+        let wrapper = dma::alloc(CommandBlockWrapper::new(header, data.into()));
+
+        self.send_scsi_command_for_out(&wrapper, buf).await
+    }
+
     async fn send_scsi_command<T>(
         &mut self,
-        c: &PageBox<CommandBlockWrapper>,
-    ) -> (PageBox<T>, PageBox<CommandStatusWrapper>)
+        c: &PoolBox<'_, CommandBlockWrapper>,
+    ) -> (PageBox<T>, PoolBox<'static, CommandStatusWrapper>)
     where
         T: Default,
     {
@@ -150,15 +247,25 @@ impl MassStorage {
 
     async fn send_scsi_command_for_out(
         &mut self,
-        c: &PageBox<CommandBlockWrapper>,
-        d: &PageBox<impl ?Sized>,
-    ) -> PageBox<CommandStatusWrapper> {
+        c: &PoolBox<'_, CommandBlockWrapper>,
+        d: &(impl DmaBuffer + ?Sized),
+    ) -> PoolBox<'static, CommandStatusWrapper> {
         self.send_command_block_wrapper(c).await;
         self.send_additional_data(d).await;
         self.receive_command_status().await
     }
 
-    async fn send_command_block_wrapper(&mut self, c: &PageBox<CommandBlockWrapper>) {
+    async fn send_scsi_command_for_in(
+        &mut self,
+        c: &PoolBox<'_, CommandBlockWrapper>,
+        d: &(impl DmaBuffer + ?Sized),
+    ) -> PoolBox<'static, CommandStatusWrapper> {
+        self.send_command_block_wrapper(c).await;
+        self.receive_additional_data(d).await;
+        self.receive_command_status().await
+    }
+
+    async fn send_command_block_wrapper(&mut self, c: &PoolBox<'_, CommandBlockWrapper>) {
         self.ep
             .issue_normal_trb(c, EndpointType::BulkOut)
             .await
@@ -177,15 +284,22 @@ impl MassStorage {
         c
     }
 
-    async fn send_additional_data(&mut self, d: &PageBox<impl ?Sized>) {
+    async fn send_additional_data(&mut self, d: &(impl DmaBuffer + ?Sized)) {
         self.ep
             .issue_normal_trb(d, EndpointType::BulkOut)
             .await
             .expect("Failed to send a data.");
     }
 
-    async fn receive_command_status(&mut self) -> PageBox<CommandStatusWrapper> {
-        let b = PageBox::default();
+    async fn receive_additional_data(&mut self, d: &(impl DmaBuffer + ?Sized)) {
+        self.ep
+            .issue_normal_trb(d, EndpointType::BulkIn)
+            .await
+            .expect("Failed to receive a data.");
+    }
+
+    async fn receive_command_status(&mut self) -> PoolBox<'static, CommandStatusWrapper> {
+        let b = dma::alloc(CommandStatusWrapper::default());
         self.ep
             .issue_normal_trb(&b, EndpointType::BulkIn)
             .await
@@ -61,6 +61,12 @@ impl CommandStatusWrapper {
             "The signature of the Command Status Wrapper is wrong."
         );
     }
+
+    pub(super) fn succeeded(&self) -> bool {
+        let status = self.status;
+
+        status == Status::Good as u8
+    }
 }
 
 #[derive(Copy, Clone, Debug, FromPrimitive)]
@@ -26,7 +26,7 @@ impl ReadCapacity10 {
         BigEndian::read_u32(&self.lba)
     }
 
-    fn block_size(self) -> u32 {
+    pub(in super::super) fn block_size(self) -> u32 {
         BigEndian::read_u32(&self.block_size)
     }
 }
@@ -0,0 +1,27 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use {
+    os_units::Bytes,
+    page_box::DmaBuffer,
+    x86_64::{
+        structures::paging::{PageSize, Size4KiB},
+        PhysAddr,
+    },
+};
+
+/// A page frame which the kernel asks to transfer.
+pub(super) struct Frame(PhysAddr);
+impl Frame {
+    pub(super) fn new(addr: PhysAddr) -> Self {
+        Self(addr)
+    }
+}
+impl DmaBuffer for Frame {
+    fn phys_addr(&self) -> PhysAddr {
+        self.0
+    }
+
+    fn bytes(&self) -> Bytes {
+        Bytes::new(Size4KiB::SIZE.try_into().unwrap())
+    }
+}
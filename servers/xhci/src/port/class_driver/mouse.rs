@@ -3,11 +3,14 @@
 use {
     crate::{
         port::init::fully_operational::FullyOperational,
-        structures::descriptor::{Configuration, Descriptor},
+        structures::{
+            descriptor::{Configuration, Descriptor},
+            dma,
+        },
     },
     alloc::vec::Vec,
     log::info,
-    page_box::PageBox,
+    page_box::PoolBox,
     xhci::context::EndpointType,
 };
 
@@ -31,13 +34,13 @@ pub(in super::super) async fn task(eps: FullyOperational) {
 
 pub(crate) struct Mouse {
     ep: FullyOperational,
-    buf: PageBox<[i8; 4]>,
+    buf: PoolBox<'static, [i8; 4]>,
 }
 impl Mouse {
     pub(super) fn new(ep: FullyOperational) -> Self {
         Self {
             ep,
-            buf: [0; 4].into(),
+            buf: dma::alloc([0; 4]),
         }
     }
 
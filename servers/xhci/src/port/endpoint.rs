@@ -2,7 +2,7 @@
 
 use {
     crate::{exchanger::transfer, structures::descriptor},
-    page_box::PageBox,
+    page_box::{DmaBuffer, PoolBox},
     x86_64::PhysAddr,
     xhci::context::EndpointType,
 };
@@ -25,7 +25,7 @@ impl Default {
             .await
     }
 
-    pub(super) async fn get_raw_configuration_descriptors(&mut self) -> PageBox<[u8]> {
+    pub(super) async fn get_raw_configuration_descriptors(&mut self) -> PoolBox<'static, [u8]> {
         self.sender.get_configuration_descriptor().await
     }
 
@@ -63,7 +63,7 @@ impl NonDefault {
         self.desc.ty()
     }
 
-    pub(super) async fn issue_normal_trb<T: ?Sized>(&mut self, b: &PageBox<T>) {
+    pub(super) async fn issue_normal_trb(&mut self, b: &(impl DmaBuffer + ?Sized)) {
         self.sender.issue_normal_trb(b).await;
     }
 }
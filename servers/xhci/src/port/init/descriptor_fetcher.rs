@@ -13,7 +13,7 @@ use {
     },
     alloc::{sync::Arc, vec::Vec},
     log::debug,
-    page_box::PageBox,
+    page_box::PoolBox,
     spinning_top::Spinlock,
 };
 
@@ -60,18 +60,18 @@ impl DescriptorFetcher {
         self.ep0
     }
 
-    async fn get_raw_descriptors(&mut self) -> PageBox<[u8]> {
+    async fn get_raw_descriptors(&mut self) -> PoolBox<'static, [u8]> {
         self.ep0.get_raw_configuration_descriptors().await
     }
 }
 
 struct RawDescriptorParser {
-    raw: PageBox<[u8]>,
+    raw: PoolBox<'static, [u8]>,
     current: usize,
     len: usize,
 }
 impl RawDescriptorParser {
-    fn new(raw: PageBox<[u8]>) -> Self {
+    fn new(raw: PoolBox<'static, [u8]>) -> Self {
         let len = raw.len();
 
         Self {
@@ -1,7 +1,7 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 use {
-    super::structures::registers,
+    super::structures::{dma, registers},
     crate::multitask::{self, task::Task},
     alloc::collections::VecDeque,
     conquer_once::spin::Lazy,
@@ -80,6 +80,7 @@ async fn init_port_and_slot_exclusively(port_number: u8) -> FullyOperational {
     let fully_operational = init::init(port_number).await;
     CURRENT_RESET_PORT.lock().complete_reset();
     info!("Port {} reset completed.", port_number);
+    info!("DMA pool: {}", dma::pool_stats());
     fully_operational
 }
 
@@ -1,8 +1,8 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 use {
-    super::registers,
-    page_box::PageBox,
+    super::{dma, registers},
+    page_box::PoolBox,
     x86_64::PhysAddr,
     xhci::context::{
         Device32Byte, Device64Byte, DeviceHandler, Input32Byte, Input64Byte, InputControlHandler,
@@ -12,20 +12,20 @@ use {
 
 pub(crate) struct Context {
     pub(crate) input: Input,
-    pub(crate) output: PageBox<Device>,
+    pub(crate) output: Device,
 }
 impl Default for Context {
     fn default() -> Self {
         Self {
             input: Input::default(),
-            output: Device::default().into(),
+            output: Device::default(),
         }
     }
 }
 
 pub(crate) enum Input {
-    Byte64(PageBox<Input64Byte>),
-    Byte32(PageBox<Input32Byte>),
+    Byte64(PoolBox<'static, Input64Byte>),
+    Byte32(PoolBox<'static, Input32Byte>),
 }
 impl Input {
     pub(crate) fn control_mut(&mut self) -> &mut dyn InputControlHandler {
@@ -52,23 +52,31 @@ impl Input {
 impl Default for Input {
     fn default() -> Self {
         if csz() {
-            Self::Byte64(Input64Byte::default().into())
+            Self::Byte64(dma::alloc(Input64Byte::default()))
         } else {
-            Self::Byte32(Input32Byte::default().into())
+            Self::Byte32(dma::alloc(Input32Byte::default()))
         }
     }
 }
 
 pub(crate) enum Device {
-    Byte64(PageBox<Device64Byte>),
-    Byte32(PageBox<Device32Byte>),
+    Byte64(PoolBox<'static, Device64Byte>),
+    Byte32(PoolBox<'static, Device32Byte>),
+}
+impl Device {
+    pub(crate) fn phys_addr(&self) -> PhysAddr {
+        match self {
+            Self::Byte32(b32) => b32.phys_addr(),
+            Self::Byte64(b64) => b64.phys_addr(),
+        }
+    }
 }
 impl Default for Device {
     fn default() -> Self {
         if csz() {
-            Self::Byte64(Device64Byte::default().into())
+            Self::Byte64(dma::alloc(Device64Byte::default()))
         } else {
-            Self::Byte32(Device32Byte::default().into())
+            Self::Byte32(dma::alloc(Device32Byte::default()))
         }
     }
 }
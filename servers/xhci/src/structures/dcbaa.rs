@@ -1,12 +1,15 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 use {
-    super::registers,
+    super::{dma, registers},
     conquer_once::spin::Lazy,
     core::ops::{Index, IndexMut},
-    page_box::PageBox,
+    page_box::DmaBox,
     spinning_top::Spinlock,
-    x86_64::PhysAddr,
+    x86_64::{
+        structures::paging::{PageSize, Size4KiB},
+        PhysAddr,
+    },
 };
 
 static DCBAA: Lazy<Spinlock<DeviceContextBaseAddressArray>> =
@@ -21,11 +24,16 @@ pub(crate) fn register(port_id: usize, a: PhysAddr) {
 }
 
 pub(crate) struct DeviceContextBaseAddressArray {
-    arr: PageBox<[PhysAddr]>,
+    arr: DmaBox<[PhysAddr]>,
 }
 impl DeviceContextBaseAddressArray {
     fn new() -> Self {
-        let arr = PageBox::new_slice(PhysAddr::zero(), Self::num_of_slots());
+        // The array must not cross a page boundary.
+        let c = dma::constraints().boundary(Size4KiB::SIZE);
+
+        let arr = DmaBox::new_slice(PhysAddr::zero(), Self::num_of_slots(), &c);
+        let arr = arr.expect("Failed to allocate the Device Context Base Address Array.");
+
         Self { arr }
     }
 
@@ -0,0 +1,46 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use {
+    super::registers,
+    conquer_once::spin::Lazy,
+    page_box::{DmaConstraints, DmaPool, PoolBox, PoolStats},
+};
+
+/// The pool for the small structures and transfer buffers, such as contexts and HID reports.
+static POOL: Lazy<DmaPool> = Lazy::new(|| DmaPool::new(constraints()));
+
+pub(crate) fn alloc<T>(x: T) -> PoolBox<'static, T> {
+    POOL.alloc(x).expect("Failed to allocate a DMA buffer.")
+}
+
+pub(crate) fn alloc_slice<T: Clone>(x: T, num_of_elements: usize) -> PoolBox<'static, [T]> {
+    POOL.alloc_slice(x, num_of_elements)
+        .expect("Failed to allocate a DMA buffer.")
+}
+
+pub(crate) fn pool_stats() -> PoolStats {
+    POOL.stats()
+}
+
+/// Returns the constraints which the xHCI data structures placed on memory must satisfy.
+///
+/// The structures must not cross a 64 KiB boundary, and they must be placed below 4 GiB if the xHC
+/// does not support the 64-bit addressing.
+pub(crate) fn constraints() -> DmaConstraints {
+    let c = DmaConstraints::new().align(64).boundary(0x1_0000);
+
+    if supports_64bit_addressing() {
+        c
+    } else {
+        c.below_4gib()
+    }
+}
+
+pub(crate) fn supports_64bit_addressing() -> bool {
+    registers::handle(|r| {
+        r.capability
+            .hccparams1
+            .read_volatile()
+            .addressing_capability()
+    })
+}
@@ -3,6 +3,7 @@
 pub(crate) mod context;
 pub(crate) mod dcbaa;
 pub(crate) mod descriptor;
+pub(crate) mod dma;
 pub(super) mod extended_capabilities;
 pub(super) mod registers;
 pub(crate) mod ring;
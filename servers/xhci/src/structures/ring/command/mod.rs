@@ -2,8 +2,8 @@
 
 use {
     super::CycleBit,
-    crate::registers,
-    page_box::PageBox,
+    crate::{registers, structures::dma},
+    page_box::DmaBox,
     trb::Link,
     x86_64::{
         structures::paging::{PageSize, Size4KiB},
@@ -52,14 +52,15 @@ impl Default for Ring {
 }
 
 struct Raw {
-    raw: PageBox<[[u32; 4]]>,
+    raw: DmaBox<[[u32; 4]]>,
     enq_p: usize,
     c: CycleBit,
 }
 impl Raw {
     fn new() -> Self {
         Self {
-            raw: PageBox::new_slice([0; 4], NUM_OF_TRBS),
+            raw: DmaBox::new_slice([0; 4], NUM_OF_TRBS, &dma::constraints())
+                .expect("Failed to allocate the Command Ring."),
             enq_p: 0,
             c: CycleBit::new(true),
         }
@@ -2,7 +2,11 @@
 
 use {
     super::CycleBit,
-    crate::{exchanger::receiver, port, structures::registers},
+    crate::{
+        exchanger::receiver,
+        interrupt, port,
+        structures::{dma, registers},
+    },
     alloc::vec::Vec,
     bit_field::BitField,
     core::{
@@ -12,7 +16,7 @@ use {
     },
     futures_util::{stream::Stream, StreamExt},
     log::{debug, info, warn},
-    page_box::PageBox,
+    page_box::DmaBox,
     segment_table::SegmentTable,
     x86_64::{
         structures::paging::{PageSize, Size4KiB},
@@ -93,15 +97,30 @@ impl Ring {
 impl Stream for Ring {
     type Item = event::Allowed;
 
-    fn poll_next(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        Pin::into_inner(self)
-            .try_dequeue()
-            .map_or_else(|| Poll::Pending, |trb| Poll::Ready(Some(trb)))
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let ring = Pin::into_inner(self);
+
+        if let Some(trb) = ring.try_dequeue() {
+            return Poll::Ready(Some(trb));
+        }
+
+        if interrupt::enabled() {
+            // The xHC does not interrupt again until the dequeue pointer is updated.
+            ring.raw.update_deq_p_with_xhci();
+            interrupt::register_event_waker(cx.waker());
+
+            // An event may arrive before the waker is registered.
+            if let Some(trb) = ring.try_dequeue() {
+                return Poll::Ready(Some(trb));
+            }
+        }
+
+        Poll::Pending
     }
 }
 
 struct Raw {
-    rings: Vec<PageBox<[[u32; 4]]>>,
+    rings: Vec<DmaBox<[[u32; 4]]>>,
     c: CycleBit,
     deq_p_seg: usize,
     deq_p_trb: usize,
@@ -117,10 +136,11 @@ impl Raw {
         }
     }
 
-    fn new_rings() -> Vec<PageBox<[[u32; 4]]>> {
+    fn new_rings() -> Vec<DmaBox<[[u32; 4]]>> {
         let mut v = Vec::new();
         for _ in 0..Self::max_num_of_erst() {
-            v.push(PageBox::new_slice([0; 4], MAX_NUM_OF_TRB_IN_QUEUE.into()));
+            let r = DmaBox::new_slice([0; 4], MAX_NUM_OF_TRB_IN_QUEUE.into(), &dma::constraints());
+            v.push(r.expect("Failed to allocate an Event Ring segment."));
         }
 
         v
@@ -193,6 +213,7 @@ impl Raw {
                 .erdp
                 .update_volatile(|r| {
                     r.set_event_ring_dequeue_pointer(self.next_trb_addr().as_u64());
+                    r.clear_event_handler_busy();
                 });
         });
     }
@@ -202,7 +223,7 @@ impl Raw {
     }
 
     fn head_addrs(&self) -> Vec<PhysAddr> {
-        self.rings.iter().map(PageBox::phys_addr).collect()
+        self.rings.iter().map(DmaBox::phys_addr).collect()
     }
 }
 
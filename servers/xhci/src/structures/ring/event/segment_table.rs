@@ -1,19 +1,22 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 use {
+    crate::structures::dma,
     core::{
         ops::{Index, IndexMut},
         slice,
     },
-    page_box::PageBox,
+    page_box::DmaBox,
     x86_64::PhysAddr,
 };
 
 #[derive(Debug)]
-pub struct SegmentTable(PageBox<[Entry]>);
+pub struct SegmentTable(DmaBox<[Entry]>);
 impl SegmentTable {
     pub fn new(len: usize) -> Self {
-        Self(PageBox::new_slice(Entry::null(), len))
+        let t = DmaBox::new_slice(Entry::null(), len, &dma::constraints());
+
+        Self(t.expect("Failed to allocate the Event Ring Segment Table."))
     }
 
     pub fn phys_addr(&self) -> PhysAddr {
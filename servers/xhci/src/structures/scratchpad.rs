@@ -1,8 +1,14 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 use {
-    super::dcbaa, crate::registers, alloc::vec::Vec, conquer_once::spin::OnceCell,
-    core::convert::TryInto, os_units::Bytes, page_box::PageBox, x86_64::PhysAddr,
+    super::{dcbaa, dma},
+    crate::registers,
+    alloc::vec::Vec,
+    conquer_once::spin::OnceCell,
+    core::convert::TryInto,
+    os_units::Bytes,
+    page_box::DmaBox,
+    x86_64::PhysAddr,
 };
 
 static SCRATCHPAD: OnceCell<Scratchpad> = OnceCell::uninit();
@@ -22,15 +28,18 @@ fn init_static() {
 }
 
 struct Scratchpad {
-    arr: PageBox<[PhysAddr]>,
-    bufs: Vec<PageBox<[u8]>>,
+    arr: DmaBox<[PhysAddr]>,
+    bufs: Vec<DmaBox<[u8]>>,
 }
 impl Scratchpad {
     fn new() -> Self {
         let len: usize = Self::num_of_buffers().try_into().unwrap();
 
+        let arr = DmaBox::new_slice(PhysAddr::zero(), len, &dma::constraints());
+        let arr = arr.expect("Failed to allocate the Scratchpad Buffer Array.");
+
         Self {
-            arr: PageBox::new_slice(PhysAddr::zero(), len),
+            arr,
             bufs: Vec::new(),
         }
     }
@@ -49,18 +58,25 @@ impl Scratchpad {
     }
 
     fn allocate_buffers(&mut self) {
+        let page_size = Self::page_size();
+        let page_size_u64: u64 = page_size.as_usize().try_into().unwrap();
+
+        // Each buffer must be page-aligned, and it must not cross a page boundary.
+        let c = dma::constraints()
+            .align(page_size_u64)
+            .boundary(page_size_u64);
+
         for _ in 0..Self::num_of_buffers() {
-            // Allocate the double size of memory, then register the aligned address with the
-            // array.
-            let b = PageBox::new_slice(0, Self::page_size().as_usize() * 2);
+            let b = DmaBox::new_slice(0, page_size.as_usize(), &c);
+            let b = b.expect("Failed to allocate a scratchpad buffer.");
+
             self.bufs.push(b);
         }
     }
 
     fn write_buffer_addresses(&mut self) {
-        let page_size: u64 = Self::page_size().as_usize().try_into().unwrap();
         for (x, buf) in self.arr.iter_mut().zip(self.bufs.iter()) {
-            *x = buf.phys_addr().align_up(page_size);
+            *x = buf.phys_addr();
         }
     }
 